@@ -0,0 +1,81 @@
+//! CRC-32 checksums, as used by UEFI table headers, GPT partition tables and
+//! capsules.
+//!
+//! The firmware's `CalculateCrc32` boot service can only checksum a buffer
+//! which is entirely in memory. The `Crc32` accumulator of this module instead
+//! computes the same checksum incrementally, e.g. over a large file which is
+//! read in chunks, and remains usable after exiting boot services.
+
+use crate::table::boot::BootServices;
+use crate::Result;
+
+/// CRC-32 of the 16 possible values of a nibble, for the reflected
+/// polynomial 0xEDB88320
+///
+/// Processing data a nibble at a time keeps the table small, which matters
+/// more than raw speed for the sizes of data that UEFI applications hash.
+const NIBBLE_TABLE: [u32; 16] = [
+    0x0000_0000,
+    0x1DB7_1064,
+    0x3B6E_20C8,
+    0x26D9_30AC,
+    0x76DC_4190,
+    0x6B6B_51F4,
+    0x4DB2_6158,
+    0x5005_713C,
+    0xEDB8_8320,
+    0xF00F_9344,
+    0xD6D6_A3E8,
+    0xCB61_B38C,
+    0x9B64_C2B0,
+    0x86D3_D2D4,
+    0xA00A_E278,
+    0xBDBD_F21C,
+];
+
+/// Incremental computation of the CRC-32 checksum used by UEFI
+///
+/// Feeding data to `update()` in several chunks gives the same result as
+/// feeding it all at once, which is also the result of the firmware's
+/// `CalculateCrc32` boot service.
+#[derive(Debug, Clone)]
+pub struct Crc32 {
+    state: u32,
+}
+
+impl Crc32 {
+    /// Start a new checksum computation
+    pub fn new() -> Self {
+        Self { state: !0 }
+    }
+
+    /// Compute the checksum of `data` in one go, using the firmware's
+    /// implementation
+    ///
+    /// This is mostly useful to validate the results of this module against
+    /// the firmware, as both compute the same checksum.
+    pub fn from_firmware(bt: &BootServices, data: &[u8]) -> Result<u32> {
+        bt.calculate_crc32(data)
+    }
+
+    /// Add `data` to the checksummed data
+    pub fn update(&mut self, data: &[u8]) {
+        let mut crc = self.state;
+        for &byte in data {
+            crc = NIBBLE_TABLE[((crc ^ u32::from(byte)) & 0xF) as usize] ^ (crc >> 4);
+            crc = NIBBLE_TABLE[((crc ^ u32::from(byte >> 4)) & 0xF) as usize] ^ (crc >> 4);
+        }
+        self.state = crc;
+    }
+
+    /// Returns the checksum of all the data which was added
+    pub fn finalize(self) -> u32 {
+        !self.state
+    }
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -28,6 +28,8 @@ impl TryFrom<char> for Char8 {
 }
 
 impl Into<char> for Char8 {
+    /// Latin-1 code points are the first 256 Unicode code points, so e.g.
+    /// 0xE9 is decoded as U+00E9 (`é`).
     fn into(self) -> char {
         self.0 as char
     }
@@ -78,9 +80,13 @@ impl TryFrom<char> for Char16 {
     }
 }
 
+/// Lone surrogate code units, which firmware occasionally stores in UCS-2
+/// strings, are not characters and are decoded as U+FFFD.
 impl Into<char> for Char16 {
     fn into(self) -> char {
-        u32::from(self.0).try_into().unwrap()
+        u32::from(self.0)
+            .try_into()
+            .unwrap_or(core::char::REPLACEMENT_CHARACTER)
     }
 }
 
@@ -0,0 +1,163 @@
+//! Encoding of Rust strings into UCS-2 buffers, and back
+//!
+//! The console output translates line feeds into the CR LF sequences expected
+//! by UEFI consoles, but other targets, such as text files or serial links,
+//! may expect different line endings, which these functions can produce.
+
+use super::chars::Char16;
+use super::strs::CStr16;
+use core::convert::TryFrom;
+use core::str;
+
+/// Errors which can occur when encoding a Rust string into an UEFI string
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StrEncodeError {
+    /// The character at this position (in chars) cannot be represented
+    InvalidChar(usize),
+
+    /// A null character was encountered at this position (in chars)
+    InteriorNul(usize),
+
+    /// The output buffer is too small for the encoded string
+    BufferOverflow,
+}
+
+/// Errors which can occur when decoding an UEFI string into a Rust string
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum StrDecodeError {
+    /// The output buffer is too small for the first character of the string
+    BufferOverflow,
+}
+
+/// Translation of line endings during encoding or decoding
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum LineEnding {
+    /// Line feeds are encoded as CR LF, as expected by UEFI consoles, unless
+    /// they already follow a carriage return
+    CrLf,
+    /// CR LF sequences are encoded as a single line feed
+    Lf,
+    /// Line endings are encoded as they are
+    Verbatim,
+}
+
+/// Options of `encode_with_options()`
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub struct EncodeOptions {
+    /// Translation of line endings
+    pub line_ending: LineEnding,
+    /// Character encoded in place of those which UCS-2 cannot represent, or
+    /// `None` to fail on them
+    pub replacement: Option<Char16>,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        EncodeOptions {
+            line_ending: LineEnding::CrLf,
+            replacement: None,
+        }
+    }
+}
+
+/// Encodes a string into UCS-2, with the default options
+///
+/// Line feeds are encoded as CR LF, and characters outside of the Basic
+/// Multilingual Plane are rejected. Returns the number of characters which
+/// were written to `buffer`, no null terminator is added.
+pub fn encode(input: &str, buffer: &mut [u16]) -> Result<usize, StrEncodeError> {
+    encode_with_options(input, buffer, EncodeOptions::default())
+}
+
+/// Encodes a string into UCS-2, translating line endings and replacing
+/// unsupported characters as specified by `options`
+///
+/// Returns the number of characters which were written to `buffer`, no null
+/// terminator is added.
+pub fn encode_with_options(
+    input: &str,
+    buffer: &mut [u16],
+    options: EncodeOptions,
+) -> Result<usize, StrEncodeError> {
+    let mut len = 0;
+    let mut push = |code: u16| {
+        let slot = buffer.get_mut(len).ok_or(StrEncodeError::BufferOverflow)?;
+        *slot = code;
+        len += 1;
+        Ok(())
+    };
+
+    let mut chars = input.chars().enumerate().peekable();
+    let mut previous = None;
+    while let Some((pos, c)) = chars.next() {
+        match (c, options.line_ending) {
+            ('\n', LineEnding::CrLf) if previous != Some('\r') => {
+                push(u16::from(b'\r'))?;
+                push(u16::from(b'\n'))?;
+            }
+            ('\r', LineEnding::Lf) if chars.peek().map(|&(_, next)| next) == Some('\n') => {}
+            (c, _) => match (Char16::try_from(c), options.replacement) {
+                (Ok(c), _) | (Err(_), Some(c)) => push(c.into())?,
+                (Err(_), None) => return Err(StrEncodeError::InvalidChar(pos)),
+            },
+        }
+        previous = Some(c);
+    }
+    Ok(len)
+}
+
+/// Decodes an UEFI string into UTF-8, translating line endings
+///
+/// With `LineEnding::Lf`, CR LF sequences are decoded as a single line feed,
+/// the other line endings leave the string untouched. Lone UTF-16 surrogates,
+/// which firmware strings may contain, are decoded as U+FFFD like in
+/// `CStr16::chars()`.
+///
+/// As much of the string as fits is decoded into `buffer`, and returned
+/// along with the rest of the string, if any, which can be decoded next.
+/// Characters are never split, and neither are CR LF sequences which are
+/// translated.
+pub fn decode<'buf, 'inp>(
+    input: &'inp CStr16,
+    buffer: &'buf mut [u8],
+    line_ending: LineEnding,
+) -> Result<(&'buf str, Option<&'inp CStr16>), StrDecodeError> {
+    let codes = input.to_u16_slice_with_nul();
+    let text = &codes[..codes.len() - 1];
+    let (cr, lf) = (u16::from(b'\r'), u16::from(b'\n'));
+
+    // Input code units consumed, and output bytes written
+    let (mut pos, mut len) = (0, 0);
+    while pos < text.len() {
+        let (c, consumed) = match (text[pos], text.get(pos + 1)) {
+            (code, Some(&next)) if code == cr && next == lf && line_ending == LineEnding::Lf => {
+                ('\n', 2)
+            }
+            (code, _) => {
+                let c =
+                    char::try_from(u32::from(code)).unwrap_or(core::char::REPLACEMENT_CHARACTER);
+                (c, 1)
+            }
+        };
+        let mut utf8 = [0; 4];
+        let bytes = c.encode_utf8(&mut utf8).as_bytes();
+        match buffer.get_mut(len..len + bytes.len()) {
+            Some(slot) => slot.copy_from_slice(bytes),
+            None => break,
+        }
+        len += bytes.len();
+        pos += consumed;
+    }
+
+    if pos == 0 && !text.is_empty() {
+        return Err(StrDecodeError::BufferOverflow);
+    }
+    let rest = if pos == text.len() {
+        None
+    } else {
+        Some(unsafe { CStr16::from_u16_with_nul_unchecked(&codes[pos..]) })
+    };
+    // Only whole characters were written to the buffer
+    let decoded = unsafe { str::from_utf8_unchecked(&buffer[..len]) };
+    Ok((decoded, rest))
+}
@@ -5,7 +5,9 @@
 use core::ffi::c_void;
 
 /// Opaque handle to an UEFI entity (protocol, image...)
-#[derive(Clone, Copy)]
+///
+/// Handles compare equal if they designate the same entity.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
 #[repr(transparent)]
 pub struct Handle(*mut c_void);
 
@@ -44,4 +46,17 @@ pub use self::chars::{Char16, Char8};
 mod enums;
 
 mod strs;
-pub use self::strs::{CStr16, CStr8};
+pub use self::strs::{CStr16, CStr8, FromSliceWithNulError, Ucs2Chars, Ucs2Str};
+
+mod utf16;
+pub use self::utf16::{Utf16Chars, Utf16Str};
+
+mod encode;
+pub use self::encode::{
+    decode, encode, encode_with_options, EncodeOptions, LineEnding, StrDecodeError, StrEncodeError,
+};
+
+#[cfg(feature = "exts")]
+mod owned_strs;
+#[cfg(feature = "exts")]
+pub use self::owned_strs::{CString16, CString8};
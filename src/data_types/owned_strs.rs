@@ -0,0 +1,227 @@
+use super::chars::{Char16, Char8, NUL_16, NUL_8};
+use super::encode::StrEncodeError;
+use super::strs::{CStr16, CStr8};
+use alloc_api::vec::Vec;
+use core::convert::{TryFrom, TryInto};
+use core::fmt;
+use core::iter::FromIterator;
+use core::ops::Deref;
+
+/// An owned UCS-2 null-terminated string
+///
+/// This is the owned counterpart of `CStr16`, which it dereferences to.
+#[derive(Clone, Eq, PartialEq)]
+pub struct CString16(Vec<Char16>);
+
+impl CString16 {
+    /// Creates an empty string
+    pub fn new() -> Self {
+        CString16(alloc_api::vec![NUL_16])
+    }
+
+    /// Appends a character to the end of this string
+    ///
+    /// On failure, the string is left unchanged.
+    pub fn push(&mut self, c: char) -> Result<(), StrEncodeError> {
+        let c = encode_char(c, 0)?;
+        self.0.insert(self.0.len() - 1, c);
+        Ok(())
+    }
+
+    /// Appends a string slice to the end of this string
+    ///
+    /// On failure, the string is left unchanged, and the error reports the
+    /// position of the faulty character in `s`.
+    pub fn push_str(&mut self, s: &str) -> Result<(), StrEncodeError> {
+        let len = self.0.len() - 1;
+        self.0.pop();
+        for (pos, c) in s.chars().enumerate() {
+            match encode_char(c, pos) {
+                Ok(c) => self.0.push(c),
+                Err(e) => {
+                    self.0.truncate(len);
+                    self.0.push(NUL_16);
+                    return Err(e);
+                }
+            }
+        }
+        self.0.push(NUL_16);
+        Ok(())
+    }
+
+    /// Builds a string from a sequence of characters
+    ///
+    /// The error reports the position of the first character which UCS-2
+    /// cannot represent, or of the first null character. This is the
+    /// fallible counterpart of `collect()`, which panics on these errors.
+    pub fn try_from_chars<I: IntoIterator<Item = char>>(chars: I) -> Result<Self, StrEncodeError> {
+        let mut codes: Vec<Char16> = chars
+            .into_iter()
+            .enumerate()
+            .map(|(pos, c)| encode_char(c, pos))
+            .collect::<Result<_, _>>()?;
+        codes.push(NUL_16);
+        Ok(CString16(codes))
+    }
+}
+
+/// Encode a character found at position `pos` of a string
+fn encode_char(c: char, pos: usize) -> Result<Char16, StrEncodeError> {
+    if c == '\0' {
+        return Err(StrEncodeError::InteriorNul(pos));
+    }
+    c.try_into().map_err(|_| StrEncodeError::InvalidChar(pos))
+}
+
+impl Default for CString16 {
+    fn default() -> Self {
+        CString16::new()
+    }
+}
+
+impl TryFrom<&str> for CString16 {
+    type Error = StrEncodeError;
+
+    fn try_from(s: &str) -> Result<Self, StrEncodeError> {
+        let mut string = CString16::new();
+        string.push_str(s)?;
+        Ok(string)
+    }
+}
+
+/// Collects characters into a string
+///
+/// # Panics
+///
+/// Panics if UCS-2 cannot represent one of the characters, e.g. because it is
+/// outside of the Basic Multilingual Plane, or if one of them is null. Use
+/// `CString16::try_from_chars()` to handle these errors instead.
+impl FromIterator<char> for CString16 {
+    fn from_iter<I: IntoIterator<Item = char>>(chars: I) -> Self {
+        match CString16::try_from_chars(chars) {
+            Ok(string) => string,
+            Err(e) => panic!("Cannot collect characters into a CString16: {:?}", e),
+        }
+    }
+}
+
+impl From<&CStr16> for CString16 {
+    fn from(s: &CStr16) -> Self {
+        CString16(s.iter().copied().chain(Some(NUL_16)).collect())
+    }
+}
+
+impl Deref for CString16 {
+    type Target = CStr16;
+
+    fn deref(&self) -> &CStr16 {
+        let codes = unsafe { &*(self.0.as_slice() as *const [Char16] as *const [u16]) };
+        unsafe { CStr16::from_u16_with_nul_unchecked(codes) }
+    }
+}
+
+impl fmt::Debug for CString16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for CString16 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
+
+/// An owned Latin-1 null-terminated string
+///
+/// This is the owned counterpart of `CStr8`, which it dereferences to.
+#[derive(Clone, Eq, PartialEq)]
+pub struct CString8(Vec<Char8>);
+
+impl CString8 {
+    /// Creates an empty string
+    pub fn new() -> Self {
+        CString8(alloc_api::vec![NUL_8])
+    }
+
+    /// Appends a character to the end of this string
+    ///
+    /// On failure, the string is left unchanged.
+    pub fn push(&mut self, c: char) -> Result<(), StrEncodeError> {
+        let c = encode_latin1_char(c, 0)?;
+        self.0.insert(self.0.len() - 1, c);
+        Ok(())
+    }
+
+    /// Appends a string slice to the end of this string
+    ///
+    /// On failure, the string is left unchanged, and the error reports the
+    /// position of the faulty character in `s`.
+    pub fn push_str(&mut self, s: &str) -> Result<(), StrEncodeError> {
+        let len = self.0.len() - 1;
+        self.0.pop();
+        for (pos, c) in s.chars().enumerate() {
+            match encode_latin1_char(c, pos) {
+                Ok(c) => self.0.push(c),
+                Err(e) => {
+                    self.0.truncate(len);
+                    self.0.push(NUL_8);
+                    return Err(e);
+                }
+            }
+        }
+        self.0.push(NUL_8);
+        Ok(())
+    }
+}
+
+/// Encode a character found at position `pos` of a string into Latin-1
+fn encode_latin1_char(c: char, pos: usize) -> Result<Char8, StrEncodeError> {
+    if c == '\0' {
+        return Err(StrEncodeError::InteriorNul(pos));
+    }
+    c.try_into().map_err(|_| StrEncodeError::InvalidChar(pos))
+}
+
+impl Default for CString8 {
+    fn default() -> Self {
+        CString8::new()
+    }
+}
+
+impl TryFrom<&str> for CString8 {
+    type Error = StrEncodeError;
+
+    fn try_from(s: &str) -> Result<Self, StrEncodeError> {
+        let mut string = CString8::new();
+        string.push_str(s)?;
+        Ok(string)
+    }
+}
+
+impl From<&CStr8> for CString8 {
+    fn from(s: &CStr8) -> Self {
+        CString8(s.to_bytes_with_nul().iter().map(|&b| b.into()).collect())
+    }
+}
+
+impl Deref for CString8 {
+    type Target = CStr8;
+
+    fn deref(&self) -> &CStr8 {
+        let bytes = unsafe { &*(self.0.as_slice() as *const [Char8] as *const [u8]) };
+        unsafe { CStr8::from_bytes_with_nul_unchecked(bytes) }
+    }
+}
+
+impl fmt::Debug for CString8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&**self, f)
+    }
+}
+
+impl fmt::Display for CString8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&**self, f)
+    }
+}
@@ -1,11 +1,14 @@
 use super::chars::{Char16, Char8, NUL_16, NUL_8};
-use core::convert::TryInto;
+use crate::proto::string::unicode_collation::UnicodeCollation;
+use core::cmp::Ordering;
+use core::convert::{TryFrom, TryInto};
 use core::fmt;
 use core::iter::Iterator;
 use core::result::Result;
 use core::slice;
 
 /// Errors which can occur during checked [uN] -> CStrN conversions
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
 pub enum FromSliceWithNulError {
     /// An invalid character was encountered before the end of the slice
     InvalidChar(usize),
@@ -41,6 +44,20 @@ impl CStr8 {
         Self::from_bytes_with_nul_unchecked(slice::from_raw_parts(ptr, len + 1))
     }
 
+    /// Wraps a raw UEFI string which may be missing, as signaled by a null
+    /// pointer, with a safe C string wrapper
+    ///
+    /// # Safety
+    ///
+    /// If `ptr` is not null, the requirements of `from_ptr` apply.
+    pub unsafe fn from_ptr_opt<'ptr>(ptr: *const Char8) -> Option<&'ptr Self> {
+        if ptr.is_null() {
+            None
+        } else {
+            Some(Self::from_ptr(ptr))
+        }
+    }
+
     /// Creates a C string wrapper from bytes
     pub fn from_bytes_with_nul(chars: &[u8]) -> Result<&Self, FromSliceWithNulError> {
         let nul_pos = chars.iter().position(|&c| c == 0);
@@ -79,6 +96,59 @@ impl CStr8 {
     pub fn to_bytes_with_nul(&self) -> &[u8] {
         unsafe { &*(&self.0 as *const [Char8] as *const [u8]) }
     }
+
+    /// Truth that this C string starts with the characters of `prefix`
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        starts_with_code_points(self.code_points(), prefix.chars())
+    }
+
+    /// Truth that this C string ends with the characters of `suffix`
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        starts_with_code_points(self.code_points().rev(), suffix.chars().rev())
+    }
+
+    /// Splits this C string on a separator character, which is not included
+    /// in the returned pieces
+    ///
+    /// Separators which are not Latin-1 characters, including the null
+    /// terminator, never match.
+    pub fn split_on(&self, sep: char) -> impl Iterator<Item = &[u8]> + '_ {
+        let sep = u8::try_from(u32::from(sep)).ok().filter(|&sep| sep != 0);
+        self.to_bytes().split(move |&c| Some(c) == sep)
+    }
+
+    /// Returns an iterator over the characters of this C string, excluding
+    /// the null terminator
+    pub fn chars(&self) -> impl DoubleEndedIterator<Item = char> + '_ {
+        self.0[..self.0.len() - 1].iter().map(|&c| c.into())
+    }
+
+    fn code_points(&self) -> impl DoubleEndedIterator<Item = u32> + '_ {
+        self.to_bytes().iter().map(|&c| u32::from(c))
+    }
+}
+
+impl PartialEq for CStr8 {
+    fn eq(&self, other: &CStr8) -> bool {
+        self.to_bytes() == other.to_bytes()
+    }
+}
+
+impl Eq for CStr8 {}
+
+impl fmt::Debug for CStr8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "CStr8({:?})", &self.0)
+    }
+}
+
+impl fmt::Display for CStr8 {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.chars() {
+            <char as fmt::Display>::fmt(&c, f)?;
+        }
+        Ok(())
+    }
 }
 
 /// An UCS-2 null-terminated string
@@ -105,6 +175,20 @@ impl CStr16 {
         Self::from_u16_with_nul_unchecked(slice::from_raw_parts(ptr, len + 1))
     }
 
+    /// Wraps a raw UEFI string which may be missing, as signaled by a null
+    /// pointer, with a safe C string wrapper
+    ///
+    /// # Safety
+    ///
+    /// If `ptr` is not null, the requirements of `from_ptr` apply.
+    pub unsafe fn from_ptr_opt<'ptr>(ptr: *const Char16) -> Option<&'ptr Self> {
+        if ptr.is_null() {
+            None
+        } else {
+            Some(Self::from_ptr(ptr))
+        }
+    }
+
     /// Creates a C string wrapper from a u16 slice
     ///
     /// Since not every u16 value is a valid UCS-2 code point, this function
@@ -154,6 +238,61 @@ impl CStr16 {
         unsafe { &*(&self.0 as *const [Char16] as *const [u16]) }
     }
 
+    /// Compares this C string with another one using the firmware's collation
+    /// rules, which are case-insensitive.
+    ///
+    /// This is the correct way to compare file names from a FAT file system,
+    /// where `BOOTX64.EFI` and `bootx64.efi` name the same file. The
+    /// `PartialEq` implementation, on the other hand, compares code points.
+    pub fn eq_collated(&self, other: &CStr16, collation: &UnicodeCollation) -> bool {
+        collation.stri_coll(self, other) == Ordering::Equal
+    }
+
+    /// Truth that this C string starts with the characters of `prefix`
+    pub fn starts_with(&self, prefix: &str) -> bool {
+        starts_with_code_points(self.code_points(), prefix.chars())
+    }
+
+    /// Truth that this C string ends with the characters of `suffix`
+    pub fn ends_with(&self, suffix: &str) -> bool {
+        starts_with_code_points(self.code_points().rev(), suffix.chars().rev())
+    }
+
+    /// Splits this C string on a separator character, which is not included
+    /// in the returned pieces
+    ///
+    /// Separators which are not UCS-2 characters, including the null
+    /// terminator, never match.
+    pub fn split_on(&self, sep: char) -> impl Iterator<Item = &[u16]> + '_ {
+        let sep = u16::try_from(u32::from(sep)).ok().filter(|&sep| sep != 0);
+        self.to_u16_slice().split(move |&c| Some(c) == sep)
+    }
+
+    fn code_points(&self) -> impl DoubleEndedIterator<Item = u32> + '_ {
+        self.to_u16_slice().iter().map(|&c| u32::from(c))
+    }
+
+    /// Returns the number of console columns which this string occupies when
+    /// displayed on a single line
+    ///
+    /// Full-width characters, such as CJK ideographs, occupy two columns,
+    /// and the other characters one.
+    pub fn console_width(&self) -> usize {
+        self.to_u16_slice()
+            .iter()
+            .map(|&c| if is_wide(c) { 2 } else { 1 })
+            .sum()
+    }
+
+    /// Returns an iterator over the characters of this C string
+    ///
+    /// Strings from the firmware or from variables may contain lone UTF-16
+    /// surrogates, which are not valid characters: these are replaced by
+    /// U+FFFD.
+    pub fn chars(&self) -> impl DoubleEndedIterator<Item = char> + '_ {
+        self.0[..self.0.len() - 1].iter().map(|&c| c.into())
+    }
+
     /// Returns an iterator over this C string
     pub fn iter(&self) -> CStr16Iter {
         CStr16Iter {
@@ -183,6 +322,16 @@ impl<'a> Iterator for CStr16Iter<'a> {
     }
 }
 
+// Comparisons are code point exact, use `CStr16::eq_collated` for the
+// case-insensitive comparisons required by file systems.
+impl PartialEq for CStr16 {
+    fn eq(&self, other: &CStr16) -> bool {
+        self.to_u16_slice() == other.to_u16_slice()
+    }
+}
+
+impl Eq for CStr16 {}
+
 impl fmt::Debug for CStr16 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "CStr16({:?})", &self.0)
@@ -197,3 +346,141 @@ impl fmt::Display for CStr16 {
         Ok(())
     }
 }
+
+/// Ranges of characters which are displayed over two console columns
+///
+/// These are the wide and full-width characters of the Unicode East Asian
+/// Width property which UCS-2 can represent, grouped by block.
+const WIDE_RANGES: [(u16, u16); 13] = [
+    (0x1100, 0x115F), // Hangul Jamo initial consonants
+    (0x2E80, 0x303E), // CJK radicals and symbols
+    (0x3041, 0x33FF), // Kana, Bopomofo, Hangul Jamo and CJK compatibility
+    (0x3400, 0x4DBF), // CJK unified ideographs extension A
+    (0x4E00, 0x9FFF), // CJK unified ideographs
+    (0xA000, 0xA4CF), // Yi syllables and radicals
+    (0xA960, 0xA97F), // Hangul Jamo extended A
+    (0xAC00, 0xD7A3), // Hangul syllables
+    (0xF900, 0xFAFF), // CJK compatibility ideographs
+    (0xFE10, 0xFE19), // Vertical forms
+    (0xFE30, 0xFE6F), // CJK compatibility forms and small form variants
+    (0xFF00, 0xFF60), // Full-width forms
+    (0xFFE0, 0xFFE6), // Full-width signs
+];
+
+/// Truth that a character occupies two console columns
+fn is_wide(c: u16) -> bool {
+    WIDE_RANGES
+        .iter()
+        .any(|&(first, last)| first <= c && c <= last)
+}
+
+/// Truth that the `prefix` characters are the first code points of `codes`
+fn starts_with_code_points(
+    mut codes: impl Iterator<Item = u32>,
+    prefix: impl Iterator<Item = char>,
+) -> bool {
+    prefix
+        .map(u32::from)
+        .all(|prefix_code| codes.next() == Some(prefix_code))
+}
+
+/// An UCS-2 string which is not null-terminated
+///
+/// This is a borrowed view over a length-delimited UCS-2 buffer, such as those
+/// which some firmware interfaces return, which can be used without copying
+/// the buffer in order to add a null terminator.
+#[repr(transparent)]
+pub struct Ucs2Str([Char16]);
+
+impl Ucs2Str {
+    /// Creates an UCS-2 string view from a u16 slice
+    ///
+    /// The whole slice is part of the string. Null characters are therefore
+    /// rejected, and must be removed from the slice beforehand if the buffer
+    /// was null-terminated.
+    pub fn from_u16(codes: &[u16]) -> Result<&Self, FromSliceWithNulError> {
+        for (pos, &code) in codes.iter().enumerate() {
+            match code.try_into() {
+                Ok(NUL_16) => return Err(FromSliceWithNulError::InteriorNul(pos)),
+                Err(_) => return Err(FromSliceWithNulError::InvalidChar(pos)),
+                _ => {}
+            }
+        }
+        Ok(unsafe { Self::from_u16_unchecked(codes) })
+    }
+
+    /// Unsafely creates an UCS-2 string view from a u16 slice
+    ///
+    /// # Safety
+    ///
+    /// It's the callers responsability to ensure codes is a valid UCS-2
+    /// string, with no null characters.
+    pub unsafe fn from_u16_unchecked(codes: &[u16]) -> &Self {
+        &*(codes as *const [u16] as *const Self)
+    }
+
+    /// Returns the number of characters in this string
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Truth that this string is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Converts this string to a u16 slice
+    pub fn to_u16_slice(&self) -> &[u16] {
+        unsafe { &*(&self.0 as *const [Char16] as *const [u16]) }
+    }
+
+    /// Returns an iterator over the characters of this string
+    pub fn chars(&self) -> Ucs2Chars {
+        Ucs2Chars {
+            inner: self.0.iter(),
+        }
+    }
+}
+
+/// An iterator over the characters of an `Ucs2Str`.
+#[derive(Debug)]
+pub struct Ucs2Chars<'a> {
+    inner: slice::Iter<'a, Char16>,
+}
+
+impl<'a> Iterator for Ucs2Chars<'a> {
+    type Item = Char16;
+
+    fn next(&mut self) -> Option<Char16> {
+        self.inner.next().copied()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl<'a> ExactSizeIterator for Ucs2Chars<'a> {}
+
+impl PartialEq for Ucs2Str {
+    fn eq(&self, other: &Ucs2Str) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Ucs2Str {}
+
+impl fmt::Debug for Ucs2Str {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Ucs2Str({:?})", &self.0)
+    }
+}
+
+impl fmt::Display for Ucs2Str {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.chars() {
+            <Char16 as fmt::Display>::fmt(&c, f)?;
+        }
+        Ok(())
+    }
+}
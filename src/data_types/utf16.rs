@@ -0,0 +1,120 @@
+use super::strs::FromSliceWithNulError;
+use core::char::{decode_utf16, DecodeUtf16};
+use core::fmt;
+use core::iter::Copied;
+use core::result::Result;
+use core::slice;
+
+/// An UTF-16 string which is not null-terminated
+///
+/// Unlike `CStr16` and `Ucs2Str`, which are strictly UCS-2, this string may
+/// contain surrogate pairs, which encode the characters outside of the basic
+/// multilingual plane. Some firmware interfaces, such as those dealing with
+/// URLs, use this encoding.
+///
+/// Unpaired surrogates are rejected when creating the string, so that every
+/// string decodes to valid characters.
+#[repr(transparent)]
+pub struct Utf16Str([u16]);
+
+impl Utf16Str {
+    /// Creates an UTF-16 string view from a u16 slice
+    ///
+    /// The whole slice is part of the string. Null characters are therefore
+    /// rejected, and must be removed from the slice beforehand if the buffer
+    /// was null-terminated. Unpaired surrogates are reported as invalid
+    /// characters.
+    pub fn from_u16(codes: &[u16]) -> Result<&Self, FromSliceWithNulError> {
+        if let Some(pos) = codes.iter().position(|&code| code == 0) {
+            return Err(FromSliceWithNulError::InteriorNul(pos));
+        }
+        let mut pos = 0;
+        for c in decode_utf16(codes.iter().copied()) {
+            match c {
+                Ok(c) => pos += c.len_utf16(),
+                Err(_) => return Err(FromSliceWithNulError::InvalidChar(pos)),
+            }
+        }
+        Ok(unsafe { Self::from_u16_unchecked(codes) })
+    }
+
+    /// Unsafely creates an UTF-16 string view from a u16 slice
+    ///
+    /// # Safety
+    ///
+    /// It's the callers responsability to ensure codes is a valid UTF-16
+    /// string, with no null characters.
+    pub unsafe fn from_u16_unchecked(codes: &[u16]) -> &Self {
+        &*(codes as *const [u16] as *const Self)
+    }
+
+    /// Returns the number of UTF-16 code units in this string
+    ///
+    /// This is more than the number of characters if the string contains
+    /// surrogate pairs.
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    /// Truth that this string is empty
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Converts this string to a u16 slice
+    pub fn to_u16_slice(&self) -> &[u16] {
+        &self.0
+    }
+
+    /// Returns an iterator over the characters of this string, in which
+    /// surrogate pairs are decoded into a single character
+    pub fn chars(&self) -> Utf16Chars<'_> {
+        Utf16Chars {
+            inner: decode_utf16(self.0.iter().copied()),
+        }
+    }
+}
+
+/// An iterator over the characters of an `Utf16Str`.
+#[derive(Debug)]
+pub struct Utf16Chars<'a> {
+    inner: DecodeUtf16<Copied<slice::Iter<'a, u16>>>,
+}
+
+impl<'a> Iterator for Utf16Chars<'a> {
+    type Item = char;
+
+    fn next(&mut self) -> Option<char> {
+        // The string was validated on creation
+        self.inner
+            .next()
+            .map(|c| c.unwrap_or(core::char::REPLACEMENT_CHARACTER))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.inner.size_hint()
+    }
+}
+
+impl PartialEq for Utf16Str {
+    fn eq(&self, other: &Utf16Str) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl Eq for Utf16Str {}
+
+impl fmt::Debug for Utf16Str {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Utf16Str({:?})", &self.0)
+    }
+}
+
+impl fmt::Display for Utf16Str {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for c in self.chars() {
+            <char as fmt::Display>::fmt(&c, f)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,36 @@
+//! Well-known GUIDs.
+//!
+//! This module gathers the GUIDs defined by the UEFI specification which are
+//! not attached to a protocol, such as the GUIDs of file information types
+//! and of configuration tables. Protocol GUIDs are available through the
+//! `Identify` trait of each protocol, and the GUIDs below which belong to a
+//! type are aliases of its `Identify::GUID`.
+
+#![allow(clippy::unreadable_literal)]
+
+use crate::proto::media::file::{FileInfo, FileSystemInfo, FileSystemVolumeLabel};
+use crate::{Guid, Identify};
+
+pub use crate::table::cfg::{
+    ACPI2_GUID, ACPI_GUID, DEBUG_IMAGE_INFO_GUID, DXE_SERVICES_GUID, HAND_OFF_BLOCK_LIST_GUID,
+    LZMA_COMPRESS_GUID, MEMORY_STATUS_CODE_RECORD_GUID, MEMORY_TYPE_INFORMATION_GUID,
+    PROPERTIES_TABLE_GUID, SMBIOS3_GUID, SMBIOS_GUID, TIANO_COMPRESS_GUID,
+};
+
+/// Generic information about a file, see `FileInfo`.
+pub const FILE_INFO_GUID: Guid = FileInfo::GUID;
+
+/// Information about a file system volume, see `FileSystemInfo`.
+pub const FILE_SYSTEM_INFO_GUID: Guid = FileSystemInfo::GUID;
+
+/// Label of a file system volume, see `FileSystemVolumeLabel`.
+pub const FILE_SYSTEM_VOLUME_LABEL_GUID: Guid = FileSystemVolumeLabel::GUID;
+
+/// Vendor of the UEFI global variables, such as `BootOrder` or `Timeout`.
+pub const GLOBAL_VARIABLE_GUID: Guid = Guid::from_values(
+    0x8be4df61,
+    0x93ca,
+    0x11d2,
+    0xaa0d,
+    [0x00, 0xe0, 0x98, 0x03, 0x2b, 0x8c],
+);
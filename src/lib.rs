@@ -15,6 +15,9 @@
 //! The `proto` module contains the standard UEFI protocols, which are normally provided
 //! by the various UEFI drivers and firmware layers.
 //!
+//! Other well-known GUIDs, such as those of configuration tables, can be found in
+//! the `guids` module.
+//!
 //! ## Adapting to local conditions
 //!
 //! Unlike system tables, which are present on *all* UEFI implementations,
@@ -40,17 +43,23 @@ extern crate alloc as alloc_api;
 #[macro_use]
 pub mod data_types;
 pub use self::data_types::{unsafe_guid, Identify};
-pub use self::data_types::{CStr16, CStr8, Char16, Char8, Event, Guid, Handle};
+pub use self::data_types::{CStr16, CStr8, Char16, Char8, Event, Guid, Handle, Ucs2Str, Utf16Str};
 
 mod result;
-pub use self::result::{Completion, Result, ResultExt, Status};
+pub use self::result::{Completion, Result, ResultExt, Status, StatusCategory};
 
 pub mod table;
 
+pub mod guids;
+
 pub mod proto;
 
 pub mod prelude;
 
+pub mod mem;
+
+pub mod crc;
+
 #[cfg(feature = "alloc")]
 pub mod alloc;
 
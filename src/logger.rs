@@ -1,5 +1,6 @@
 //! This optional feature adds support for the `log` crate, providing
-//! a custom logger implementation which writes to a UEFI text output protocol.
+//! a custom logger implementation which writes to a UEFI text output protocol,
+//! or to any other `TextOutput` such as a serial port or a debug port.
 //!
 //! The main export of this module is the `Logger` structure,
 //! which implements the `log` crate's trait `Log`.
@@ -12,18 +13,20 @@
 //! The last part also means that some Unicode characters might not be
 //! supported by the UEFI console. Don't expect emoji output support.
 
-use crate::proto::console::text::Output;
+use crate::proto::console::text::TextOutput;
+use crate::ResultExt;
 
 use core::fmt::{self, Write};
 use core::ptr::NonNull;
 
-/// Logging implementation which writes to a UEFI output stream.
+/// Logging implementation which writes to a UEFI output stream, or to any
+/// other text output.
 ///
 /// If this logger is used as a global logger, you must disable it using the
 /// `disable` method before exiting UEFI boot services in order to prevent
 /// undefined behaviour from inadvertent logging.
 pub struct Logger {
-    writer: Option<NonNull<Output<'static>>>,
+    writer: Option<NonNull<dyn TextOutput>>,
 }
 
 impl Logger {
@@ -35,10 +38,15 @@ impl Logger {
     /// # Safety
     ///
     /// Undefined behaviour may occur if this logger is still active after the
-    /// application has exited the boot services stage.
-    pub unsafe fn new(output: &mut Output) -> Self {
+    /// application has exited the boot services stage, or after `output` has
+    /// stopped being usable.
+    pub unsafe fn new(output: &mut dyn TextOutput) -> Self {
+        // The `log` crate requires a `'static` logger, so the lifetime of the
+        // output is erased. The caller is responsible for disabling it in time.
+        let output =
+            core::mem::transmute::<*mut (dyn TextOutput + '_), *mut dyn TextOutput>(output);
         Logger {
-            writer: NonNull::new(output as *const _ as *mut _),
+            writer: NonNull::new(output),
         }
     }
 
@@ -56,7 +64,8 @@ impl<'boot> log::Log for Logger {
     fn log(&self, record: &log::Record) {
         if let Some(mut ptr) = self.writer {
             let writer = unsafe { ptr.as_mut() };
-            let result = DecoratedLog::write(writer, record.level(), record.args());
+            let result =
+                DecoratedLog::write(&mut TextOutputWriter(writer), record.level(), record.args());
 
             // Some UEFI implementations, such as the one used by VirtualBox,
             // may intermittently drop out some text from SimpleTextOutput and
@@ -88,6 +97,18 @@ impl<'boot> log::Log for Logger {
 unsafe impl Sync for Logger {}
 unsafe impl Send for Logger {}
 
+/// Adapter from `TextOutput` to `fmt::Write`, as expected by `DecoratedLog`
+struct TextOutputWriter<'writer>(&'writer mut dyn TextOutput);
+
+impl fmt::Write for TextOutputWriter<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0
+            .write_str(s)
+            .warning_as_error()
+            .map_err(|_| fmt::Error)
+    }
+}
+
 /// Writer wrapper which prints a log level in front of every line of text
 ///
 /// This is less easy than it sounds because...
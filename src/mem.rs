@@ -0,0 +1,85 @@
+//! Memory manipulation helpers which remain usable after exiting boot services.
+//!
+//! The firmware's `CopyMem` and `SetMem` boot services are often faster than a
+//! naive implementation, but they cannot be called anymore once UEFI boot
+//! services have been exited. The functions of this module use the firmware
+//! routines while they are available, and fall back to a plain Rust
+//! implementation afterwards.
+//!
+//! # Usage
+//!
+//! Call the `init` function with a reference to the boot services table in
+//! order to enable the use of the firmware routines. Until then, the Rust
+//! implementation is used.
+//!
+//! The firmware routines are automatically disabled when boot services are
+//! exited through `SystemTable<Boot>::exit_boot_services`. If you exit boot
+//! services by other means, you must call the `exit_boot_services` function
+//! of this module beforehand.
+
+use crate::table::boot::BootServices;
+use core::ptr::NonNull;
+
+/// Reference to the boot services table, used to call the firmware routines.
+///
+/// The inner pointer is only safe to dereference if UEFI boot services have not been
+/// exited by the host application yet.
+static mut BOOT_SERVICES: Option<NonNull<BootServices>> = None;
+
+/// Enables the use of the firmware memory routines.
+///
+/// # Safety
+///
+/// This function is unsafe because you _must_ make sure that exit_boot_services
+/// will be called when UEFI boot services will be exited.
+pub unsafe fn init(boot_services: &BootServices) {
+    BOOT_SERVICES = NonNull::new(boot_services as *const _ as *mut _);
+}
+
+/// Notify this module that boot services are not safe to call anymore
+///
+/// All subsequent calls will use the Rust implementation of the helpers.
+pub fn exit_boot_services() {
+    unsafe {
+        BOOT_SERVICES = None;
+    }
+}
+
+/// Copies `len` bytes from `src` to `dest`. The buffers can overlap.
+///
+/// # Safety
+///
+/// This function is unsafe as it can be used to violate most safety
+/// invariants of the Rust type system.
+pub unsafe fn copy_mem(dest: *mut u8, src: *const u8, len: usize) {
+    if let Some(boot_services) = BOOT_SERVICES {
+        boot_services.as_ref().memmove(dest, src, len);
+    } else if (dest as usize) < (src as usize) {
+        // Copy forward, so that an overlapping source is read before being
+        // overwritten.
+        for i in 0..len {
+            dest.add(i).write(src.add(i).read());
+        }
+    } else {
+        // Copy backward, for the same reason.
+        for i in (0..len).rev() {
+            dest.add(i).write(src.add(i).read());
+        }
+    }
+}
+
+/// Sets `len` bytes of `buffer` to `value`.
+///
+/// # Safety
+///
+/// This function is unsafe as it can be used to violate most safety
+/// invariants of the Rust type system.
+pub unsafe fn set_mem(buffer: *mut u8, len: usize, value: u8) {
+    if let Some(boot_services) = BOOT_SERVICES {
+        boot_services.as_ref().memset(buffer, len, value);
+    } else {
+        for i in 0..len {
+            buffer.add(i).write(value);
+        }
+    }
+}
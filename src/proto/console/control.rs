@@ -0,0 +1,56 @@
+//! Legacy console control protocol.
+
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Result, Status};
+
+/// Switches the screen between text and graphics mode.
+///
+/// This protocol predates the UEFI specification, and is only provided by
+/// some firmware implementations (notably Apple's), which may start UEFI
+/// applications with the screen in graphics mode, where text output is not
+/// visible.
+#[repr(C)]
+#[unsafe_guid("f42f7782-012e-4c12-9956-49f94304f721")]
+#[derive(Protocol)]
+pub struct ConsoleControl {
+    get_mode: extern "efiapi" fn(
+        this: &ConsoleControl,
+        mode: &mut ScreenMode,
+        gop_uga_exists: *mut bool,
+        std_in_locked: *mut bool,
+    ) -> Status,
+    set_mode: extern "efiapi" fn(this: &mut ConsoleControl, mode: ScreenMode) -> Status,
+    lock_std_in: usize,
+}
+
+impl ConsoleControl {
+    /// Returns the current mode of the screen.
+    pub fn mode(&self) -> Result<ScreenMode> {
+        let mut mode = ScreenMode::TEXT;
+        (self.get_mode)(
+            self,
+            &mut mode,
+            core::ptr::null_mut(),
+            core::ptr::null_mut(),
+        )
+        .into_with_val(|| mode)
+    }
+
+    /// Switches the screen to the given mode.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the mode is not supported
+    pub fn set_mode(&mut self, mode: ScreenMode) -> Result {
+        (self.set_mode)(self, mode).into()
+    }
+}
+
+newtype_enum! {
+/// Mode of the screen
+pub enum ScreenMode: u32 => {
+    /// Text output protocols are displayed on the screen
+    TEXT        = 0,
+    /// The screen is used for graphics output
+    GRAPHICS    = 1,
+}}
@@ -0,0 +1,127 @@
+//! Mouse cursor drawn over the screen contents.
+
+use super::{BltOp, BltPixel, BltRegion, GraphicsOutput, Rect};
+use crate::{Result, ResultExt};
+
+/// Maximal width and height of a cursor image, in pixels
+pub const MAX_CURSOR_SIZE: usize = 32;
+
+const MAX_CURSOR_PIXELS: usize = MAX_CURSOR_SIZE * MAX_CURSOR_SIZE;
+
+/// A cursor image drawn over the screen, which can be moved around without
+/// damaging what lies under it.
+///
+/// The pixels covered by the cursor are saved when it is shown, and restored
+/// when it is hidden or moved elsewhere. Parts of the cursor which fall
+/// outside of the screen are clipped.
+///
+/// The screen must not be drawn upon under the cursor while it is shown, as
+/// these changes would be undone when the cursor moves. Hide it first.
+pub struct CursorOverlay<'img> {
+    image: &'img [u32],
+    width: usize,
+    height: usize,
+    saved: [BltPixel; MAX_CURSOR_PIXELS],
+    shown: Option<Rect>,
+}
+
+impl<'img> CursorOverlay<'img> {
+    /// Creates a hidden cursor from an image of `width` x `height` pixels.
+    ///
+    /// The pixels of the image are stored row by row as `0xAARRGGBB` values,
+    /// where the alpha channel is the opacity of the pixel, which is blended
+    /// with the screen contents. Images are limited to `MAX_CURSOR_SIZE`
+    /// pixels in both dimensions.
+    pub fn new(image: &'img [u32], width: usize, height: usize) -> Self {
+        assert!(
+            width <= MAX_CURSOR_SIZE && height <= MAX_CURSOR_SIZE,
+            "Cursor image is too large"
+        );
+        assert!(image.len() >= width * height, "Cursor image is truncated");
+        CursorOverlay {
+            image,
+            width,
+            height,
+            saved: [BltPixel::new(0, 0, 0); MAX_CURSOR_PIXELS],
+            shown: None,
+        }
+    }
+
+    /// Returns the region of the screen which the cursor covers, if shown.
+    pub fn shown_region(&self) -> Option<Rect> {
+        self.shown
+    }
+
+    /// Draws the cursor with its top-left corner at (`x`, `y`), restoring
+    /// the pixels under its previous position if it was already shown.
+    pub fn show(&mut self, gop: &mut GraphicsOutput, x: usize, y: usize) -> Result {
+        self.hide(gop).warning_as_error()?;
+
+        let resolution = gop.current_mode_info().resolution();
+        let region = match Rect::new(x, y, self.width, self.height).clip_to(resolution) {
+            Some(region) => region,
+            None => return Ok(().into()),
+        };
+        let buffer_region = BltRegion::SubRectangle {
+            coords: (0, 0),
+            px_stride: self.width,
+        };
+
+        gop.blt(BltOp::VideoToBltBuffer {
+            buffer: &mut self.saved,
+            src: (region.x, region.y),
+            dest: buffer_region,
+            dims: (region.width, region.height),
+        })
+        .warning_as_error()?;
+
+        let mut composed = self.saved;
+        for row in 0..region.height {
+            for column in 0..region.width {
+                let index = row * self.width + column;
+                composed[index] = blend(self.image[index], self.saved[index]);
+            }
+        }
+
+        gop.blt(BltOp::BufferToVideo {
+            buffer: &composed,
+            src: buffer_region,
+            dest: (region.x, region.y),
+            dims: (region.width, region.height),
+        })
+        .warning_as_error()?;
+        self.shown = Some(region);
+        Ok(().into())
+    }
+
+    /// Removes the cursor from the screen, restoring the pixels under it.
+    pub fn hide(&mut self, gop: &mut GraphicsOutput) -> Result {
+        let region = match self.shown.take() {
+            Some(region) => region,
+            None => return Ok(().into()),
+        };
+        gop.blt(BltOp::BufferToVideo {
+            buffer: &self.saved,
+            src: BltRegion::SubRectangle {
+                coords: (0, 0),
+                px_stride: self.width,
+            },
+            dest: (region.x, region.y),
+            dims: (region.width, region.height),
+        })
+    }
+}
+
+/// Blend a `0xAARRGGBB` pixel over a screen pixel
+fn blend(argb: u32, under: BltPixel) -> BltPixel {
+    let alpha = argb >> 24;
+    let mix = |over: u32, under: u8| {
+        let under = u32::from(under);
+        ((over * alpha + under * (255 - alpha) + 127) / 255) as u8
+    };
+    BltPixel::new(
+        mix((argb >> 16) & 0xFF, under.red),
+        mix((argb >> 8) & 0xFF, under.green),
+        mix(argb & 0xFF, under.blue),
+    )
+}
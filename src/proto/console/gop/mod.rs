@@ -23,11 +23,25 @@
 //! In theory, a buffer with a width of 640 should have (640 * 4) bytes per row,
 //! but in practice there might be some extra padding used for efficiency.
 
+mod cursor;
+mod font;
+mod srgb;
+mod text;
+
+pub use self::cursor::{CursorOverlay, MAX_CURSOR_SIZE};
+pub use self::srgb::{linear_to_srgb, srgb_to_linear};
+pub use self::text::TextRenderer;
+
 use crate::proto::Protocol;
 use crate::{unsafe_guid, Completion, Result, Status};
+#[cfg(feature = "exts")]
+use alloc_api::vec::Vec;
+#[cfg(feature = "exts")]
+use core::cmp::Reverse;
 use core::marker::PhantomData;
 use core::mem;
 use core::ptr;
+use core::sync::atomic::{AtomicUsize, Ordering};
 
 /// Provides access to the video hardware's frame buffer.
 ///
@@ -90,9 +104,25 @@ impl<'boot> GraphicsOutput<'boot> {
     /// Sets the video device into the specified mode, clearing visible portions
     /// of the output display to black.
     ///
-    /// This function will invalidate the current framebuffer.
-    pub fn set_mode(&mut self, mode: &Mode) -> Result {
-        (self.set_mode)(self, mode.index).into()
+    /// This function will invalidate the current framebuffer, as some firmware
+    /// move it to another address. Frame buffers of this graphics output which
+    /// were obtained before a successful mode change report being stale, see
+    /// `FrameBuffer::is_stale()`, so a fresh one is returned, or `None` if the
+    /// new mode has no frame buffer (`PixelFormat::BltOnly`).
+    pub fn set_mode(&mut self, mode: &Mode) -> Result<Option<FrameBuffer>> {
+        let output = self.mode as *const _ as usize;
+        let completion = (self.set_mode)(self, mode.index).into_with_val(|| {
+            if let Some(generation) = mode_generation(output) {
+                generation.fetch_add(1, Ordering::Relaxed);
+            }
+        })?;
+        Ok(completion.map(move |()| {
+            if self.mode.info.format == PixelFormat::BltOnly {
+                None
+            } else {
+                Some(self.frame_buffer())
+            }
+        }))
     }
 
     /// Performs a blt (block transfer) operation on the frame buffer.
@@ -227,6 +257,54 @@ impl<'boot> GraphicsOutput<'boot> {
         }
     }
 
+    /// Fills a rectangle of the screen with a color.
+    ///
+    /// The parts of the rectangle which lie outside of the screen are
+    /// clipped, instead of making the operation fail.
+    pub fn fill_rect(&mut self, rect: Rect, color: BltPixel) -> Result {
+        match self.clip(rect) {
+            Some(rect) => self.blt(BltOp::VideoFill {
+                color,
+                dest: (rect.x, rect.y),
+                dims: (rect.width, rect.height),
+            }),
+            None => Ok(().into()),
+        }
+    }
+
+    /// Copies a rectangle of a larger back buffer to the screen.
+    ///
+    /// `buffer` holds `buffer_stride` pixels per row, and `src` is the region
+    /// of it which is copied to the `dest` coordinates of the frame buffer.
+    /// This allows only redrawing the parts of the screen which changed. The
+    /// parts of the copy which would land outside of the screen are clipped.
+    pub fn blt_region(
+        &mut self,
+        buffer: &[BltPixel],
+        buffer_stride: usize,
+        src: Rect,
+        dest: (usize, usize),
+    ) -> Result {
+        let dest = match self.clip(Rect::new(dest.0, dest.1, src.width, src.height)) {
+            Some(dest) => dest,
+            None => return Ok(().into()),
+        };
+        self.blt(BltOp::BufferToVideo {
+            buffer,
+            src: BltRegion::SubRectangle {
+                coords: (src.x, src.y),
+                px_stride: buffer_stride,
+            },
+            dest: (dest.x, dest.y),
+            dims: (dest.width, dest.height),
+        })
+    }
+
+    /// Clip a rectangle to the screen in the current mode
+    fn clip(&self, rect: Rect) -> Option<Rect> {
+        rect.clip_to(self.current_mode_info().resolution())
+    }
+
     /// Memory-safety check for accessing a region of the framebuffer
     fn check_framebuffer_region(&self, coords: (usize, usize), dims: (usize, usize)) {
         let (width, height) = self.current_mode_info().resolution();
@@ -244,7 +322,7 @@ impl<'boot> GraphicsOutput<'boot> {
     fn check_blt_buffer_region(&self, region: BltRegion, dims: (usize, usize), buf_length: usize) {
         match region {
             BltRegion::Full => assert!(
-                dims.0.saturating_add(dims.1.saturating_mul(dims.0)) <= buf_length,
+                dims.0.saturating_mul(dims.1) <= buf_length,
                 "BltBuffer access out of bounds"
             ),
             BltRegion::SubRectangle {
@@ -269,6 +347,12 @@ impl<'boot> GraphicsOutput<'boot> {
     }
 
     /// Access the frame buffer directly
+    ///
+    /// The frame buffer borrows the protocol, so that the mode cannot be
+    /// changed through it while the frame buffer is in use. If the mode is
+    /// changed anyway, e.g. through another reference to the protocol, the
+    /// frame buffer becomes stale and must not be used anymore. Mode changes
+    /// of other graphics outputs do not affect it.
     pub fn frame_buffer(&mut self) -> FrameBuffer {
         assert!(
             self.mode.info.format != PixelFormat::BltOnly,
@@ -276,10 +360,14 @@ impl<'boot> GraphicsOutput<'boot> {
         );
         let base = self.mode.fb_address as *mut u8;
         let size = self.mode.fb_size;
+        let output = self.mode as *const _ as usize;
 
         FrameBuffer {
             base,
             size,
+            info: *self.mode.info,
+            output,
+            generation: mode_generation(output).map(|g| g.load(Ordering::Relaxed)),
             _lifetime: PhantomData,
         }
     }
@@ -301,6 +389,33 @@ struct ModeData<'info> {
     fb_size: usize,
 }
 
+#[cfg(feature = "exts")]
+impl<'boot> GraphicsOutput<'boot> {
+    /// Returns the available graphics modes, with a single mode for each
+    /// resolution, from the largest resolution to the smallest one.
+    ///
+    /// Firmware mode lists are often unordered, and may list a resolution
+    /// once per pixel format. Modes with an RGB or BGR frame buffer are then
+    /// preferred, followed by those with a bitmask pixel format.
+    pub fn sorted_modes(&self) -> Vec<Mode> {
+        let format_rank = |format| match format {
+            PixelFormat::RGB | PixelFormat::BGR => 0,
+            PixelFormat::Bitmask => 1,
+            PixelFormat::BltOnly => 2,
+        };
+
+        let mut modes: Vec<_> = self.modes().map(|mode| mode.log()).collect();
+        modes.sort_by_key(|mode| {
+            let (width, height) = mode.info.resolution();
+            let rank = format_rank(mode.info.pixel_format());
+            (Reverse(width * height), Reverse(width), rank)
+        });
+        // Modes of the same resolution are now adjacent, best format first
+        modes.dedup_by_key(|mode| mode.info.resolution());
+        modes
+    }
+}
+
 /// Represents the format of the pixels in a frame buffer.
 #[derive(Debug, Copy, Clone, Eq, PartialEq)]
 #[repr(u32)]
@@ -445,19 +560,135 @@ impl BltPixel {
             _reserved: 0,
         }
     }
+
+    /// Create a new pixel from sRGB-encoded values, such as those of most
+    /// image files, converting them to the linear encoding of the GOP.
+    pub fn from_srgb(red: u8, green: u8, blue: u8) -> Self {
+        Self::new(
+            srgb_to_linear(red),
+            srgb_to_linear(green),
+            srgb_to_linear(blue),
+        )
+    }
+
+    /// Encode this pixel as a 32-bit frame buffer value, in the given format.
+    ///
+    /// `mask` is only used by the `Bitmask` format. Pixels are encoded in the
+    /// BGR format of Blt buffers in `BltOnly` mode, or if `mask` is missing.
+    pub fn to_framebuffer_u32(&self, format: PixelFormat, mask: Option<PixelBitmask>) -> u32 {
+        let (red, green, blue) = (
+            u32::from(self.red),
+            u32::from(self.green),
+            u32::from(self.blue),
+        );
+        match (format, mask) {
+            (PixelFormat::RGB, _) => red | (green << 8) | (blue << 16),
+            (PixelFormat::Bitmask, Some(mask)) => {
+                scale_to_mask(red, mask.red)
+                    | scale_to_mask(green, mask.green)
+                    | scale_to_mask(blue, mask.blue)
+            }
+            _ => blue | (green << 8) | (red << 16),
+        }
+    }
+
+    /// Decode a 32-bit frame buffer value in the given format, as encoded by
+    /// `to_framebuffer_u32()`.
+    pub fn from_framebuffer_u32(
+        value: u32,
+        format: PixelFormat,
+        mask: Option<PixelBitmask>,
+    ) -> Self {
+        let channel = |shift: u32| (value >> shift) as u8;
+        match (format, mask) {
+            (PixelFormat::RGB, _) => Self::new(channel(0), channel(8), channel(16)),
+            (PixelFormat::Bitmask, Some(mask)) => Self::new(
+                scale_from_mask(value, mask.red),
+                scale_from_mask(value, mask.green),
+                scale_from_mask(value, mask.blue),
+            ),
+            _ => Self::new(channel(16), channel(8), channel(0)),
+        }
+    }
 }
 
 impl From<u32> for BltPixel {
     fn from(color: u32) -> Self {
         Self {
             blue: (color & 0x00_00_FF) as u8,
-            green: (color & 0x00_FF_00 >> 8) as u8,
-            red: (color & 0xFF_00_00 >> 16) as u8,
+            green: ((color & 0x00_FF_00) >> 8) as u8,
+            red: ((color & 0xFF_00_00) >> 16) as u8,
             _reserved: 0,
         }
     }
 }
 
+/// A rectangle, defined by its top-left corner and its dimensions
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct Rect {
+    /// Horizontal coordinate of the top-left corner
+    pub x: usize,
+    /// Vertical coordinate of the top-left corner
+    pub y: usize,
+    /// Width of the rectangle
+    pub width: usize,
+    /// Height of the rectangle
+    pub height: usize,
+}
+
+impl Rect {
+    /// Create a new rectangle from its top-left corner and its dimensions
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+
+    /// Truth that the rectangle covers no pixel
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+
+    /// Truth that the pixel at (`x`, `y`) lies within the rectangle
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.right()).contains(&x) && (self.y..self.bottom()).contains(&y)
+    }
+
+    /// Returns the region covered by both rectangles, or `None` if they do
+    /// not overlap.
+    pub fn intersect(&self, other: &Rect) -> Option<Rect> {
+        let (x, y) = (self.x.max(other.x), self.y.max(other.y));
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        let rect = Rect::new(x, y, right.saturating_sub(x), bottom.saturating_sub(y));
+        if rect.is_empty() {
+            None
+        } else {
+            Some(rect)
+        }
+    }
+
+    /// Returns the part of the rectangle which lies within a screen of the
+    /// given (width, height), as returned by `ModeInfo::resolution()`, or
+    /// `None` if it is entirely off-screen.
+    pub fn clip_to(&self, bounds: (usize, usize)) -> Option<Rect> {
+        self.intersect(&Rect::new(0, 0, bounds.0, bounds.1))
+    }
+
+    /// Horizontal coordinate just past the right edge
+    fn right(&self) -> usize {
+        self.x.saturating_add(self.width)
+    }
+
+    /// Vertical coordinate just past the bottom edge
+    fn bottom(&self) -> usize {
+        self.y.saturating_add(self.height)
+    }
+}
+
 /// Region of the `BltBuffer` which we are operating on
 ///
 /// Some `Blt` operations can operate on either the full `BltBuffer` or a
@@ -524,10 +755,44 @@ pub enum BltOp<'buf> {
     },
 }
 
+/// Number of graphics outputs whose mode changes are tracked
+const TRACKED_OUTPUTS: usize = 8;
+
+/// Initial value of the entries below
+#[allow(clippy::declare_interior_mutable_const)]
+const UNUSED: AtomicUsize = AtomicUsize::new(0);
+
+/// Address of the mode data of the tracked graphics outputs, which identifies
+/// them, or 0 for unused entries
+static OUTPUTS: [AtomicUsize; TRACKED_OUTPUTS] = [UNUSED; TRACKED_OUTPUTS];
+
+/// Number of successful mode changes of the tracked graphics outputs, used to
+/// detect stale frame buffers
+static MODE_GENERATIONS: [AtomicUsize; TRACKED_OUTPUTS] = [UNUSED; TRACKED_OUTPUTS];
+
+/// Returns the mode change counter of the graphics output whose mode data
+/// lives at address `output`, or `None` if too many outputs are tracked
+fn mode_generation(output: usize) -> Option<&'static AtomicUsize> {
+    // Entries are never released, so an output comes before any free entry
+    OUTPUTS
+        .iter()
+        .zip(MODE_GENERATIONS.iter())
+        .find_map(|(key, generation)| {
+            match key.compare_exchange(0, output, Ordering::Relaxed, Ordering::Relaxed) {
+                Ok(_) => Some(generation),
+                Err(key) if key == output => Some(generation),
+                Err(_) => None,
+            }
+        })
+}
+
 /// Direct access to a memory-mapped frame buffer
 pub struct FrameBuffer<'gop> {
     base: *mut u8,
     size: usize,
+    info: ModeInfo,
+    output: usize,
+    generation: Option<usize>,
     _lifetime: PhantomData<&'gop mut u8>,
 }
 
@@ -548,6 +813,19 @@ impl<'gop> FrameBuffer<'gop> {
         self.size
     }
 
+    /// Truth that the mode of its graphics output was changed since this
+    /// frame buffer was obtained, which may have moved it to another address.
+    ///
+    /// Accessing a stale frame buffer panics in debug builds. Mode changes
+    /// are only tracked for the first few graphics outputs in use, frame
+    /// buffers of the other ones never report being stale.
+    pub fn is_stale(&self) -> bool {
+        match (self.generation, mode_generation(self.output)) {
+            (Some(generation), Some(current)) => generation != current.load(Ordering::Relaxed),
+            _ => false,
+        }
+    }
+
     /// Modify the i-th byte of the frame buffer
     ///
     /// # Safety
@@ -557,6 +835,7 @@ impl<'gop> FrameBuffer<'gop> {
     /// - There is no bound checking on memory accesses in release mode
     #[inline]
     pub unsafe fn write_byte(&mut self, index: usize, value: u8) {
+        debug_assert!(!self.is_stale(), "Frame buffer used after a mode change");
         debug_assert!(index < self.size, "Frame buffer accessed out of bounds");
         self.base.add(index).write_volatile(value)
     }
@@ -570,6 +849,7 @@ impl<'gop> FrameBuffer<'gop> {
     /// - There is no bound checking on memory accesses in release mode
     #[inline]
     pub unsafe fn read_byte(&self, index: usize) -> u8 {
+        debug_assert!(!self.is_stale(), "Frame buffer used after a mode change");
         debug_assert!(index < self.size, "Frame buffer accessed out of bounds");
         self.base.add(index).read_volatile()
     }
@@ -588,6 +868,7 @@ impl<'gop> FrameBuffer<'gop> {
     /// - There is no bound checking on memory accesses in release mode
     #[inline]
     pub unsafe fn write_value<T>(&mut self, index: usize, value: T) {
+        debug_assert!(!self.is_stale(), "Frame buffer used after a mode change");
         debug_assert!(
             index.saturating_add(mem::size_of::<T>()) <= self.size,
             "Frame buffer accessed out of bounds"
@@ -609,10 +890,81 @@ impl<'gop> FrameBuffer<'gop> {
     /// - There is no bound checking on memory accesses in release mode
     #[inline]
     pub unsafe fn read_value<T>(&self, index: usize) -> T {
+        debug_assert!(!self.is_stale(), "Frame buffer used after a mode change");
         debug_assert!(
             index.saturating_add(mem::size_of::<T>()) <= self.size,
             "Frame buffer accessed out of bounds"
         );
         (self.base.add(index) as *const T).read_volatile()
     }
+
+    /// Write a pixel at coordinates `(x, y)` of the screen, converting it to
+    /// the pixel format of the current mode
+    ///
+    /// Rows of pixels are `stride` pixels apart in the frame buffer, which may
+    /// be more than the horizontal resolution. The padding pixels are not part
+    /// of the screen, so coordinates are checked against the resolution.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if `(x, y)` is outside of the screen
+    pub fn try_write_pixel(&mut self, x: usize, y: usize, pixel: BltPixel) -> Result {
+        let (width, height) = self.info.resolution();
+        if x >= width || y >= height {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+        unsafe { self.write_pixel_unchecked(x, y, pixel) };
+        Ok(().into())
+    }
+
+    /// Write a pixel at coordinates `(x, y)` of the screen, converting it to
+    /// the pixel format of the current mode, without checking the coordinates
+    ///
+    /// The pixel is written at byte `4 * (y * stride + x)` of the frame buffer,
+    /// as every pixel format with a frame buffer uses 32-bit pixels.
+    ///
+    /// # Safety
+    ///
+    /// `(x, y)` must be within the resolution of the current mode. There is no
+    /// bound checking on memory accesses in release mode.
+    #[inline]
+    pub unsafe fn write_pixel_unchecked(&mut self, x: usize, y: usize, pixel: BltPixel) {
+        let index = 4 * (y * self.info.stride() + x);
+        self.write_value(index, encode_pixel(&self.info, pixel));
+    }
+}
+
+/// Convert a color to the pixel format of a mode which has a frame buffer
+fn encode_pixel(info: &ModeInfo, color: BltPixel) -> u32 {
+    color.to_framebuffer_u32(info.pixel_format(), info.pixel_bitmask())
+}
+
+/// Scale an 8-bit color channel to the bits covered by `mask`
+fn scale_to_mask(value: u32, mask: u32) -> u32 {
+    if mask == 0 {
+        return 0;
+    }
+    let bits = mask.count_ones();
+    let value = if bits >= 8 {
+        value << (bits - 8)
+    } else {
+        value >> (8 - bits)
+    };
+    (value << mask.trailing_zeros()) & mask
+}
+
+/// Extract the bits of `value` covered by `mask` as an 8-bit color channel
+fn scale_from_mask(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let bits = mask.count_ones();
+    let value = (value & mask) >> mask.trailing_zeros();
+    let value = if bits >= 8 {
+        value >> (bits - 8)
+    } else {
+        // Scale rather than shift, so that a full channel maps to 255
+        value * 255 / ((1 << bits) - 1)
+    };
+    value as u8
 }
@@ -0,0 +1,182 @@
+//! Text rendering directly into the frame buffer.
+
+use super::font::{FIRST_CHAR, GLYPHS, GLYPH_HEIGHT};
+use super::{encode_pixel, BltPixel, FrameBuffer, ModeInfo};
+use core::fmt;
+use core::ptr;
+
+/// Width of a character cell in pixels
+const GLYPH_WIDTH: usize = 8;
+
+/// Distance between two tab stops, in columns
+const TAB_WIDTH: usize = 8;
+
+/// Draws text into a frame buffer, using a built-in 8x16 bitmap font.
+///
+/// Unlike the text output protocol, this only needs access to the frame
+/// buffer, and can thus keep being used after UEFI boot services have been
+/// exited, e.g. for displaying an error message from an OS loader.
+///
+/// Only printable ASCII characters are supported, other characters are
+/// displayed as `?`. Tabs advance to the next multiple of 8 columns. Text wraps
+/// around at the end of a line, and the screen is scrolled when the last line
+/// is full.
+///
+/// The renderer implements `core::fmt::Write`, so that the `write!` and
+/// `writeln!` macros can be used with it.
+pub struct TextRenderer<'gop> {
+    fb: FrameBuffer<'gop>,
+    info: ModeInfo,
+    foreground: u32,
+    background: u32,
+    column: usize,
+    row: usize,
+}
+
+impl<'gop> TextRenderer<'gop> {
+    /// Set up a text renderer drawing into `fb`, which must have been obtained
+    /// from a graphics output protocol in the mode described by `info`.
+    ///
+    /// Text is drawn in white on a black background, starting from the top
+    /// left corner of the screen. The screen is not cleared.
+    pub fn new(fb: FrameBuffer<'gop>, info: ModeInfo) -> Self {
+        let mut renderer = TextRenderer {
+            fb,
+            info,
+            foreground: 0,
+            background: 0,
+            column: 0,
+            row: 0,
+        };
+        renderer.set_colors(BltPixel::new(255, 255, 255), BltPixel::new(0, 0, 0));
+        renderer
+    }
+
+    /// Set the colors used for subsequent text output
+    pub fn set_colors(&mut self, foreground: BltPixel, background: BltPixel) {
+        self.foreground = self.encode(foreground);
+        self.background = self.encode(background);
+    }
+
+    /// Returns the number of (columns, rows) of text which fit on the screen.
+    pub fn dimensions(&self) -> (usize, usize) {
+        let (width, height) = self.info.resolution();
+        (width / GLYPH_WIDTH, height / GLYPH_HEIGHT)
+    }
+
+    /// Fill the screen with the background color, and move back to the top
+    /// left corner of the screen.
+    pub fn clear(&mut self) {
+        let (width, height) = self.info.resolution();
+        self.fill_rows(0, height, width);
+        self.column = 0;
+        self.row = 0;
+    }
+
+    /// Draw a single character, handling newlines, carriage returns and tabs.
+    pub fn write_char(&mut self, c: char) {
+        let (columns, rows) = self.dimensions();
+        if columns == 0 || rows == 0 {
+            return;
+        }
+
+        match c {
+            '\n' => self.new_line(),
+            '\r' => self.column = 0,
+            '\t' => loop {
+                self.write_char(' ');
+                if self.column % TAB_WIDTH == 0 {
+                    break;
+                }
+            },
+            c => {
+                if self.column == columns {
+                    self.new_line();
+                }
+                self.draw_glyph(c);
+                self.column += 1;
+            }
+        }
+    }
+
+    /// Draw a string, handling newlines, carriage returns and tabs.
+    pub fn write_str(&mut self, s: &str) {
+        for c in s.chars() {
+            self.write_char(c);
+        }
+    }
+
+    /// Move to the beginning of the next line, scrolling if necessary
+    fn new_line(&mut self) {
+        let (_, rows) = self.dimensions();
+        self.column = 0;
+        if self.row + 1 < rows {
+            self.row += 1;
+        } else {
+            self.scroll();
+        }
+    }
+
+    /// Move all lines of text up by one, and clear the last one
+    fn scroll(&mut self) {
+        let (width, _) = self.info.resolution();
+        let (_, rows) = self.dimensions();
+        let line_size = 4 * self.info.stride() * GLYPH_HEIGHT;
+        unsafe {
+            let base = self.fb.as_mut_ptr();
+            ptr::copy(base.add(line_size), base, (rows - 1) * line_size);
+        }
+        self.fill_rows((rows - 1) * GLYPH_HEIGHT, GLYPH_HEIGHT, width);
+    }
+
+    /// Draw the glyph of `c` in the current character cell
+    fn draw_glyph(&mut self, c: char) {
+        let glyph = glyph(c);
+        let stride = self.info.stride();
+        let x = self.column * GLYPH_WIDTH;
+        let y = self.row * GLYPH_HEIGHT;
+        for (dy, bits) in glyph.iter().enumerate() {
+            for dx in 0..GLYPH_WIDTH {
+                let color = if bits & (0x80 >> dx) != 0 {
+                    self.foreground
+                } else {
+                    self.background
+                };
+                let pixel_index = (y + dy) * stride + x + dx;
+                unsafe {
+                    self.fb.write_value(4 * pixel_index, color);
+                }
+            }
+        }
+    }
+
+    /// Fill `count` pixel rows starting at `first` with the background color
+    fn fill_rows(&mut self, first: usize, count: usize, width: usize) {
+        let stride = self.info.stride();
+        for y in first..first + count {
+            for x in 0..width {
+                unsafe {
+                    self.fb.write_value(4 * (y * stride + x), self.background);
+                }
+            }
+        }
+    }
+
+    /// Convert a color to the pixel format of the frame buffer
+    fn encode(&self, color: BltPixel) -> u32 {
+        encode_pixel(&self.info, color)
+    }
+}
+
+impl fmt::Write for TextRenderer<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        TextRenderer::write_str(self, s);
+        Ok(())
+    }
+}
+
+/// Find the glyph of a character, or the glyph of `?` if there is none
+fn glyph(c: char) -> &'static [u8; GLYPH_HEIGHT] {
+    let index = |c| (c as usize).wrapping_sub(FIRST_CHAR as usize);
+    GLYPHS.get(index(c)).unwrap_or(&GLYPHS[index('?')])
+}
@@ -0,0 +1,123 @@
+//! Absolute pointer devices, such as touch screens and pen tablets.
+
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Event, Result, Status};
+use bitflags::bitflags;
+use core::mem::MaybeUninit;
+
+/// Provides information about a pointer device which reports absolute
+/// coordinates, such as a touch screen.
+#[repr(C)]
+#[unsafe_guid("8d59d32b-c655-4ae9-9b15-f25904992a43")]
+#[derive(Protocol)]
+pub struct AbsolutePointer<'boot> {
+    reset: extern "efiapi" fn(this: &mut AbsolutePointer, ext_verif: bool) -> Status,
+    get_state:
+        extern "efiapi" fn(this: &AbsolutePointer, state: *mut AbsolutePointerState) -> Status,
+    wait_for_input: Event,
+    mode: &'boot AbsolutePointerMode,
+}
+
+impl<'boot> AbsolutePointer<'boot> {
+    /// Resets the pointer device hardware.
+    ///
+    /// The `extended_verification` parameter is used to request that UEFI
+    /// performs an extended check and reset of the input device.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device is malfunctioning and cannot be reset.
+    pub fn reset(&mut self, extended_verification: bool) -> Result {
+        (self.reset)(self, extended_verification).into()
+    }
+
+    /// Retrieves the pointer device's current state, if a state change occured
+    /// since the last time this function was called.
+    ///
+    /// Use `wait_for_input_event()` with the `BootServices::wait_for_event()`
+    /// interface in order to wait for input from the pointer device.
+    ///
+    /// # Errors
+    /// - `DeviceError` if there was an issue with the pointer device.
+    pub fn read_state(&mut self) -> Result<Option<AbsolutePointerState>> {
+        let mut pointer_state = MaybeUninit::<AbsolutePointerState>::uninit();
+
+        match (self.get_state)(self, pointer_state.as_mut_ptr()) {
+            Status::NOT_READY => Ok(None.into()),
+            other => other.into_with_val(|| unsafe { Some(pointer_state.assume_init()) }),
+        }
+    }
+
+    /// Event to be used with `BootServices::wait_for_event()` in order to wait
+    /// for input from the pointer device
+    pub fn wait_for_input_event(&self) -> Event {
+        self.wait_for_input
+    }
+
+    /// Returns a reference to the pointer device information.
+    pub fn mode(&self) -> &AbsolutePointerMode {
+        self.mode
+    }
+}
+
+/// Information about this absolute pointer device.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct AbsolutePointerMode {
+    min: (u64, u64, u64),
+    max: (u64, u64, u64),
+    attributes: AbsolutePointerAttributes,
+}
+
+impl AbsolutePointerMode {
+    /// Returns the minimal coordinates on the X/Y/Z axis.
+    pub fn min(&self) -> (u64, u64, u64) {
+        self.min
+    }
+
+    /// Returns the maximal coordinates on the X/Y/Z axis.
+    ///
+    /// An axis whose maximal coordinate is 0 is not supported by the device.
+    pub fn max(&self) -> (u64, u64, u64) {
+        self.max
+    }
+
+    /// Returns the optional features supported by the device.
+    pub fn attributes(&self) -> AbsolutePointerAttributes {
+        self.attributes
+    }
+}
+
+bitflags! {
+    /// Optional features of an absolute pointer device.
+    pub struct AbsolutePointerAttributes: u32 {
+        /// The device has an alternate button, such as a pen barrel button
+        const SUPPORTS_ALT_ACTIVE = 0x1;
+        /// The Z axis reports the pressure applied to the device
+        const SUPPORTS_PRESSURE_AS_Z = 0x2;
+    }
+}
+
+/// The current state of an absolute pointer device.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct AbsolutePointerState {
+    /// The coordinates on the X/Y/Z axis, within the range given by the
+    /// `AbsolutePointerMode`.
+    ///
+    /// If `AbsolutePointerMode` indicates an axis is not supported, it must be
+    /// ignored.
+    pub current: (u64, u64, u64),
+    /// The buttons which are currently pressed.
+    pub active_buttons: AbsolutePointerButtons,
+}
+
+bitflags! {
+    /// Buttons of an absolute pointer device.
+    pub struct AbsolutePointerButtons: u32 {
+        /// The device is being touched
+        const TOUCH_ACTIVE = 0x1;
+        /// The alternate button is pressed
+        const ALT_ACTIVE = 0x2;
+    }
+}
@@ -0,0 +1,122 @@
+//! Waiting for input from several pointer devices at once.
+
+use super::{AbsolutePointer, AbsolutePointerButtons, Pointer, PointerTracker};
+use crate::table::boot::{with_event_array, BootServices};
+use crate::{Completion, Event, Result, ResultExt};
+
+/// A pointer device which `wait_for_input` listens to
+///
+/// Both kinds of devices report positions in screen coordinates: relative
+/// movements are accumulated by a `PointerTracker`, and absolute coordinates
+/// are scaled from the range of the device to the screen.
+pub enum PointerSource<'dev, 'boot> {
+    /// A relative pointer device, such as a mouse
+    Relative(&'dev mut Pointer<'boot>, PointerTracker),
+    /// An absolute pointer device, such as a touch screen, and the (width,
+    /// height) of the screen in pixels
+    Absolute(&'dev mut AbsolutePointer<'boot>, (usize, usize)),
+}
+
+impl PointerSource<'_, '_> {
+    fn event(&self) -> Event {
+        match self {
+            PointerSource::Relative(pointer, _) => pointer.wait_for_input_event(),
+            PointerSource::Absolute(pointer, _) => pointer.wait_for_input_event(),
+        }
+    }
+
+    /// Read the state of the device, if it changed, as the `source`-th source
+    fn read(&mut self, source: usize) -> Result<Option<InputEvent>> {
+        match self {
+            PointerSource::Relative(pointer, tracker) => pointer.read_state().map_inner(|state| {
+                state.map(|state| {
+                    tracker.update(&state);
+                    InputEvent {
+                        source,
+                        position: tracker.position(),
+                        buttons: tracker.buttons(),
+                    }
+                })
+            }),
+            PointerSource::Absolute(pointer, screen) => {
+                let (min, max) = (pointer.mode().min(), pointer.mode().max());
+                let screen = *screen;
+                pointer.read_state().map_inner(|state| {
+                    state.map(|state| {
+                        let (x, y, _) = state.current;
+                        let buttons = state.active_buttons;
+                        InputEvent {
+                            source,
+                            position: (
+                                scale(x, min.0, max.0, screen.0),
+                                scale(y, min.1, max.1, screen.1),
+                            ),
+                            buttons: (
+                                buttons.contains(AbsolutePointerButtons::TOUCH_ACTIVE),
+                                buttons.contains(AbsolutePointerButtons::ALT_ACTIVE),
+                            ),
+                        }
+                    })
+                })
+            }
+        }
+    }
+}
+
+/// Scale a coordinate from the [min, max] range of a device to a screen axis
+/// of `size` pixels
+fn scale(value: u64, min: u64, max: u64, size: usize) -> usize {
+    let last = size.saturating_sub(1) as u128;
+    if max <= min {
+        return 0;
+    }
+    let value = u128::from(value.max(min).min(max) - min);
+    (value * last / u128::from(max - min)) as usize
+}
+
+/// An input from one of the sources of `wait_for_input`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct InputEvent {
+    /// Index of the source which reported the input
+    pub source: usize,
+    /// Position of the pointer on the screen, in pixels
+    pub position: (usize, usize),
+    /// State of the primary / secondary buttons
+    ///
+    /// For absolute devices, these are touching the device and pressing the
+    /// alternate button.
+    pub buttons: (bool, bool),
+}
+
+/// Waits until one of `sources` reports input, or until `timeout_us`
+/// microseconds have elapsed if a timeout is given, in which case `None` is
+/// returned.
+///
+/// Signals of devices whose state did not actually change are ignored. The
+/// timeout is restarted when this happens.
+///
+/// The limits of `BootServices::wait_any()` apply to the sources, with the
+/// timer implementing the timeout counting as one more source. An empty set
+/// of sources is rejected with `INVALID_PARAMETER`.
+pub fn wait_for_input(
+    bt: &BootServices,
+    sources: &mut [PointerSource],
+    timeout_us: Option<u64>,
+) -> Result<Option<InputEvent>> {
+    loop {
+        let events = sources.iter().map(PointerSource::event);
+        let signaled = with_event_array(events, None, |events| match timeout_us {
+            Some(timeout_us) => bt.wait_for_event_timeout(events, timeout_us),
+            None => bt.wait_for_event(events).discard_errdata().map_inner(Some),
+        })?;
+        let (status, index) = signaled.split();
+        let index = match index {
+            Some(index) => index,
+            None => return Ok(None.into()),
+        };
+        let (read_status, event) = sources[index].read(index)?.split();
+        if event.is_some() {
+            return Ok(Completion::new(status, event).with_status(read_status));
+        }
+    }
+}
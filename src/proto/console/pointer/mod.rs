@@ -4,6 +4,18 @@ use crate::proto::Protocol;
 use crate::{unsafe_guid, Event, Result, Status};
 use core::mem::MaybeUninit;
 
+mod absolute;
+pub use self::absolute::{
+    AbsolutePointer, AbsolutePointerAttributes, AbsolutePointerButtons, AbsolutePointerMode,
+    AbsolutePointerState,
+};
+
+mod input;
+pub use self::input::{wait_for_input, InputEvent, PointerSource};
+
+mod tracker;
+pub use self::tracker::PointerTracker;
+
 /// Provides information about a pointer device.
 #[repr(C)]
 #[unsafe_guid("31878c87-0b75-11d5-9a4f-0090273fc14d")]
@@ -68,6 +80,15 @@ pub struct PointerMode {
     has_button: (bool, bool),
 }
 
+impl PointerMode {
+    /// Returns the resolution of the device on the X/Y/Z axis, in counts/mm.
+    ///
+    /// An axis with a resolution of 0 is not supported by the device.
+    pub fn resolution(&self) -> (u64, u64, u64) {
+        self.resolution
+    }
+}
+
 /// The relative change in the pointer's state.
 #[derive(Debug, Copy, Clone)]
 #[repr(C)]
@@ -0,0 +1,122 @@
+//! Conversion of relative pointer movements into screen coordinates.
+
+use super::PointerState;
+
+/// Tracks the position of a cursor on the screen, from the relative movements
+/// reported by a pointer device.
+///
+/// Movements are converted from the device resolution (in counts/mm) to
+/// pixels, and the resulting position is clamped to the screen. Fractions of
+/// pixels are carried over to the next update, so that slow movements are not
+/// lost. Movements on the Z axis (usually a scroll wheel) are accumulated
+/// separately, and do not affect the position.
+#[derive(Debug, Clone)]
+pub struct PointerTracker {
+    resolution: (u64, u64, u64),
+    pixels_per_mm: u64,
+    screen: (usize, usize),
+    position: (usize, usize),
+    remainder: (i64, i64),
+    scroll: i64,
+    buttons: (bool, bool),
+}
+
+impl PointerTracker {
+    /// Set up a tracker for a device with the given resolution, as reported by
+    /// `PointerMode::resolution`, on a screen of `screen` (width, height)
+    /// pixels with a density of `pixels_per_mm`.
+    ///
+    /// The cursor starts at the center of the screen.
+    pub fn new(resolution: (u64, u64, u64), screen: (usize, usize), pixels_per_mm: u64) -> Self {
+        PointerTracker {
+            resolution,
+            pixels_per_mm,
+            screen,
+            position: (screen.0 / 2, screen.1 / 2),
+            remainder: (0, 0),
+            scroll: 0,
+            buttons: (false, false),
+        }
+    }
+
+    /// Apply a state change reported by `Pointer::read_state`.
+    ///
+    /// Movements along axes which the device does not support are ignored.
+    pub fn update(&mut self, state: &PointerState) {
+        let (dx, dy, dz) = state.relative_movement;
+        let (x, rem_x) = self.move_axis(
+            self.position.0,
+            self.screen.0,
+            self.remainder.0,
+            dx,
+            self.resolution.0,
+        );
+        let (y, rem_y) = self.move_axis(
+            self.position.1,
+            self.screen.1,
+            self.remainder.1,
+            dy,
+            self.resolution.1,
+        );
+        self.position = (x, y);
+        self.remainder = (rem_x, rem_y);
+        if self.resolution.2 != 0 {
+            self.scroll += i64::from(dz);
+        }
+        self.buttons = state.button;
+    }
+
+    /// Returns the (x, y) position of the cursor in pixels.
+    pub fn position(&self) -> (usize, usize) {
+        self.position
+    }
+
+    /// Moves the cursor to the given position, clamped to the screen.
+    pub fn set_position(&mut self, x: usize, y: usize) {
+        self.position = (
+            x.min(self.screen.0.saturating_sub(1)),
+            y.min(self.screen.1.saturating_sub(1)),
+        );
+        self.remainder = (0, 0);
+    }
+
+    /// Returns the state of the left / right buttons at the last update.
+    pub fn buttons(&self) -> (bool, bool) {
+        self.buttons
+    }
+
+    /// Returns the movement on the Z axis accumulated since the last call, in
+    /// device counts.
+    pub fn take_scroll(&mut self) -> i64 {
+        core::mem::replace(&mut self.scroll, 0)
+    }
+
+    /// Move along one axis, returning the new position and the movement which
+    /// was too small to amount to a whole pixel
+    fn move_axis(
+        &self,
+        position: usize,
+        size: usize,
+        remainder: i64,
+        delta: i32,
+        resolution: u64,
+    ) -> (usize, i64) {
+        if resolution == 0 {
+            return (position, remainder);
+        }
+        let resolution = resolution as i64;
+        let scaled = remainder + i64::from(delta) * self.pixels_per_mm as i64;
+        let pixels = scaled / resolution;
+        let remainder = scaled - pixels * resolution;
+
+        let max = size.saturating_sub(1) as i64;
+        let position = (position as i64).saturating_add(pixels);
+        if position < 0 {
+            (0, 0)
+        } else if position > max {
+            (max as usize, 0)
+        } else {
+            (position as usize, remainder)
+        }
+    }
+}
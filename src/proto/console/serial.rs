@@ -1,7 +1,8 @@
 //! Abstraction over byte stream devices, also known as serial I/O devices.
 
+use super::text::{write_utf8, TextOutput};
 use crate::proto::Protocol;
-use crate::{unsafe_guid, Result, Status};
+use crate::{unsafe_guid, CStr16, Result, ResultExt, Status};
 use bitflags::bitflags;
 
 /// Provides access to a serial I/O device.
@@ -113,6 +114,13 @@ impl<'boot> Serial<'boot> {
     }
 }
 
+/// Text is written to serial devices in UTF-8.
+impl TextOutput for Serial<'_> {
+    fn write_str16(&mut self, string: &CStr16) -> Result {
+        write_utf8(string, |bytes| self.write(bytes).discard_errdata())
+    }
+}
+
 /// Structure representing the device's current parameters.
 ///
 /// The default values for all UART-like devices is:
@@ -0,0 +1,176 @@
+use super::Output;
+use crate::{CStr16, Result, ResultExt};
+use core::fmt;
+
+/// Writes text within a rectangular region of a text output device.
+///
+/// Lines are wrapped at the width of the region, and when its last line is
+/// full, the text of the region is scrolled up by one line. As the contents
+/// of the screen cannot be read back, the writer keeps a copy of the text of
+/// the region in a caller-provided buffer, and rewrites the region from it.
+///
+/// The cursor is left where it was by writes, so that several writers can
+/// share a screen, e.g. for a status box next to a log.
+pub struct BoundedWriter<'out, 'boot: 'out, 'buf> {
+    output: &'out mut Output<'boot>,
+    left: usize,
+    top: usize,
+    width: usize,
+    height: usize,
+    cells: &'buf mut [u16],
+    column: usize,
+    row: usize,
+    /// Truth that the last column of the current row was written, so that
+    /// the next printable character goes to the next row, while a line feed
+    /// only ends the current one.
+    pending_wrap: bool,
+    first_dirty_row: Option<usize>,
+}
+
+impl<'out, 'boot, 'buf> BoundedWriter<'out, 'boot, 'buf> {
+    /// Creates a writer for the region of `width` columns and `height` rows
+    /// whose top-left corner is at (`left`, `top`).
+    ///
+    /// The region must fit on the screen, and `cells` must be able to hold
+    /// `width * height` characters. The region is considered to be blank
+    /// initially, call `clear()` to erase what the screen shows there.
+    pub fn new(
+        output: &'out mut Output<'boot>,
+        (left, top): (usize, usize),
+        (width, height): (usize, usize),
+        cells: &'buf mut [u16],
+    ) -> Self {
+        assert!(width > 0 && height > 0, "The region cannot be empty");
+        assert!(
+            cells.len() >= width * height,
+            "The buffer is too small for the region"
+        );
+        for cell in cells.iter_mut() {
+            *cell = BLANK;
+        }
+        BoundedWriter {
+            output,
+            left,
+            top,
+            width,
+            height,
+            cells,
+            column: 0,
+            row: 0,
+            pending_wrap: false,
+            first_dirty_row: None,
+        }
+    }
+
+    /// Returns the characters of a row of the region, as displayed.
+    ///
+    /// The unused part of the row is filled with spaces.
+    pub fn row(&self, row: usize) -> &[u16] {
+        &self.cells[row * self.width..(row + 1) * self.width]
+    }
+
+    /// Blanks the region, and moves back to its top-left corner.
+    pub fn clear(&mut self) -> Result {
+        for cell in self.cells.iter_mut() {
+            *cell = BLANK;
+        }
+        self.column = 0;
+        self.row = 0;
+        self.pending_wrap = false;
+        self.first_dirty_row = Some(0);
+        self.flush()
+    }
+
+    /// Store a character at the current position
+    fn put_char(&mut self, c: char) {
+        match c {
+            '\n' => self.new_line(),
+            '\r' => {
+                self.column = 0;
+                self.pending_wrap = false;
+            }
+            c => {
+                if self.pending_wrap {
+                    self.new_line();
+                }
+                let code = if c >= ' ' && c as u32 <= 0xFFFF {
+                    c as u16
+                } else {
+                    REPLACEMENT
+                };
+                self.cells[self.row * self.width + self.column] = code;
+                if self.column + 1 == self.width {
+                    self.pending_wrap = true;
+                } else {
+                    self.column += 1;
+                }
+                self.mark_dirty(self.row);
+            }
+        }
+    }
+
+    /// Move to the beginning of the next line, scrolling if necessary
+    fn new_line(&mut self) {
+        self.column = 0;
+        self.pending_wrap = false;
+        if self.row + 1 < self.height {
+            self.row += 1;
+            return;
+        }
+
+        let used = self.width * self.height;
+        self.cells.copy_within(self.width..used, 0);
+        for cell in &mut self.cells[used - self.width..used] {
+            *cell = BLANK;
+        }
+        self.first_dirty_row = Some(0);
+    }
+
+    fn mark_dirty(&mut self, row: usize) {
+        self.first_dirty_row = Some(self.first_dirty_row.map_or(row, |first| first.min(row)));
+    }
+
+    /// Rewrite the rows of the region from the first one which changed
+    fn flush(&mut self) -> Result {
+        const CHUNK_SIZE: usize = 32;
+
+        let first = match self.first_dirty_row.take() {
+            Some(first) => first,
+            None => return Ok(().into()),
+        };
+
+        let (left, top, width, height) = (self.left, self.top, self.width, self.height);
+        let cells = &*self.cells;
+        let mut output = self.output.save_cursor();
+        for row in first..height {
+            output
+                .set_cursor_position(left, top + row)
+                .warning_as_error()?;
+            let line = &cells[row * width..(row + 1) * width];
+            for chunk in line.chunks(CHUNK_SIZE) {
+                // Add 1 extra character for the null terminator.
+                let mut buf = [0u16; CHUNK_SIZE + 1];
+                buf[..chunk.len()].copy_from_slice(chunk);
+                // Only printable characters are stored in the cells
+                let text = unsafe { CStr16::from_u16_with_nul_unchecked(&buf[..=chunk.len()]) };
+                output.output_string(text).warning_as_error()?;
+            }
+        }
+        Ok(().into())
+    }
+}
+
+impl fmt::Write for BoundedWriter<'_, '_, '_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.put_char(c);
+        }
+        self.flush().map(|_| ()).map_err(|_| fmt::Error)
+    }
+}
+
+/// Character of the empty cells of a region
+const BLANK: u16 = b' ' as u16;
+
+/// Character displayed in place of those which cannot be displayed
+const REPLACEMENT: u16 = b'?' as u16;
@@ -0,0 +1,100 @@
+//! Buffered formatting of console output.
+
+use super::TextOutput;
+use crate::{CStr16, Result, ResultExt, Status};
+use core::convert::TryFrom;
+use core::fmt;
+use core::mem;
+
+/// Number of characters which are buffered before being written out
+const BUF_SIZE: usize = 128;
+
+/// Batches formatted text before writing it to a text output.
+///
+/// Writing to an `Output` through `fmt::Write` encodes every string passed by
+/// the formatting machinery into a fresh buffer, and issues one firmware call
+/// for each of them. This writer instead accumulates the UCS-2 text in a
+/// buffer which it owns and reuses, translating line feeds to CR LF, and only
+/// writes it out when a line is complete, when the buffer is full, or when the
+/// writer is dropped.
+pub struct BufferedConsole<'out, O: TextOutput + ?Sized> {
+    output: &'out mut O,
+    // Add 1 extra character for the null terminator.
+    buf: [u16; BUF_SIZE + 1],
+    len: usize,
+}
+
+impl<'out, O: TextOutput + ?Sized> BufferedConsole<'out, O> {
+    /// Creates a buffered writer for `output`, with an empty buffer.
+    pub fn new(output: &'out mut O) -> Self {
+        BufferedConsole {
+            output,
+            buf: [0; BUF_SIZE + 1],
+            len: 0,
+        }
+    }
+
+    /// Writes the buffered text to the output, if there is any.
+    pub fn flush(&mut self) -> Result {
+        if self.len == 0 {
+            return Ok(().into());
+        }
+        let len = mem::replace(&mut self.len, 0);
+        self.buf[len] = 0;
+        // Only non-null characters are stored in the buffer
+        let text = unsafe { CStr16::from_u16_with_nul_unchecked(&self.buf[..=len]) };
+        self.output.write_str16(text)
+    }
+
+    /// Adds a character to the buffer, flushing it as necessary.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if UCS-2 cannot represent the character
+    /// - `InvalidParameter` if the character is null
+    /// - Any error reported by the output
+    fn push_char(&mut self, c: char) -> Result {
+        let code = match u16::try_from(u32::from(c)) {
+            Ok(0) => return Err(Status::INVALID_PARAMETER.into()),
+            Ok(code) => code,
+            Err(_) => return Err(Status::UNSUPPORTED.into()),
+        };
+        if c == '\n' {
+            self.push_code('\r' as u16).warning_as_error()?;
+        }
+        self.push_code(code).warning_as_error()?;
+        if c == '\n' {
+            self.flush()
+        } else {
+            Ok(().into())
+        }
+    }
+
+    fn push_code(&mut self, code: u16) -> Result {
+        self.buf[self.len] = code;
+        self.len += 1;
+        if self.len == BUF_SIZE {
+            self.flush()
+        } else {
+            Ok(().into())
+        }
+    }
+}
+
+impl<O: TextOutput + ?Sized> fmt::Write for BufferedConsole<'_, O> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for c in s.chars() {
+            self.push_char(c)
+                .warning_as_error()
+                .map_err(|_| fmt::Error)?;
+        }
+        Ok(())
+    }
+}
+
+impl<O: TextOutput + ?Sized> Drop for BufferedConsole<'_, O> {
+    fn drop(&mut self) {
+        // Errors cannot be reported from here
+        let _ = self.flush();
+    }
+}
@@ -0,0 +1,48 @@
+//! In-memory capture of console output.
+
+use super::output::write_str_with;
+use crate::{CStr16, Result, ResultExt};
+use alloc_api::vec::Vec;
+use core::fmt;
+
+/// Records text in memory instead of displaying it.
+///
+/// This provides the same text writing interface as `Output`, including the
+/// translation of line feeds to CR LF, so that code formatting console output
+/// can be tested by checking the UCS-2 text which it would have displayed.
+#[derive(Debug, Default, Clone)]
+pub struct CaptureOutput {
+    codes: Vec<u16>,
+}
+
+impl CaptureOutput {
+    /// Creates an empty capture buffer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a string to the captured text, as `Output::output_string`
+    /// would display it.
+    pub fn output_string(&mut self, string: &CStr16) -> Result {
+        self.codes.extend_from_slice(string.to_u16_slice());
+        Ok(().into())
+    }
+
+    /// Returns the captured UCS-2 text, without a null terminator.
+    pub fn codes(&self) -> &[u16] {
+        &self.codes
+    }
+
+    /// Discards the captured text.
+    pub fn clear(&mut self) {
+        self.codes.clear();
+    }
+}
+
+impl fmt::Write for CaptureOutput {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        write_str_with(s, |text| self.output_string(text))
+            .warning_as_error()
+            .map_err(|_| fmt::Error)
+    }
+}
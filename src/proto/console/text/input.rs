@@ -42,6 +42,24 @@ impl Input {
         }
     }
 
+    /// Discards all the keystrokes buffered by the input device, returning
+    /// how many of them were discarded.
+    ///
+    /// This is useful before displaying a prompt, so that keys pressed
+    /// earlier (e.g. to validate a previous prompt) are not mistaken for an
+    /// answer to it. Unlike `reset()`, this does not touch the hardware.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input device
+    pub fn drain(&mut self) -> Result<usize> {
+        let mut count = 0;
+        while self.read_key()?.log().is_some() {
+            count += 1;
+        }
+        Ok(count.into())
+    }
+
     /// Event to be used with `BootServices::wait_for_event()` in order to wait
     /// for a key to be available
     pub fn wait_for_key_event(&self) -> Event {
@@ -0,0 +1,218 @@
+use super::input::{Key, RawKey};
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Event, Result, Status};
+use bitflags::bitflags;
+use core::ffi::c_void;
+use core::mem::MaybeUninit;
+use core::ptr;
+
+/// Extended interface for text-based input devices.
+///
+/// In addition to what the `Input` protocol provides, this protocol reports
+/// the state of the modifier keys (shift, control, alt...) and of the toggle
+/// keys (caps lock, num lock...), and allows registering hotkeys.
+#[repr(C)]
+#[unsafe_guid("dd9e7534-7762-4698-8c14-f58517a625aa")]
+#[derive(Protocol)]
+pub struct InputEx {
+    reset: extern "efiapi" fn(this: &mut InputEx, extended: bool) -> Status,
+    read_key_stroke_ex: extern "efiapi" fn(this: &mut InputEx, key: *mut RawKeyData) -> Status,
+    wait_for_key_ex: Event,
+    set_state: extern "efiapi" fn(this: &mut InputEx, toggle_state: &KeyToggleState) -> Status,
+    register_key_notify: extern "efiapi" fn(
+        this: &mut InputEx,
+        key_data: &RawKeyData,
+        notify_fn: KeyNotifyFn,
+        notify_handle: &mut *mut c_void,
+    ) -> Status,
+    unregister_key_notify: extern "efiapi" fn(this: &mut InputEx, handle: *mut c_void) -> Status,
+}
+
+impl InputEx {
+    /// Resets the input device hardware.
+    ///
+    /// The `extended_verification` parameter is used to request that UEFI
+    /// performs an extended check and reset of the input device.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device is malfunctioning and cannot be reset.
+    pub fn reset(&mut self, extended_verification: bool) -> Result {
+        (self.reset)(self, extended_verification).into()
+    }
+
+    /// Reads the next keystroke from the input device, if any, along with the
+    /// state of the modifier and toggle keys.
+    ///
+    /// Use `wait_for_key_event()` with the `BootServices::wait_for_event()`
+    /// interface in order to wait for a key to be pressed.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input device
+    /// - `Unsupported` if the key is not supported by the current keyboard layout
+    pub fn read_key(&mut self) -> Result<Option<KeyData>> {
+        let mut key = MaybeUninit::<RawKeyData>::uninit();
+
+        match (self.read_key_stroke_ex)(self, key.as_mut_ptr()) {
+            Status::NOT_READY => Ok(None.into()),
+            other => other.into_with_val(|| Some(unsafe { key.assume_init() }.into())),
+        }
+    }
+
+    /// Event to be used with `BootServices::wait_for_event()` in order to wait
+    /// for a key to be available
+    pub fn wait_for_key_event(&self) -> Event {
+        self.wait_for_key_ex
+    }
+
+    /// Sets the state of the toggle keys (caps lock, num lock...)
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if there was an issue with the input device
+    /// - `Unsupported` if the device does not support changing this state
+    pub fn set_state(&mut self, toggle_state: KeyToggleState) -> Result {
+        let toggle_state = toggle_state | KeyToggleState::VALID;
+        (self.set_state)(self, &toggle_state).into()
+    }
+
+    /// Registers a function to be called when a given key combination is
+    /// pressed.
+    ///
+    /// The returned handle must be passed to `unregister_key_notify()` in order
+    /// to remove the notification.
+    pub fn register_key_notify(
+        &mut self,
+        key_data: &RawKeyData,
+        notify_fn: KeyNotifyFn,
+    ) -> Result<KeyNotifyHandle> {
+        let mut handle = ptr::null_mut();
+        (self.register_key_notify)(self, key_data, notify_fn, &mut handle)
+            .into_with_val(|| KeyNotifyHandle(handle))
+    }
+
+    /// Removes a key notification registered with `register_key_notify()`.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the handle is not a valid notification handle
+    pub fn unregister_key_notify(&mut self, handle: KeyNotifyHandle) -> Result {
+        (self.unregister_key_notify)(self, handle.0).into()
+    }
+}
+
+/// Function called when a registered key combination is pressed
+pub type KeyNotifyFn = extern "efiapi" fn(key_data: &RawKeyData) -> Status;
+
+/// Handle to a key notification, used to unregister it
+#[derive(Debug)]
+pub struct KeyNotifyHandle(*mut c_void);
+
+/// A key read from the console, with the modifier state (high-level version)
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct KeyData {
+    /// The key which was pressed
+    pub key: Key,
+    /// The state of the modifier and toggle keys when it was pressed
+    pub state: KeyState,
+}
+
+impl From<RawKeyData> for KeyData {
+    fn from(k: RawKeyData) -> KeyData {
+        KeyData {
+            key: k.key.into(),
+            state: k.state,
+        }
+    }
+}
+
+/// A key read from the console, with the modifier state (UEFI version)
+#[repr(C)]
+pub struct RawKeyData {
+    /// The key which was pressed
+    pub key: RawKey,
+    /// The state of the modifier and toggle keys when it was pressed
+    pub state: KeyState,
+}
+
+/// State of the modifier and toggle keys
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct KeyState {
+    /// State of the modifier keys, only meaningful if the `VALID` bit is set
+    pub shift_state: KeyShiftState,
+    /// State of the toggle keys, only meaningful if the `VALID` bit is set
+    pub toggle_state: KeyToggleState,
+}
+
+impl KeyState {
+    /// Truth that a shift key was pressed
+    pub fn shift(&self) -> bool {
+        self.shift_pressed(KeyShiftState::LEFT_SHIFT | KeyShiftState::RIGHT_SHIFT)
+    }
+
+    /// Truth that a control key was pressed
+    pub fn ctrl(&self) -> bool {
+        self.shift_pressed(KeyShiftState::LEFT_CONTROL | KeyShiftState::RIGHT_CONTROL)
+    }
+
+    /// Truth that an alt key was pressed
+    pub fn alt(&self) -> bool {
+        self.shift_pressed(KeyShiftState::LEFT_ALT | KeyShiftState::RIGHT_ALT)
+    }
+
+    /// Truth that caps lock was active
+    pub fn caps_lock(&self) -> bool {
+        self.toggle_state.contains(KeyToggleState::VALID)
+            && self.toggle_state.contains(KeyToggleState::CAPS_LOCK_ACTIVE)
+    }
+
+    fn shift_pressed(&self, keys: KeyShiftState) -> bool {
+        self.shift_state.contains(KeyShiftState::VALID) && self.shift_state.intersects(keys)
+    }
+}
+
+bitflags! {
+    /// State of the modifier keys
+    pub struct KeyShiftState: u32 {
+        /// The other bits of the shift state are valid
+        const VALID = 0x8000_0000;
+        /// The right shift key is pressed
+        const RIGHT_SHIFT = 0x0000_0001;
+        /// The left shift key is pressed
+        const LEFT_SHIFT = 0x0000_0002;
+        /// The right control key is pressed
+        const RIGHT_CONTROL = 0x0000_0004;
+        /// The left control key is pressed
+        const LEFT_CONTROL = 0x0000_0008;
+        /// The right alt key is pressed
+        const RIGHT_ALT = 0x0000_0010;
+        /// The left alt key is pressed
+        const LEFT_ALT = 0x0000_0020;
+        /// The right logo key is pressed
+        const RIGHT_LOGO = 0x0000_0040;
+        /// The left logo key is pressed
+        const LEFT_LOGO = 0x0000_0080;
+        /// The menu key is pressed
+        const MENU_KEY = 0x0000_0100;
+        /// The SysReq key is pressed
+        const SYS_REQ = 0x0000_0200;
+    }
+}
+
+bitflags! {
+    /// State of the toggle keys
+    pub struct KeyToggleState: u8 {
+        /// The other bits of the toggle state are valid
+        const VALID = 0x80;
+        /// Partial keystrokes, such as a lone modifier key press, are reported
+        const KEY_STATE_EXPOSED = 0x40;
+        /// Scroll lock is active
+        const SCROLL_LOCK_ACTIVE = 0x01;
+        /// Num lock is active
+        const NUM_LOCK_ACTIVE = 0x02;
+        /// Caps lock is active
+        const CAPS_LOCK_ACTIVE = 0x04;
+    }
+}
@@ -1,7 +1,31 @@
 //! Text I/O.
 
+mod bounded;
+pub use self::bounded::BoundedWriter;
+
+mod buffered;
+pub use self::buffered::BufferedConsole;
+
+#[cfg(feature = "exts")]
+mod capture;
+#[cfg(feature = "exts")]
+pub use self::capture::CaptureOutput;
+
 mod input;
-pub use self::input::{Input, Key, ScanCode};
+pub use self::input::{Input, Key, RawKey, ScanCode};
+
+mod input_ex;
+pub use self::input_ex::{
+    InputEx, KeyData, KeyNotifyFn, KeyNotifyHandle, KeyShiftState, KeyState, KeyToggleState,
+    RawKeyData,
+};
 
 mod output;
-pub use self::output::{Color, Output, OutputMode};
+pub use self::output::{AttributeGuard, Color, CursorGuard, CursorState, Output, OutputMode};
+
+mod progress;
+pub use self::progress::{ProgressBar, Spinner};
+
+mod sink;
+pub(crate) use self::sink::write_utf8;
+pub use self::sink::TextOutput;
@@ -1,7 +1,9 @@
 use crate::prelude::*;
 use crate::proto::Protocol;
+use crate::result::Error;
 use crate::{unsafe_guid, CStr16, Char16, Completion, Result, Status};
 use core::fmt;
+use core::ops::{Deref, DerefMut};
 
 /// Interface for text-based output devices.
 ///
@@ -47,6 +49,16 @@ impl<'boot> Output<'boot> {
         unsafe { (self.output_string)(self, string.as_ptr()) }.into()
     }
 
+    /// Rings the console bell, by writing the BEL control character.
+    ///
+    /// This can be used to signal rejected input in prompts. Many firmwares
+    /// silently ignore this character, or report it as an unknown glyph with
+    /// a warning, so nothing should be assumed about what the user noticed.
+    pub fn beep(&mut self) -> Result {
+        let bel = unsafe { CStr16::from_u16_with_nul_unchecked(&[0x07, 0]) };
+        self.output_string(bel)
+    }
+
     /// Checks if a string contains only supported characters.
     ///
     /// UEFI applications are encouraged to try to print a string even if it contains
@@ -69,6 +81,24 @@ impl<'boot> Output<'boot> {
         }
     }
 
+    /// Returns an iterator of all supported text modes, after checking that
+    /// they can all be queried.
+    ///
+    /// `modes()` silently skips the modes which cannot be queried, whereas
+    /// this function reports the first failure. Modes which the device reports
+    /// as `Unsupported`, like mode 1 on devices which lack an 80x50 mode, are
+    /// not considered to be failures.
+    pub fn try_modes<'out>(&'out mut self) -> Result<OutputModeIter<'out, 'boot>> {
+        let max = self.data.max_mode as usize;
+        for index in 0..max {
+            match self.query_mode(index) {
+                Err(e) if e.status() != Status::UNSUPPORTED => return Err(e),
+                _ => {}
+            }
+        }
+        Ok(self.modes().into())
+    }
+
     /// Returns the width (column count) and height (row count) of a text mode.
     ///
     /// Devices are required to support at least an 80x25 text mode and to
@@ -88,7 +118,7 @@ impl<'boot> Output<'boot> {
     pub fn current_mode(&self) -> Result<Option<OutputMode>> {
         match self.data.mode {
             -1 => Ok(None.into()),
-            n if n > 0 => {
+            n if n >= 0 => {
                 let index = n as usize;
                 self.query_mode(index)
                     .map_inner(|dims| Some(OutputMode { index, dims }))
@@ -102,6 +132,28 @@ impl<'boot> Output<'boot> {
         (self.set_mode)(self, mode.index).into()
     }
 
+    /// Brings the console back to a defined state after a mode change, by
+    /// checking that a valid mode is current and moving the cursor to the
+    /// top-left corner of the screen.
+    ///
+    /// Unlike `reset()`, this does not clear the screen, which avoids flicker
+    /// in applications which change modes frequently and redraw the screen
+    /// anyway. Prefer `reset()` when the device itself may be misbehaving, or
+    /// when the previous contents of the screen must not remain visible.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device does not report a valid current mode
+    /// - `Unsupported` if the current mode cannot be queried
+    pub fn sync(&mut self) -> Result {
+        let (status, mode) = self.current_mode()?.split();
+        if mode.is_none() {
+            return Err(Status::DEVICE_ERROR.into());
+        }
+        self.set_cursor_position(0, 0)
+            .map(|position| position.with_status(status))
+    }
+
     /// Returns whether the cursor is currently shown or not.
     pub fn cursor_visible(&self) -> bool {
         self.data.cursor_visible
@@ -109,9 +161,25 @@ impl<'boot> Output<'boot> {
 
     /// Make the cursor visible or invisible.
     ///
+    /// Nothing is done if the cursor visibility already is the requested one,
+    /// as reported by `cursor_visible()`, which spares firmware calls in
+    /// redraw loops.
+    ///
     /// The output device may not support this operation, in which case an
     /// `Unsupported` error will be returned.
     pub fn enable_cursor(&mut self, visible: bool) -> Result {
+        if self.cursor_visible() == visible {
+            return Ok(().into());
+        }
+        self.force_enable_cursor(visible)
+    }
+
+    /// Make the cursor visible or invisible, even if `cursor_visible()` says
+    /// that it already is.
+    ///
+    /// This is meant for output devices which do not keep track of the cursor
+    /// visibility accurately. The errors are those of `enable_cursor`.
+    pub fn force_enable_cursor(&mut self, visible: bool) -> Result {
         (self.enable_cursor)(self, visible).into()
     }
 
@@ -129,6 +197,44 @@ impl<'boot> Output<'boot> {
         (self.set_cursor_position)(self, column, row).into()
     }
 
+    /// Returns the position and visibility of the cursor.
+    pub fn cursor_state(&self) -> CursorState {
+        let (column, row) = self.cursor_position();
+        CursorState {
+            column,
+            row,
+            visible: self.cursor_visible(),
+        }
+    }
+
+    /// Moves the cursor back to a previously saved position and visibility.
+    ///
+    /// The visibility is only changed if it differs from the current one, so
+    /// restoring a state does not fail on devices which cannot toggle it
+    /// unless the cursor visibility was changed in the meantime.
+    pub fn restore_cursor(&mut self, state: CursorState) -> Result {
+        let position = self.set_cursor_position(state.column, state.row)?;
+        if self.cursor_visible() != state.visible {
+            self.enable_cursor(state.visible)
+                .map(|visibility| position.with_status(visibility.status()))
+        } else {
+            Ok(position)
+        }
+    }
+
+    /// Saves the cursor state, which will be restored when the returned guard
+    /// is dropped.
+    ///
+    /// The guard dereferences to this output device, so that it can be used to
+    /// move the cursor around in the meantime.
+    pub fn save_cursor<'out>(&'out mut self) -> CursorGuard<'out, 'boot> {
+        let state = self.cursor_state();
+        CursorGuard {
+            output: self,
+            state,
+        }
+    }
+
     /// Sets the text and background colors for the console.
     ///
     /// Note that for the foreground color you can choose any color.
@@ -142,58 +248,187 @@ impl<'boot> Output<'boot> {
         let attr = ((bgc & 0x7) << 4) | (fgc & 0xF);
         (self.set_attribute)(self, attr).into()
     }
+
+    /// Returns the current text attribute, which encodes the foreground color
+    /// in bits 0..4 and the background color in bits 4..7.
+    pub fn attribute(&self) -> usize {
+        self.data.attribute as usize
+    }
+
+    /// Sets the text and background colors for the console, which will be
+    /// restored to their current values when the returned guard is dropped.
+    ///
+    /// The guard dereferences to this output device, so that color changes can
+    /// be nested, e.g. to highlight part of a colored error message. The same
+    /// constraints as for `set_color()` apply to the colors.
+    pub fn with_color<'out>(
+        &'out mut self,
+        foreground: Color,
+        background: Color,
+    ) -> Result<AttributeGuard<'out, 'boot>> {
+        let attribute = self.attribute();
+        let completion = self.set_color(foreground, background)?;
+        Ok(completion.map(move |()| AttributeGuard {
+            output: self,
+            attribute,
+        }))
+    }
 }
 
 impl<'boot> fmt::Write for Output<'boot> {
     fn write_str(&mut self, s: &str) -> fmt::Result {
-        // Allocate a small buffer on the stack.
-        const BUF_SIZE: usize = 128;
-        // Add 1 extra character for the null terminator.
-        let mut buf = [0u16; BUF_SIZE + 1];
-
-        let mut i = 0;
-
-        // This closure writes the local buffer to the output and resets the buffer.
-        let mut flush_buffer = |buf: &mut [u16], i: &mut usize| {
-            buf[*i] = 0;
-            let codes = &buf[..=*i];
-            *i = 0;
-
-            let text = CStr16::from_u16_with_nul(codes).map_err(|_| fmt::Error)?;
-
-            self.output_string(text)
-                .warning_as_error()
-                .map_err(|_| fmt::Error)
-        };
-
-        // This closure converts a character to UCS-2 and adds it to the buffer,
-        // flushing it as necessary.
-        let mut add_char = |ch| {
-            // UEFI only supports UCS-2 characters, not UTF-16,
-            // so there are no multibyte characters.
-            buf[i] = ch;
-            i += 1;
-
-            if i == BUF_SIZE {
-                flush_buffer(&mut buf, &mut i).map_err(|_| ucs2::Error::BufferOverflow)
-            } else {
-                Ok(())
-            }
-        };
+        write_str_with(s, |text| self.output_string(text))
+            .warning_as_error()
+            .map_err(|_| fmt::Error)
+    }
+}
 
-        // This one converts Rust line feeds to UEFI line feeds beforehand
-        let add_ch = |ch| {
-            if ch == '\n' as u16 {
-                add_char('\r' as u16)?;
-            }
-            add_char(ch)
-        };
+/// Converts a Rust string to UCS-2, translating line feeds to CR LF, and
+/// passes it to `output_string` in chunks of bounded size.
+///
+/// Characters which UCS-2 cannot represent are reported as `Unsupported`, and
+/// null characters as `InvalidParameter`. Warnings are treated as errors.
+pub(super) fn write_str_with(s: &str, mut output_string: impl FnMut(&CStr16) -> Result) -> Result {
+    // Allocate a small buffer on the stack.
+    const BUF_SIZE: usize = 128;
+    // Add 1 extra character for the null terminator.
+    let mut buf = [0u16; BUF_SIZE + 1];
+
+    let mut i = 0;
+    let mut failure = None;
+
+    // This closure writes the local buffer to the output and resets the buffer.
+    let mut flush_buffer = |buf: &mut [u16], i: &mut usize| {
+        buf[*i] = 0;
+        let codes = &buf[..=*i];
+        *i = 0;
+
+        let text =
+            CStr16::from_u16_with_nul(codes).map_err(|_| Error::from(Status::INVALID_PARAMETER))?;
+
+        output_string(text).warning_as_error()
+    };
+
+    // This closure converts a character to UCS-2 and adds it to the buffer,
+    // flushing it as necessary.
+    let mut add_char = |ch| {
+        // UEFI only supports UCS-2 characters, not UTF-16,
+        // so there are no multibyte characters.
+        buf[i] = ch;
+        i += 1;
+
+        if i == BUF_SIZE {
+            flush_buffer(&mut buf, &mut i).map_err(|err| {
+                failure = Some(err);
+                ucs2::Error::BufferOverflow
+            })
+        } else {
+            Ok(())
+        }
+    };
+
+    // This one converts Rust line feeds to UEFI line feeds beforehand
+    let add_ch = |ch| {
+        if ch == '\n' as u16 {
+            add_char('\r' as u16)?;
+        }
+        add_char(ch)
+    };
+
+    // Translate and write the input string, flushing the buffer when needed
+    if ucs2::encode_with(s, add_ch).is_err() {
+        return Err(failure.unwrap_or_else(|| Status::UNSUPPORTED.into()));
+    }
+
+    // Flush the remainder of the buffer
+    flush_buffer(&mut buf, &mut i)?;
+    Ok(().into())
+}
+
+/// RAII guard for text attribute changes
+///
+/// Will automatically restore the former text and background colors when
+/// dropped.
+pub struct AttributeGuard<'out, 'boot: 'out> {
+    output: &'out mut Output<'boot>,
+    attribute: usize,
+}
+
+impl<'out, 'boot> AttributeGuard<'out, 'boot> {
+    /// Returns the text attribute which will be restored.
+    pub fn saved_attribute(&self) -> usize {
+        self.attribute
+    }
+}
+
+impl<'out, 'boot> Deref for AttributeGuard<'out, 'boot> {
+    type Target = Output<'boot>;
+
+    fn deref(&self) -> &Self::Target {
+        self.output
+    }
+}
 
-        // Translate and write the input string, flushing the buffer when needed
-        ucs2::encode_with(s, add_ch).map_err(|_| fmt::Error)?;
+impl<'out, 'boot> DerefMut for AttributeGuard<'out, 'boot> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.output
+    }
+}
+
+impl Drop for AttributeGuard<'_, '_> {
+    fn drop(&mut self) {
+        // There is no way to report a failure from a destructor, and the
+        // console is still usable if the colors could not be restored.
+        let _ = (self.output.set_attribute)(self.output, self.attribute);
+    }
+}
+
+/// The position and visibility of the cursor, as saved by `cursor_state()`.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct CursorState {
+    /// The cursor's column.
+    pub column: usize,
+    /// The cursor's row.
+    pub row: usize,
+    /// Whether the cursor is visible or not.
+    pub visible: bool,
+}
+
+/// RAII guard for cursor state changes
+///
+/// Will automatically restore the former cursor position and visibility when
+/// dropped.
+pub struct CursorGuard<'out, 'boot: 'out> {
+    output: &'out mut Output<'boot>,
+    state: CursorState,
+}
+
+impl<'out, 'boot> CursorGuard<'out, 'boot> {
+    /// Returns the cursor state which will be restored.
+    pub fn saved_state(&self) -> CursorState {
+        self.state
+    }
+}
+
+impl<'out, 'boot> Deref for CursorGuard<'out, 'boot> {
+    type Target = Output<'boot>;
+
+    fn deref(&self) -> &Self::Target {
+        self.output
+    }
+}
+
+impl<'out, 'boot> DerefMut for CursorGuard<'out, 'boot> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        self.output
+    }
+}
 
-        // Flush the remainder of the buffer
-        flush_buffer(&mut buf, &mut i)
+impl Drop for CursorGuard<'_, '_> {
+    fn drop(&mut self) {
+        // There is no way to report a failure from a destructor, and the
+        // console is still usable if the cursor could not be restored.
+        let _ = self.output.restore_cursor(self.state);
     }
 }
 
@@ -0,0 +1,124 @@
+//! Progress indicators drawn on a text output device.
+
+use super::Output;
+use crate::{CStr16, Completion, Result, ResultExt};
+
+/// Characters successively displayed by a `Spinner`
+const SPINNER_FRAMES: [char; 4] = ['|', '/', '-', '\\'];
+
+/// A spinning indicator, showing that a task of unknown duration is ongoing.
+///
+/// The spinner is drawn at the cursor position, which it leaves untouched.
+#[derive(Debug, Default)]
+pub struct Spinner {
+    frame: usize,
+}
+
+impl Spinner {
+    /// Creates a spinner, which will start from its first frame.
+    pub fn new() -> Self {
+        Spinner { frame: 0 }
+    }
+
+    /// Draws the next frame of the spinner at the cursor position.
+    pub fn tick(&mut self, output: &mut Output) -> Result {
+        let c = SPINNER_FRAMES[self.frame];
+        self.frame = (self.frame + 1) % SPINNER_FRAMES.len();
+        let mut output = output.save_cursor();
+        write_chars(&mut output, core::iter::once(c))
+    }
+}
+
+/// A progress bar, drawn as `[####    ]  50%` at the start of a given row.
+///
+/// The cursor is left untouched by redraws. If the screen is too narrow for
+/// the bar, it is truncated so that the screen never scrolls.
+#[derive(Debug, Copy, Clone)]
+pub struct ProgressBar {
+    row: usize,
+    width: usize,
+}
+
+impl ProgressBar {
+    /// Creates a progress bar drawn on `row`, with `width` cells between the
+    /// brackets.
+    pub fn new(row: usize, width: usize) -> Self {
+        ProgressBar { row, width }
+    }
+
+    /// Redraws the progress bar, with `done` out of `total` steps completed.
+    ///
+    /// Progress beyond `total` is shown as 100%, as is any progress if `total`
+    /// is 0.
+    pub fn update(&self, output: &mut Output, done: u64, total: u64) -> Result {
+        let percent = if total == 0 || done >= total {
+            100
+        } else {
+            (u128::from(done) * 100 / u128::from(total)) as usize
+        };
+        let filled = self.width * percent / 100;
+        let label = [
+            ' ',
+            digit(percent, 100),
+            digit(percent, 10),
+            char::from(b'0' + (percent % 10) as u8),
+            '%',
+        ];
+
+        // Writing to the last column may move the cursor to the next line,
+        // and scroll the screen if this was the last one.
+        let columns = output
+            .current_mode()
+            .warning_as_error()?
+            .map_or(usize::MAX, |mode| mode.columns().saturating_sub(1));
+
+        let bar = core::iter::once('[')
+            .chain(core::iter::repeat('#').take(filled))
+            .chain(core::iter::repeat(' ').take(self.width - filled))
+            .chain(core::iter::once(']'))
+            .chain(label.iter().copied())
+            .take(columns);
+
+        let mut output = output.save_cursor();
+        output.set_cursor_position(0, self.row).warning_as_error()?;
+        write_chars(&mut output, bar)
+    }
+}
+
+/// Digit of `value` for `place` (a power of 10), or a space if it is a
+/// leading zero
+fn digit(value: usize, place: usize) -> char {
+    if value >= place {
+        char::from(b'0' + (value / place % 10) as u8)
+    } else {
+        ' '
+    }
+}
+
+/// Writes ASCII characters at the cursor position, without translating
+/// line feeds.
+fn write_chars(output: &mut Output, chars: impl Iterator<Item = char>) -> Result {
+    const CHUNK_SIZE: usize = 32;
+    // Add 1 extra character for the null terminator.
+    let mut buf = [0u16; CHUNK_SIZE + 1];
+    let mut len = 0;
+    let mut chars = chars.fuse();
+
+    loop {
+        let next = chars.next();
+        if let Some(c) = next {
+            buf[len] = c as u16;
+            len += 1;
+        }
+        if len > 0 && (len == CHUNK_SIZE || next.is_none()) {
+            buf[len] = 0;
+            let text = CStr16::from_u16_with_nul(&buf[..=len])
+                .expect("Progress indicators only use printable ASCII");
+            output.output_string(text).warning_as_error()?;
+            len = 0;
+        }
+        if next.is_none() {
+            return Ok(Completion::from(()));
+        }
+    }
+}
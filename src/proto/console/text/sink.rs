@@ -0,0 +1,94 @@
+//! Abstraction over the destinations of text output.
+
+use super::output::write_str_with;
+use super::Output;
+use crate::{CStr16, Result};
+
+/// A destination for text, such as a console.
+///
+/// This allows text writing code, like the `log` integration, to work with
+/// other destinations than the firmware console, e.g. a serial port or an
+/// in-memory capture buffer.
+pub trait TextOutput {
+    /// Writes a null-terminated UCS-2 string.
+    fn write_str16(&mut self, string: &CStr16) -> Result;
+
+    /// Writes a Rust string, translating line feeds to CR LF.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the string contains characters which UCS-2 cannot
+    ///   represent
+    /// - `InvalidParameter` if the string contains null characters
+    /// - Any error reported by `write_str16`, including warnings
+    fn write_str(&mut self, s: &str) -> Result {
+        write_str_with(s, |text| self.write_str16(text))
+    }
+
+    /// Writes a number in upper-case hexadecimal, without prefix or leading
+    /// zeros.
+    ///
+    /// Unlike `write!`, this does not involve the `core::fmt` machinery, which
+    /// makes it suitable for constrained paths such as panic handlers.
+    fn write_hex(&mut self, value: u64) -> Result {
+        write_digits(self, value, 16)
+    }
+
+    /// Writes a number in decimal, without leading zeros.
+    ///
+    /// Like `write_hex()`, this does not involve the `core::fmt` machinery.
+    fn write_dec(&mut self, value: u64) -> Result {
+        write_digits(self, value, 10)
+    }
+}
+
+/// Writes the digits of `value` in base `radix`, from a stack buffer
+fn write_digits<O: TextOutput + ?Sized>(out: &mut O, mut value: u64, radix: u64) -> Result {
+    // Enough for the 20 decimal digits of `u64::MAX`, and a null terminator
+    let mut buf = [0u16; 21];
+    let mut start = buf.len() - 1;
+    loop {
+        let digit = (value % radix) as u8;
+        start -= 1;
+        buf[start] = u16::from(if digit < 10 {
+            b'0' + digit
+        } else {
+            b'A' + digit - 10
+        });
+        value /= radix;
+        if value == 0 {
+            break;
+        }
+    }
+    out.write_str16(unsafe { CStr16::from_u16_with_nul_unchecked(&buf[start..]) })
+}
+
+impl TextOutput for Output<'_> {
+    fn write_str16(&mut self, string: &CStr16) -> Result {
+        self.output_string(string)
+    }
+}
+
+/// Writes a UCS-2 string to a byte stream, encoded as UTF-8, through
+/// `write` calls of bounded size
+pub(crate) fn write_utf8(string: &CStr16, mut write: impl FnMut(&[u8]) -> Result) -> Result {
+    let mut buf = [0; 128];
+    let mut len = 0;
+    for &code in string.to_u16_slice() {
+        // UCS-2 has no surrogate pairs, so lone surrogates are invalid
+        let c = core::char::from_u32(code.into()).unwrap_or('\u{FFFD}');
+        if len + c.len_utf8() > buf.len() {
+            write(&buf[..len])?.log();
+            len = 0;
+        }
+        len += c.encode_utf8(&mut buf[len..]).len();
+    }
+    write(&buf[..len])
+}
+
+#[cfg(feature = "exts")]
+impl TextOutput for super::CaptureOutput {
+    fn write_str16(&mut self, string: &CStr16) -> Result {
+        self.output_string(string)
+    }
+}
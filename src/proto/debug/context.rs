@@ -0,0 +1,79 @@
+//! Architecture-specific processor state, as passed to debug callbacks.
+
+/// Processor state of a x86_64 CPU
+#[allow(missing_docs)]
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct SystemContextX64 {
+    /// Error code pushed by the processor for some exceptions
+    pub exception_data: u64,
+    /// State of the x87 FPU and SSE units, in FXSAVE format
+    pub fx_save_state: [u8; 512],
+    pub dr0: u64,
+    pub dr1: u64,
+    pub dr2: u64,
+    pub dr3: u64,
+    pub dr6: u64,
+    pub dr7: u64,
+    pub cr0: u64,
+    /// Reserved
+    pub cr1: u64,
+    pub cr2: u64,
+    pub cr3: u64,
+    pub cr4: u64,
+    pub cr8: u64,
+    pub rflags: u64,
+    pub ldtr: u64,
+    pub tr: u64,
+    pub gdtr: [u64; 2],
+    pub idtr: [u64; 2],
+    pub rip: u64,
+    pub gs: u64,
+    pub fs: u64,
+    pub es: u64,
+    pub ds: u64,
+    pub cs: u64,
+    pub ss: u64,
+    pub rdi: u64,
+    pub rsi: u64,
+    pub rbp: u64,
+    pub rsp: u64,
+    pub rbx: u64,
+    pub rdx: u64,
+    pub rcx: u64,
+    pub rax: u64,
+    pub r8: u64,
+    pub r9: u64,
+    pub r10: u64,
+    pub r11: u64,
+    pub r12: u64,
+    pub r13: u64,
+    pub r14: u64,
+    pub r15: u64,
+}
+
+/// Processor state of an AArch64 CPU
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct SystemContextAArch64 {
+    /// General purpose registers X0 to X28
+    pub x: [u64; 29],
+    /// Frame pointer (X29)
+    pub fp: u64,
+    /// Link register (X30)
+    pub lr: u64,
+    /// Stack pointer
+    pub sp: u64,
+    /// Floating point / SIMD registers V0 to V31, as (low, high) halves
+    pub v: [[u64; 2]; 32],
+    /// Exception link register
+    pub elr: u64,
+    /// Saved program status register
+    pub spsr: u64,
+    /// Floating point status register
+    pub fpsr: u64,
+    /// Exception syndrome register
+    pub esr: u64,
+    /// Fault address register
+    pub far: u64,
+}
@@ -10,7 +10,15 @@
 //! [udk]: https://firmware.intel.com/develop/intel-uefi-tools-and-utilities/intel-uefi-development-kit-debugger-tool
 
 use crate::proto::Protocol;
-use crate::unsafe_guid;
+use crate::{unsafe_guid, Result, ResultExt, Status};
+use core::ffi::c_void;
+use core::fmt;
+
+mod context;
+pub use self::context::{SystemContextAArch64, SystemContextX64};
+
+mod port;
+pub use self::port::DebugPort;
 
 /// The debugging support protocol allows debuggers to connect to a UEFI machine.
 #[repr(C)]
@@ -18,8 +26,17 @@ use crate::unsafe_guid;
 #[derive(Protocol)]
 pub struct DebugSupport {
     isa: ProcessorArch,
-    // FIXME: Add the mising parts of the interface. Beware that it features
-    //        unsafety in the form of an unchecked processor index.
+    get_maximum_processor_index:
+        extern "efiapi" fn(this: &mut DebugSupport, max_processor_index: &mut usize) -> Status,
+    register_periodic_callback: usize,
+    register_exception_callback: extern "efiapi" fn(
+        this: &mut DebugSupport,
+        processor_index: usize,
+        exception_callback: Option<ExceptionCallback>,
+        exception_type: ExceptionType,
+    ) -> Status,
+    // FIXME: Add the mising parts of the interface.
+    invalidate_instruction_cache: usize,
 }
 
 impl DebugSupport {
@@ -27,8 +44,158 @@ impl DebugSupport {
     pub fn arch(&self) -> ProcessorArch {
         self.isa
     }
+
+    /// Returns the maximum value that may be used for the processor index
+    /// parameter of the other functions of this protocol.
+    pub fn get_maximum_processor_index(&mut self) -> Result<usize> {
+        let mut max_processor_index = 0;
+        (self.get_maximum_processor_index)(self, &mut max_processor_index)
+            .into_with_val(|| max_processor_index)
+    }
+
+    /// Checks that a processor index is valid for this protocol
+    ///
+    /// Firmwares are not required to validate processor indices themselves,
+    /// so this must be done before passing them to the protocol.
+    fn check_processor_index(&mut self, processor_index: usize) -> Result {
+        let (status, max_processor_index) = self.get_maximum_processor_index()?.split();
+        if processor_index > max_processor_index {
+            Err(Status::INVALID_PARAMETER.into())
+        } else {
+            Ok(status.into())
+        }
+    }
+
+    /// Registers a function to be called when a given processor exception
+    /// occurs, or unregisters the current one if `callback` is `None`.
+    ///
+    /// The callback is called using the native calling convention, with a
+    /// system context whose layout depends on the `arch()` of this protocol.
+    /// Registration is therefore refused with `Status::UNSUPPORTED` if that
+    /// architecture is not the one this code was compiled for, as the callback
+    /// would misinterpret the system context.
+    ///
+    /// The one legitimate exception is the debug support of the EFI Byte Code
+    /// interpreter: it calls native callbacks with an EBC system context, and
+    /// is thus accepted on every architecture.
+    ///
+    /// # Errors
+    ///
+    /// * `uefi::Status::UNSUPPORTED` - The protocol's processor architecture
+    ///   does not match the one of the running code.
+    /// * `uefi::Status::INVALID_PARAMETER` - `processor_index` is greater than
+    ///   `get_maximum_processor_index()`, which is checked before calling the
+    ///   firmware.
+    /// * `uefi::Status::ALREADY_STARTED` - A callback was already registered
+    ///   for this exception type.
+    ///
+    /// # Safety
+    ///
+    /// The callback is invoked in exception context, where it must neither
+    /// call boot services nor otherwise rely on the interrupted code's state.
+    pub unsafe fn register_exception_callback(
+        &mut self,
+        processor_index: usize,
+        callback: Option<ExceptionCallback>,
+        exception_type: ExceptionType,
+    ) -> Result {
+        if !self.isa.accepts_native_callbacks() {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        self.check_processor_index(processor_index)
+            .warning_as_error()?;
+        (self.register_exception_callback)(self, processor_index, callback, exception_type).into()
+    }
 }
 
+/// Function called when a processor exception occurs
+pub type ExceptionCallback =
+    extern "efiapi" fn(exception_type: ExceptionType, system_context: SystemContext);
+
+/// Pointer to the architecture-specific processor state at the time of a
+/// debug event
+///
+/// The layout of the pointed-to state depends on the `DebugSupport::arch()` of
+/// the protocol which invoked the callback. Changes made to the state are
+/// applied to the processor when the callback returns.
+///
+/// The UEFI debug support protocol does not provide any other way to access
+/// the registers of a processor than registering a callback.
+#[derive(Debug, Copy, Clone)]
+#[repr(transparent)]
+pub struct SystemContext(*mut c_void);
+
+impl SystemContext {
+    /// Access the raw pointer to the processor state
+    pub fn as_ptr(self) -> *mut c_void {
+        self.0
+    }
+
+    /// Access the processor state of a x86_64 CPU
+    ///
+    /// # Safety
+    ///
+    /// The context must have been passed to a callback by a `DebugSupport`
+    /// protocol whose `arch()` is `ProcessorArch::X86_64`, and may only be
+    /// accessed until the callback returns.
+    pub unsafe fn x64<'ctx>(self) -> &'ctx mut SystemContextX64 {
+        &mut *(self.0 as *mut SystemContextX64)
+    }
+
+    /// Access the processor state of an AArch64 CPU
+    ///
+    /// # Safety
+    ///
+    /// The context must have been passed to a callback by a `DebugSupport`
+    /// protocol whose `arch()` is `ProcessorArch::AARCH_64`, and may only be
+    /// accessed until the callback returns.
+    pub unsafe fn aarch64<'ctx>(self) -> &'ctx mut SystemContextAArch64 {
+        &mut *(self.0 as *mut SystemContextAArch64)
+    }
+}
+
+newtype_enum! {
+/// The type of a processor exception.
+///
+/// The meaning of exception types is architecture-specific. The constants
+/// defined here are those of the x86 architectures.
+pub enum ExceptionType: isize => {
+    /// Division by zero
+    DIVIDE_ERROR    = 0,
+    /// Debug exception (e.g. single-stepping)
+    DEBUG           = 1,
+    /// Non-maskable interrupt
+    NMI             = 2,
+    /// Breakpoint instruction
+    BREAKPOINT      = 3,
+    /// Arithmetic overflow
+    OVERFLOW        = 4,
+    /// BOUND range exceeded
+    BOUND           = 5,
+    /// Invalid opcode
+    INVALID_OPCODE  = 6,
+    /// Double fault
+    DOUBLE_FAULT    = 8,
+    /// Invalid task state segment
+    INVALID_TSS     = 10,
+    /// Segment not present
+    SEG_NOT_PRESENT = 11,
+    /// Stack segment fault
+    STACK_FAULT     = 12,
+    /// General protection fault
+    GP_FAULT        = 13,
+    /// Page fault
+    PAGE_FAULT      = 14,
+    /// x87 floating point error
+    FP_ERROR        = 16,
+    /// Alignment check
+    ALIGNMENT_CHECK = 17,
+    /// Machine check
+    MACHINE_CHECK   = 18,
+    /// SIMD floating point exception
+    SIMD            = 19,
+}}
+
 newtype_enum! {
 /// The instruction set architecture of the running processor.
 ///
@@ -55,3 +222,77 @@ pub enum ProcessorArch: u32 => {
     /// RISC-V 128-bit
     RISCV_128   = 0x5128,
 }}
+
+impl ProcessorArch {
+    /// Returns all the architectures which UEFI defines.
+    pub fn all() -> &'static [ProcessorArch] {
+        &[
+            ProcessorArch::X86_32,
+            ProcessorArch::X86_64,
+            ProcessorArch::ITANIUM,
+            ProcessorArch::EBC,
+            ProcessorArch::ARM,
+            ProcessorArch::AARCH_64,
+            ProcessorArch::RISCV_32,
+            ProcessorArch::RISCV_64,
+            ProcessorArch::RISCV_128,
+        ]
+    }
+
+    /// Returns the architecture which this code was compiled for.
+    ///
+    /// On architectures which UEFI does not define, `ProcessorArch(0)` is
+    /// returned, which no `DebugSupport` protocol reports.
+    pub fn current() -> ProcessorArch {
+        #[cfg(target_arch = "x86")]
+        let arch = ProcessorArch::X86_32;
+        #[cfg(target_arch = "x86_64")]
+        let arch = ProcessorArch::X86_64;
+        #[cfg(target_arch = "arm")]
+        let arch = ProcessorArch::ARM;
+        #[cfg(target_arch = "aarch64")]
+        let arch = ProcessorArch::AARCH_64;
+        #[cfg(target_arch = "riscv32")]
+        let arch = ProcessorArch::RISCV_32;
+        #[cfg(target_arch = "riscv64")]
+        let arch = ProcessorArch::RISCV_64;
+        #[cfg(not(any(
+            target_arch = "x86",
+            target_arch = "x86_64",
+            target_arch = "arm",
+            target_arch = "aarch64",
+            target_arch = "riscv32",
+            target_arch = "riscv64"
+        )))]
+        let arch = ProcessorArch(0);
+        arch
+    }
+
+    /// Truth that debug callbacks compiled for the current architecture can be
+    /// registered with a `DebugSupport` protocol of this architecture.
+    ///
+    /// See `DebugSupport::register_exception_callback()` for details.
+    pub fn accepts_native_callbacks(self) -> bool {
+        self == ProcessorArch::current() || self == ProcessorArch::EBC
+    }
+}
+
+/// Architectures are displayed with their usual names, which are meant to
+/// remain the same across releases.
+impl fmt::Display for ProcessorArch {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            ProcessorArch::X86_32 => "x86",
+            ProcessorArch::X86_64 => "x86-64",
+            ProcessorArch::ITANIUM => "Itanium",
+            ProcessorArch::EBC => "EFI Byte Code",
+            ProcessorArch::ARM => "ARM",
+            ProcessorArch::AARCH_64 => "AArch64",
+            ProcessorArch::RISCV_32 => "RISC-V 32-bit",
+            ProcessorArch::RISCV_64 => "RISC-V 64-bit",
+            ProcessorArch::RISCV_128 => "RISC-V 128-bit",
+            ProcessorArch(isa) => return write!(f, "Unknown({:#x})", isa),
+        };
+        f.write_str(name)
+    }
+}
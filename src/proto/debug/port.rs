@@ -0,0 +1,94 @@
+//! Debugport protocol.
+
+use crate::proto::console::text::{write_utf8, TextOutput};
+use crate::proto::Protocol;
+use crate::{unsafe_guid, CStr16, Result, ResultExt, Status};
+use core::ffi::c_void;
+
+/// Communication channel with a debugger, usually over a serial link.
+///
+/// This protocol implements `TextOutput`, so that it can be used as the
+/// destination of the `log` integration: log records then remain visible in
+/// the debugger while the screen is in graphics mode.
+#[repr(C)]
+#[unsafe_guid("eba4e8d2-3858-41ec-a281-2647ba9660d0")]
+#[derive(Protocol)]
+pub struct DebugPort {
+    reset: extern "efiapi" fn(this: &mut DebugPort) -> Status,
+    write: unsafe extern "efiapi" fn(
+        this: &mut DebugPort,
+        timeout: u32,
+        buffer_size: &mut usize,
+        buffer: *const c_void,
+    ) -> Status,
+    read: unsafe extern "efiapi" fn(
+        this: &mut DebugPort,
+        timeout: u32,
+        buffer_size: &mut usize,
+        buffer: *mut c_void,
+    ) -> Status,
+    poll: extern "efiapi" fn(this: &mut DebugPort) -> Status,
+}
+
+/// Time allowed for each write of text, in microseconds
+const TEXT_TIMEOUT: u32 = 10_000;
+
+impl DebugPort {
+    /// Resets the debug port.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device is malfunctioning
+    pub fn reset(&mut self) -> Result {
+        (self.reset)(self).into()
+    }
+
+    /// Writes data to the debug port, waiting at most `timeout` microseconds
+    /// between bytes.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device is malfunctioning
+    /// - `Timeout` if the data could not be written in time, along with the
+    ///   number of bytes which were written
+    pub fn write(&mut self, timeout: u32, data: &[u8]) -> Result<(), usize> {
+        let mut size = data.len();
+        unsafe { (self.write)(self, timeout, &mut size, data.as_ptr().cast()) }
+            .into_with_err(|_| size)
+    }
+
+    /// Reads data from the debug port, waiting at most `timeout` microseconds
+    /// between bytes.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device is malfunctioning
+    /// - `Timeout` if `data` could not be filled in time, along with the
+    ///   number of bytes which were read
+    pub fn read(&mut self, timeout: u32, data: &mut [u8]) -> Result<(), usize> {
+        let mut size = data.len();
+        unsafe { (self.read)(self, timeout, &mut size, data.as_mut_ptr().cast()) }
+            .into_with_err(|_| size)
+    }
+
+    /// Truth that data is available for reading.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the device is malfunctioning
+    pub fn poll(&mut self) -> Result<bool> {
+        match (self.poll)(self) {
+            Status::NOT_READY => Ok(false.into()),
+            other => other.into_with_val(|| true),
+        }
+    }
+}
+
+/// Text is written to the debugger in UTF-8.
+impl TextOutput for DebugPort {
+    fn write_str16(&mut self, string: &CStr16) -> Result {
+        write_utf8(string, |bytes| {
+            self.write(TEXT_TIMEOUT, bytes).discard_errdata()
+        })
+    }
+}
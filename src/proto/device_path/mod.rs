@@ -0,0 +1,366 @@
+//! Device path protocol.
+//!
+//! A device path describes the location of a device, as a sequence of nodes
+//! going from the root of the system (e.g. a PCI root bridge) down to the
+//! device itself (e.g. a partition of a disk), followed by an end node. Each
+//! node starts with a header giving its type and its length in bytes, and is
+//! immediately followed in memory by the next node.
+
+use crate::proto::Protocol;
+use crate::table::boot::{BootServices, MemoryType, PoolBox};
+use crate::{unsafe_guid, CStr16, Guid, Result, ResultExt, Status};
+use core::convert::TryFrom;
+use core::ptr::{self, NonNull};
+use core::slice;
+
+/// A device path, as provided by the device path protocol.
+///
+/// This type only describes the first node of the path, the other nodes are
+/// found in memory right after it. Device paths must therefore only be
+/// accessed by reference.
+#[repr(C)]
+#[unsafe_guid("09576e91-6d3f-11d2-8e39-00a0c969723b")]
+#[derive(Protocol)]
+pub struct DevicePath {
+    first: DevicePathNode,
+}
+
+impl DevicePath {
+    /// Returns an iterator over the nodes of this path, excluding the end
+    /// node.
+    pub fn node_iter(&self) -> DevicePathNodeIter<'_> {
+        DevicePathNodeIter {
+            next: Some(&self.first),
+            count: 0,
+        }
+    }
+
+    /// Returns the number of nodes in this path, excluding the end node.
+    pub fn num_nodes(&self) -> usize {
+        self.node_iter().count()
+    }
+
+    /// Returns the size of this path in bytes, including the end node.
+    ///
+    /// This is the amount of memory needed to copy the path. `None` is
+    /// returned if the path is malformed, i.e. if one of its nodes is shorter
+    /// than a node header, or if its end node is not found among its first
+    /// thousand nodes.
+    pub fn byte_len(&self) -> Option<usize> {
+        let mut node = &self.first;
+        let mut len = 0;
+        for _ in 0..=MAX_NODES {
+            if node.length() < DevicePathNode::HEADER_SIZE {
+                return None;
+            }
+            if node.is_end_entire() {
+                return Some(len + DevicePathNode::HEADER_SIZE);
+            }
+            len += node.length();
+            node = unsafe { node.following() };
+        }
+        None
+    }
+
+    /// Truth that the nodes of `prefix` are the first nodes of this path.
+    ///
+    /// Every path starts with itself and with the empty path.
+    pub fn starts_with(&self, prefix: &DevicePath) -> bool {
+        let mut nodes = self.node_iter();
+        prefix.node_iter().all(|prefix_node| {
+            nodes.next().map(DevicePathNode::as_bytes) == Some(prefix_node.as_bytes())
+        })
+    }
+}
+
+/// Paths are equal if they are made of the same nodes, byte for byte.
+impl PartialEq for DevicePath {
+    fn eq(&self, other: &DevicePath) -> bool {
+        self.node_iter()
+            .map(DevicePathNode::as_bytes)
+            .eq(other.node_iter().map(DevicePathNode::as_bytes))
+    }
+}
+
+impl Eq for DevicePath {}
+
+/// A node of a device path.
+///
+/// This type only describes the header of the node, its data is found in
+/// memory right after it.
+#[derive(Debug)]
+#[repr(C)]
+pub struct DevicePathNode {
+    device_type: DeviceType,
+    sub_type: u8,
+    length: [u8; 2],
+}
+
+impl DevicePathNode {
+    /// Size of the node header, which is the minimal length of a node
+    const HEADER_SIZE: usize = 4;
+
+    /// Returns the type of this node.
+    pub fn device_type(&self) -> DeviceType {
+        self.device_type
+    }
+
+    /// Returns the sub-type of this node, whose meaning depends on its type.
+    pub fn sub_type(&self) -> u8 {
+        self.sub_type
+    }
+
+    /// Returns the length of this node in bytes, header included.
+    pub fn length(&self) -> usize {
+        usize::from(u16::from_le_bytes(self.length))
+    }
+
+    /// Truth that this node marks the end of a device path.
+    pub fn is_end_entire(&self) -> bool {
+        self.device_type == DeviceType::END && self.sub_type == END_ENTIRE
+    }
+
+    /// Returns the node which follows this one in memory.
+    ///
+    /// # Safety
+    ///
+    /// This node must not be the end node of a path, and must be at least as
+    /// long as its header.
+    unsafe fn following(&self) -> &DevicePathNode {
+        &*(self as *const Self as *const u8)
+            .add(self.length())
+            .cast::<DevicePathNode>()
+    }
+
+    /// Returns the whole node as bytes, header included.
+    ///
+    /// Nodes are deemed at least as long as their header.
+    pub fn as_bytes(&self) -> &[u8] {
+        let len = self.length().max(Self::HEADER_SIZE);
+        unsafe { slice::from_raw_parts(self as *const Self as *const u8, len) }
+    }
+
+    /// Returns the data which follows the node header.
+    pub fn data(&self) -> &[u8] {
+        &self.as_bytes()[Self::HEADER_SIZE..]
+    }
+
+    /// Parses this node as a hard drive partition node.
+    ///
+    /// Returns `None` if this is not a `MEDIA` / `HARD_DRIVE` node, if it is
+    /// too short, or if its signature type is unknown.
+    pub fn as_hard_drive(&self) -> Option<HardDriveDevicePath> {
+        if self.device_type != DeviceType::MEDIA || self.sub_type != HARD_DRIVE {
+            return None;
+        }
+        let data = self.data();
+        if data.len() < HardDriveDevicePath::DATA_SIZE {
+            return None;
+        }
+        let u32_at = |i: usize| u32::from_le_bytes(<[u8; 4]>::try_from(&data[i..i + 4]).unwrap());
+        let u64_at = |i: usize| u64::from_le_bytes(<[u8; 8]>::try_from(&data[i..i + 8]).unwrap());
+        let raw_signature = &data[20..36];
+        let signature = match data[37] {
+            0 => PartitionSignature::None,
+            1 => PartitionSignature::Mbr(u32_at(20)),
+            2 => PartitionSignature::Gpt(unsafe {
+                ptr::read_unaligned(raw_signature.as_ptr().cast::<Guid>())
+            }),
+            _ => return None,
+        };
+        Some(HardDriveDevicePath {
+            partition_number: u32_at(0),
+            partition_start: u64_at(4),
+            partition_size: u64_at(12),
+            partition_format: PartitionFormat(data[36]),
+            signature,
+        })
+    }
+}
+
+/// Iterator over the nodes of a device path, see `DevicePath::node_iter()`
+#[derive(Debug)]
+pub struct DevicePathNodeIter<'path> {
+    next: Option<&'path DevicePathNode>,
+    count: usize,
+}
+
+/// Number of nodes after which a path is deemed malformed. Real paths are
+/// much shorter, this prevents walking through memory forever when the end
+/// node is missing.
+const MAX_NODES: usize = 1024;
+
+impl<'path> Iterator for DevicePathNodeIter<'path> {
+    type Item = &'path DevicePathNode;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let node = self.next.take()?;
+        // A node shorter than its header would make the iteration go round in
+        // circles, treat it as the end of a malformed path.
+        if node.is_end_entire()
+            || node.length() < DevicePathNode::HEADER_SIZE
+            || self.count == MAX_NODES
+        {
+            return None;
+        }
+        self.count += 1;
+        self.next = Some(unsafe { node.following() });
+        Some(node)
+    }
+}
+
+newtype_enum! {
+/// The type of a device path node
+pub enum DeviceType: u8 => {
+    /// Hardware device, e.g. a PCI device or a memory-mapped device
+    HARDWARE        = 0x01,
+    /// ACPI device, e.g. a PCI root bridge
+    ACPI            = 0x02,
+    /// Device reached through a messaging protocol, e.g. USB, SATA, or a
+    /// network protocol
+    MESSAGING       = 0x03,
+    /// Part of a storage medium, e.g. a partition or a file
+    MEDIA           = 0x04,
+    /// Boot device described by the legacy BIOS boot specification
+    BIOS_BOOT_SPEC  = 0x05,
+    /// End of a device path, or of one instance of a multi-instance path
+    END             = 0x7F,
+}}
+
+/// Sub-type of the `END` node which terminates a device path
+pub const END_ENTIRE: u8 = 0xFF;
+
+/// Sub-type of the `END` node which separates the instances of a
+/// multi-instance device path
+pub const END_INSTANCE: u8 = 0x01;
+
+/// Sub-type of the `MEDIA` node describing a partition of a hard drive
+pub const HARD_DRIVE: u8 = 0x01;
+
+/// Sub-type of the `MEDIA` node holding a file path
+pub const FILE_PATH: u8 = 0x04;
+
+/// A hard drive partition node, as parsed by `DevicePathNode::as_hard_drive()`
+///
+/// This is the node which identifies the partition a file system lives on,
+/// and thus allows finding e.g. the partition with a given GPT unique GUID.
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub struct HardDriveDevicePath {
+    partition_number: u32,
+    partition_start: u64,
+    partition_size: u64,
+    partition_format: PartitionFormat,
+    signature: PartitionSignature,
+}
+
+impl HardDriveDevicePath {
+    /// Size of the node data, header excluded
+    const DATA_SIZE: usize = 38;
+
+    /// Returns the number of the partition, starting at 1. Zero designates
+    /// the whole device.
+    pub fn partition_number(&self) -> u32 {
+        self.partition_number
+    }
+
+    /// Returns the first logical block of the partition.
+    pub fn partition_start(&self) -> u64 {
+        self.partition_start
+    }
+
+    /// Returns the size of the partition, in logical blocks.
+    pub fn partition_size(&self) -> u64 {
+        self.partition_size
+    }
+
+    /// Returns the format of the partition table.
+    pub fn partition_format(&self) -> PartitionFormat {
+        self.partition_format
+    }
+
+    /// Returns the signature identifying the partition.
+    pub fn signature(&self) -> PartitionSignature {
+        self.signature
+    }
+}
+
+newtype_enum! {
+/// Format of the partition table of a hard drive
+pub enum PartitionFormat: u8 => {
+    /// Legacy PC-AT compatible master boot record
+    MBR = 0x01,
+    /// GUID partition table
+    GPT = 0x02,
+}}
+
+/// Signature of a hard drive partition
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum PartitionSignature {
+    /// The partition has no signature
+    None,
+    /// 32-bit signature taken from the master boot record of the disk
+    Mbr(u32),
+    /// Unique GUID of a GPT partition
+    Gpt(Guid),
+}
+
+/// Builds a new device path made of the nodes of `base` followed by `node`,
+/// in memory allocated from a pool.
+///
+/// This is typically used to build the full path of a file, by appending a
+/// node built by `file_path_node()` to the path of a file system.
+///
+/// # Errors
+///
+/// - `InvalidParameter` if `base` is malformed, see `DevicePath::byte_len()`
+pub fn append<'boot>(
+    bt: &'boot BootServices,
+    base: &DevicePath,
+    node: &DevicePathNode,
+) -> Result<PoolBox<'boot, DevicePath>> {
+    let base_len = match base.byte_len() {
+        Some(len) => len - DevicePathNode::HEADER_SIZE,
+        None => return Err(Status::INVALID_PARAMETER.into()),
+    };
+    let node = node.as_bytes();
+    let size = base_len + node.len() + DevicePathNode::HEADER_SIZE;
+    bt.allocate_pool(MemoryType::LOADER_DATA, size)
+        .map_inner(|buffer| unsafe {
+            ptr::copy_nonoverlapping(&base.first as *const _ as *const u8, buffer, base_len);
+            ptr::copy_nonoverlapping(node.as_ptr(), buffer.add(base_len), node.len());
+            let end = [DeviceType::END.0, END_ENTIRE, 4, 0];
+            ptr::copy_nonoverlapping(end.as_ptr(), buffer.add(base_len + node.len()), 4);
+            PoolBox::from_raw(bt, NonNull::new_unchecked(buffer.cast()))
+        })
+}
+
+/// Builds a media node holding the path of a file, in memory allocated from
+/// a pool.
+///
+/// File paths are relative to the file system which the preceding nodes
+/// designate, and use backslashes as separators.
+///
+/// # Errors
+///
+/// - `InvalidParameter` if the file path is too long to fit in a node
+pub fn file_path_node<'boot>(
+    bt: &'boot BootServices,
+    path: &CStr16,
+) -> Result<PoolBox<'boot, DevicePathNode>> {
+    let path = path.to_u16_slice_with_nul();
+    let size = DevicePathNode::HEADER_SIZE + 2 * path.len();
+    let length = match u16::try_from(size) {
+        Ok(length) => length.to_le_bytes(),
+        Err(_) => return Err(Status::INVALID_PARAMETER.into()),
+    };
+    bt.allocate_pool(MemoryType::LOADER_DATA, size)
+        .map_inner(|buffer| unsafe {
+            let header = [DeviceType::MEDIA.0, FILE_PATH, length[0], length[1]];
+            ptr::copy_nonoverlapping(header.as_ptr(), buffer, header.len());
+            for (i, code) in path.iter().enumerate() {
+                let offset = DevicePathNode::HEADER_SIZE + 2 * i;
+                ptr::copy_nonoverlapping(code.to_le_bytes().as_ptr(), buffer.add(offset), 2);
+            }
+            PoolBox::from_raw(bt, NonNull::new_unchecked(buffer.cast()))
+        })
+}
@@ -48,8 +48,11 @@ pub enum LoadOptionsError {
 impl LoadedImage {
     /// Get the load options of the given image. If the image was executed from the EFI shell, or from a boot
     /// option, this is the command line that was used to execute it as a string.
+    ///
+    /// An image which was started without load options gets an empty string.
     pub fn load_options<'a>(&self, buffer: &'a mut [u8]) -> Result<&'a str, LoadOptionsError> {
-        let ucs2_slice = unsafe { CStr16::from_ptr(self.load_options).to_u16_slice() };
+        let ucs2_slice = unsafe { CStr16::from_ptr_opt(self.load_options) }
+            .map_or(&[][..], |options| options.to_u16_slice());
         let length =
             ucs2::decode(ucs2_slice, buffer).map_err(|_| LoadOptionsError::BufferTooSmall)?;
         core::str::from_utf8(&buffer[0..length]).map_err(|_| LoadOptionsError::NotValidUtf8)
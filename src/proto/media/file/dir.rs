@@ -1,4 +1,4 @@
-use super::{File, FileHandle, FileInfo, FromUefi, RegularFile};
+use super::{File, FileAttribute, FileHandle, FileInfo, FileMode, FromUefi, RegularFile};
 use crate::data_types::Align;
 use crate::prelude::*;
 use crate::Result;
@@ -61,6 +61,32 @@ impl Directory {
     pub fn reset_entry_readout(&mut self) -> Result {
         self.0.set_position(0)
     }
+
+    /// Opens an existing file in this directory for reading only.
+    ///
+    /// This is `File::open()` with `FileMode::Read` and no attributes, and
+    /// fails in the same ways.
+    pub fn open_read(&mut self, filename: &str) -> Result<FileHandle> {
+        self.open(filename, FileMode::Read, FileAttribute::empty())
+    }
+
+    /// Opens an existing file in this directory for reading and writing.
+    ///
+    /// This is `File::open()` with `FileMode::ReadWrite` and no attributes,
+    /// and fails in the same ways.
+    pub fn open_read_write(&mut self, filename: &str) -> Result<FileHandle> {
+        self.open(filename, FileMode::ReadWrite, FileAttribute::empty())
+    }
+
+    /// Opens a file in this directory for reading and writing, creating it
+    /// with the given attributes if it does not exist.
+    ///
+    /// Pass `FileAttribute::DIRECTORY` to create a directory. This is
+    /// `File::open()` with `FileMode::CreateReadWrite`, and fails in the same
+    /// ways.
+    pub fn create(&mut self, filename: &str, attributes: FileAttribute) -> Result<FileHandle> {
+        self.open(filename, FileMode::CreateReadWrite, attributes)
+    }
 }
 
 impl File for Directory {
@@ -225,6 +225,30 @@ impl FileInfo {
         &self.header.modification_time
     }
 
+    /// Time when the file was created, if known
+    ///
+    /// Unlike `create_time()`, this returns `None` when the file system did
+    /// not record this time.
+    pub fn created(&self) -> Option<Time> {
+        recorded_time(&self.header.create_time)
+    }
+
+    /// Time when the file was last accessed, if known
+    ///
+    /// Unlike `last_access_time()`, this returns `None` when the file system
+    /// did not record this time.
+    pub fn accessed(&self) -> Option<Time> {
+        recorded_time(&self.header.last_access_time)
+    }
+
+    /// Time when the file's contents were last modified, if known
+    ///
+    /// Unlike `modification_time()`, this returns `None` when the file system
+    /// did not record this time.
+    pub fn modified(&self) -> Option<Time> {
+        recorded_time(&self.header.modification_time)
+    }
+
     /// Attribute bits for the file
     pub fn attribute(&self) -> FileAttribute {
         self.header.attribute
@@ -238,6 +262,16 @@ impl FileInfo {
 
 impl FileProtocolInfo for FileInfo {}
 
+/// Filters out the all-zero times which file systems use for unrecorded times
+fn recorded_time(time: &Time) -> Option<Time> {
+    // No valid time has a zero month or day
+    if time.year() == 0 && time.month() == 0 && time.day() == 0 {
+        None
+    } else {
+        Some(*time)
+    }
+}
+
 /// System volume information
 ///
 /// May only be obtained on the root directory's file handle.
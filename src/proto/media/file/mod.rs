@@ -43,6 +43,7 @@ pub trait File: Sized {
     ///
     /// # Errors
     /// * `uefi::Status::INVALID_PARAMETER`  The filename exceeds the maximum length of 255 chars
+    /// * `uefi::Status::INVALID_PARAMETER`  The attributes are not accepted by the open mode
     /// * `uefi::Status::NOT_FOUND`          Could not find file
     /// * `uefi::Status::NO_MEDIA`           The device has no media
     /// * `uefi::Status::MEDIA_CHANGED`      The device has a different medium in it
@@ -59,7 +60,7 @@ pub trait File: Sized {
         attributes: FileAttribute,
     ) -> Result<FileHandle> {
         const BUF_SIZE: usize = 255;
-        if filename.len() > BUF_SIZE {
+        if filename.len() > BUF_SIZE || !open_mode.accepts(attributes) {
             Err(Status::INVALID_PARAMETER.into())
         } else {
             let mut buf = [0u16; BUF_SIZE + 1];
@@ -333,6 +334,20 @@ pub enum FileMode {
     CreateReadWrite = (1 << 63) | 2 | 1,
 }
 
+impl FileMode {
+    /// Truth that `attributes` can be passed to `File::open` along with this
+    /// mode.
+    ///
+    /// Attributes are only used when a file is created, so they must be empty
+    /// for the other modes. Reserved attribute bits must never be set.
+    pub fn accepts(self, attributes: FileAttribute) -> bool {
+        match self {
+            FileMode::CreateReadWrite => FileAttribute::VALID_ATTR.contains(attributes),
+            FileMode::Read | FileMode::ReadWrite => attributes.is_empty(),
+        }
+    }
+}
+
 bitflags! {
     /// Attributes describing the properties of a file on the file system.
     pub struct FileAttribute: u64 {
@@ -342,6 +357,8 @@ bitflags! {
         const HIDDEN = 1 << 1;
         /// System file, indicates this file is an internal operating system file.
         const SYSTEM = 1 << 2;
+        /// Reserved by the UEFI specification, must not be set.
+        const RESERVED = 1 << 3;
         /// This file is a directory.
         const DIRECTORY = 1 << 4;
         /// This file is compressed.
@@ -1,5 +1,13 @@
+#[cfg(feature = "exts")]
+use super::FileInfo;
 use super::{File, FileHandle, FileInternal};
+#[cfg(feature = "exts")]
+use crate::ResultExt;
 use crate::{Result, Status};
+#[cfg(feature = "exts")]
+use alloc_api::vec::Vec;
+#[cfg(feature = "exts")]
+use core::convert::TryFrom;
 
 /// A `FileHandle` that is also a regular (data) file.
 ///
@@ -97,6 +105,36 @@ impl RegularFile {
     pub fn set_position(&mut self, position: u64) -> Result {
         (self.imp().set_position)(self.imp(), position).into()
     }
+
+    /// Read the whole contents of the file into a newly allocated buffer
+    ///
+    /// The file is read from the beginning, regardless of the current position,
+    /// which is left at the end of the file. The buffer is sized according to
+    /// the file size reported by `FileInfo`, and reads are repeated until it
+    /// is full or until the end of the file is reached.
+    ///
+    /// # Errors
+    /// * `uefi::Status::BAD_BUFFER_SIZE`    The file is too large to fit in memory
+    /// * The errors of `get_boxed_info()`, `set_position()` and `read()`
+    #[cfg(feature = "exts")]
+    pub fn read_to_vec(&mut self) -> Result<Vec<u8>> {
+        let info = self.get_boxed_info::<FileInfo>()?.log();
+        let size = usize::try_from(info.file_size())?;
+        self.set_position(0)?.log();
+
+        let mut buffer = alloc_api::vec![0; size];
+        let mut len = 0;
+        while len < size {
+            let read = self.read(&mut buffer[len..]).discard_errdata()?.log();
+            if read == 0 {
+                // The file shrank since its size was queried
+                break;
+            }
+            len += read;
+        }
+        buffer.truncate(len);
+        Ok(buffer.into())
+    }
 }
 
 impl File for RegularFile {
@@ -0,0 +1,4 @@
+//! Miscellaneous protocols.
+
+pub mod rng;
+pub mod timestamp;
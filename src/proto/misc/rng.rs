@@ -0,0 +1,143 @@
+//! Random number generator protocol.
+
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Guid, Result, Status};
+use core::{mem, ptr};
+use log::warn;
+
+/// Provides random numbers, from a hardware source or a seeded generator.
+#[repr(C)]
+#[unsafe_guid("3152bca5-eade-433d-862e-c01cdc291f44")]
+#[derive(Protocol)]
+pub struct Rng {
+    get_info: unsafe extern "efiapi" fn(
+        this: &mut Rng,
+        algorithm_list_size: &mut usize,
+        algorithm_list: *mut Guid,
+    ) -> Status,
+    get_rng: unsafe extern "efiapi" fn(
+        this: &mut Rng,
+        algorithm: *const Guid,
+        value_length: usize,
+        value: *mut u8,
+    ) -> Status,
+}
+
+impl Rng {
+    /// Lists the algorithms supported by the generator into `algorithms`,
+    /// and returns their number.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if `algorithms` is too small, the number of
+    ///   supported algorithms is then returned as error data
+    /// - `Unsupported` if the algorithms cannot be listed
+    /// - `DeviceError` if the hardware failed
+    pub fn get_info(&mut self, algorithms: &mut [Guid]) -> Result<usize, Option<usize>> {
+        let mut size = mem::size_of_val(algorithms);
+        let status = unsafe { (self.get_info)(self, &mut size, algorithms.as_mut_ptr()) };
+        let count = size / mem::size_of::<Guid>();
+        status.into_with(
+            || count,
+            |status| {
+                if status == Status::BUFFER_TOO_SMALL {
+                    Some(count)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Fills `buffer` with random bytes, produced by `algorithm` or by the
+    /// default algorithm of the generator if none is given.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the algorithm is not supported
+    /// - `DeviceError` if the random bytes could not be produced
+    /// - `NotReady` if not enough entropy is available yet
+    pub fn get_rng(&mut self, algorithm: Option<&Guid>, buffer: &mut [u8]) -> Result {
+        let algorithm = algorithm.map_or(ptr::null(), |guid| guid as *const Guid);
+        unsafe { (self.get_rng)(self, algorithm, buffer.len(), buffer.as_mut_ptr()) }.into()
+    }
+}
+
+/// The core interface of random number generators, modeled after the
+/// `RngCore` trait of the `rand` crate
+pub trait RngCore {
+    /// Returns the next random `u32`
+    fn next_u32(&mut self) -> u32;
+
+    /// Returns the next random `u64`
+    fn next_u64(&mut self) -> u64;
+
+    /// Fills `dest` with random bytes
+    fn fill_bytes(&mut self, dest: &mut [u8]);
+}
+
+/// Number of random bytes which `BufferedRng` fetches at once
+const RNG_BUFFER_SIZE: usize = 64;
+
+/// Adapter making the `Rng` protocol usable as a `RngCore`.
+///
+/// Random bytes are fetched from the firmware in blocks, rather than with a
+/// protocol call per value.
+///
+/// # Security
+///
+/// `RngCore` methods cannot fail, so if the firmware fails to produce random
+/// bytes, zeros are returned instead and a warning is logged. Do not use this
+/// adapter to generate secrets without checking that the generator works.
+pub struct BufferedRng<'rng> {
+    rng: &'rng mut Rng,
+    buffer: [u8; RNG_BUFFER_SIZE],
+    pos: usize,
+}
+
+impl<'rng> BufferedRng<'rng> {
+    /// Wraps a random number generator, using its default algorithm
+    pub fn new(rng: &'rng mut Rng) -> Self {
+        BufferedRng {
+            rng,
+            buffer: [0; RNG_BUFFER_SIZE],
+            pos: RNG_BUFFER_SIZE,
+        }
+    }
+
+    /// Refill the buffer with fresh random bytes
+    fn refill(&mut self) {
+        if let Err(e) = self.rng.get_rng(None, &mut self.buffer) {
+            warn!("Random number generator failed: {:?}", e.status());
+            self.buffer = [0; RNG_BUFFER_SIZE];
+        }
+        self.pos = 0;
+    }
+}
+
+impl RngCore for BufferedRng<'_> {
+    fn next_u32(&mut self) -> u32 {
+        let mut bytes = [0; 4];
+        self.fill_bytes(&mut bytes);
+        u32::from_le_bytes(bytes)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        let mut bytes = [0; 8];
+        self.fill_bytes(&mut bytes);
+        u64::from_le_bytes(bytes)
+    }
+
+    fn fill_bytes(&mut self, dest: &mut [u8]) {
+        let mut filled = 0;
+        while filled < dest.len() {
+            if self.pos == RNG_BUFFER_SIZE {
+                self.refill();
+            }
+            let count = (dest.len() - filled).min(RNG_BUFFER_SIZE - self.pos);
+            dest[filled..filled + count].copy_from_slice(&self.buffer[self.pos..self.pos + count]);
+            filled += count;
+            self.pos += count;
+        }
+    }
+}
@@ -0,0 +1,64 @@
+//! Timestamp protocol.
+
+use crate::proto::Protocol;
+use crate::{unsafe_guid, Result, Status};
+use core::mem::MaybeUninit;
+
+/// Provides a platform-wide, monotonically increasing counter.
+///
+/// The counter counts up from 0 to `TimestampProperties::end_value`, and then
+/// wraps around, at a constant `TimestampProperties::frequency`.
+#[repr(C)]
+#[unsafe_guid("afbfde41-2e6e-4262-ba65-62b9236e5495")]
+#[derive(Protocol)]
+pub struct Timestamp {
+    get_timestamp: extern "efiapi" fn() -> u64,
+    get_properties: extern "efiapi" fn(properties: *mut TimestampProperties) -> Status,
+}
+
+impl Timestamp {
+    /// Returns the current value of the counter.
+    pub fn get_timestamp(&self) -> u64 {
+        (self.get_timestamp)()
+    }
+
+    /// Returns the frequency and the maximal value of the counter.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the timestamp counter is not available
+    pub fn get_properties(&self) -> Result<TimestampProperties> {
+        let mut properties = MaybeUninit::<TimestampProperties>::uninit();
+        (self.get_properties)(properties.as_mut_ptr())
+            .into_with_val(|| unsafe { properties.assume_init() })
+    }
+}
+
+/// Properties of the timestamp counter
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+#[repr(C)]
+pub struct TimestampProperties {
+    /// Frequency of the counter in Hz
+    pub frequency: u64,
+    /// Value of the counter right before it wraps around to 0
+    pub end_value: u64,
+}
+
+impl TimestampProperties {
+    /// Number of ticks elapsed between two counter values, assuming that the
+    /// counter wrapped around at most once in between.
+    pub fn ticks_between(&self, start: u64, end: u64) -> u64 {
+        if end >= start {
+            end - start
+        } else {
+            (self.end_value - start) + end + 1
+        }
+    }
+
+    /// Number of ticks which the counter takes to advance by `micros`
+    /// microseconds, rounded up.
+    pub fn micros_to_ticks(&self, micros: u64) -> u64 {
+        let ticks = (u128::from(micros) * u128::from(self.frequency) + 999_999) / 1_000_000;
+        ticks as u64
+    }
+}
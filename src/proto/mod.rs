@@ -8,7 +8,9 @@
 //! and are usually retrieved from a standard UEFI table or
 //! by querying a handle.
 
-use crate::Identify;
+use crate::table::boot::BootServices;
+use crate::{Guid, Handle, Identify, Result, Status};
+use core::cell::UnsafeCell;
 
 /// Common trait implemented by all standard UEFI protocols
 ///
@@ -22,12 +24,43 @@ use crate::Identify;
 /// #[derive(Protocol)]
 /// struct DummyProtocol {}
 /// ```
-pub trait Protocol: Identify {}
+pub trait Protocol: Identify {
+    /// Returns the GUID of this protocol
+    ///
+    /// This is the same as `Self::GUID`, but can be called on a value, e.g.
+    /// to log which protocol a generic function is operating on.
+    fn guid(&self) -> Guid {
+        Self::GUID
+    }
+}
+
+/// Opens the protocol `P` on `handle`, if the handle supports it.
+///
+/// Unlike `BootServices::handle_protocol()`, this reports a handle which does
+/// not support the protocol as `None` rather than as an error, so that the
+/// handles of the system can easily be processed with whichever of the known
+/// protocols they support.
+///
+/// # Errors
+///
+/// - `InvalidParameter` if `handle` is not a valid handle
+pub fn open_if<P: Protocol>(bt: &BootServices, handle: Handle) -> Result<Option<&UnsafeCell<P>>> {
+    match bt.handle_protocol::<P>(handle) {
+        Ok(protocol) => Ok(protocol.map(Some)),
+        Err(err) if err.status() == Status::UNSUPPORTED || err.status() == Status::NOT_FOUND => {
+            Ok(None.into())
+        }
+        Err(err) => Err(err),
+    }
+}
 
 pub use uefi_macros::Protocol;
 
 pub mod console;
 pub mod debug;
+pub mod device_path;
 pub mod loaded_image;
 pub mod media;
+pub mod misc;
 pub mod pi;
+pub mod string;
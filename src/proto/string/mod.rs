@@ -0,0 +1,6 @@
+//! String protocols.
+//!
+//! The protocols provide some string operations like
+//! lexical comparison.
+
+pub mod unicode_collation;
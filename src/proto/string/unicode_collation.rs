@@ -0,0 +1,63 @@
+//! The Unicode Collation Protocol.
+//!
+//! Used to perform case-insensitive comparisons of strings, using the
+//! language-specific rules of the firmware.
+
+use crate::proto::Protocol;
+use crate::{unsafe_guid, CStr16, CStr8, Char16, Char8};
+use core::cmp::Ordering;
+
+/// Provides lexical comparison functions, as required to match file names on
+/// FAT file systems, which are case-insensitive.
+#[repr(C)]
+#[unsafe_guid("a4c751fc-23ae-4c3e-92e9-4964cf63f349")]
+#[derive(Protocol)]
+pub struct UnicodeCollation {
+    stri_coll:
+        extern "efiapi" fn(this: &UnicodeCollation, s1: *const Char16, s2: *const Char16) -> isize,
+    metai_match: extern "efiapi" fn(
+        this: &UnicodeCollation,
+        string: *const Char16,
+        pattern: *const Char16,
+    ) -> bool,
+    str_lwr: usize,
+    str_upr: usize,
+    fat_to_str: usize,
+    str_to_fat: usize,
+    supported_languages: *const Char8,
+}
+
+impl UnicodeCollation {
+    /// Performs a case insensitive comparison of two
+    /// null-terminated strings.
+    pub fn stri_coll(&self, s1: &CStr16, s2: &CStr16) -> Ordering {
+        let order = (self.stri_coll)(self, s1.as_ptr(), s2.as_ptr());
+        order.cmp(&0)
+    }
+
+    /// Performs a case insensitive comparison between a null terminated
+    /// pattern string and a null terminated string.
+    ///
+    /// This function checks if character pattern described in `pattern`
+    /// is found in `string`. If the pattern match succeeds, true is returned.
+    /// Otherwise, false is returned.
+    ///
+    /// The following syntax can be used to build the string `pattern`:
+    ///
+    /// |Pattern Character            |Meaning                                           |
+    /// |-----------------------------|--------------------------------------------------|
+    /// |*                            | Match 0 or more characters                       |
+    /// |?                            | Match any one character                          |
+    /// |[`char1` `char2`...`charN`]  | Match any character in the set                   |
+    /// |[`char1`-`char2`]            | Match any character between `char1` and `char2`  |
+    /// |`char`                       | Match the character `char`                       |
+    pub fn metai_match(&self, s: &CStr16, pattern: &CStr16) -> bool {
+        (self.metai_match)(self, s.as_ptr(), pattern.as_ptr())
+    }
+
+    /// Returns the languages supported by this protocol instance, as a list of
+    /// RFC 4646 language codes separated by semicolons.
+    pub fn supported_languages(&self) -> &CStr8 {
+        unsafe { CStr8::from_ptr(self.supported_languages) }
+    }
+}
@@ -1,5 +1,6 @@
 use super::Status;
 use core::fmt::Debug;
+use core::num::TryFromIntError;
 
 /// Errors emitted from UEFI entry point must propagate erronerous UEFI statuses,
 /// and may optionally propagate additional entry point-specific data.
@@ -36,6 +37,12 @@ impl From<Status> for Error<()> {
     }
 }
 
+impl From<TryFromIntError> for Error<()> {
+    fn from(other: TryFromIntError) -> Self {
+        Status::from(other).into()
+    }
+}
+
 // FIXME: This conversion will go away along with usage of the ucs2 crate
 
 impl From<ucs2::Error> for Error<()> {
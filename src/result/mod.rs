@@ -10,7 +10,9 @@
 ///!
 ///! Convenience methods are also provided via extension traits to ease working
 ///! with this complex type in everyday usage.
-use core::fmt::Debug;
+use crate::table::runtime::ResetType;
+use crate::table::{Boot, SystemTable};
+use core::fmt::{Debug, Write};
 
 /// `Completion`s are used to model operations which have completed, but may
 /// have encountered non-fatal errors ("warnings") along the way
@@ -23,7 +25,7 @@ pub use self::error::Error;
 
 /// Definition of UEFI's standard status codes
 mod status;
-pub use self::status::Status;
+pub use self::status::{Status, StatusCategory};
 
 /// Return type of most UEFI functions. Both success and error payloads are optional.
 pub type Result<Output = (), ErrData = ()> =
@@ -41,8 +43,17 @@ pub trait ResultExt<Output, ErrData: Debug> {
     fn unwrap_success(self) -> Output;
 
     /// Expect success without warnings, panic with provided message otherwise
+    ///
+    /// The panic message includes the name of the status, e.g. `NOT_FOUND`.
     fn expect_success(self, msg: &str) -> Output;
 
+    /// Expect success without warnings, otherwise display the provided message
+    /// along with the status on the standard output, and reset the computer
+    ///
+    /// This is meant for fatal errors of OS loaders, where there is nothing
+    /// better to do than restarting the boot process.
+    fn or_reset(self, st: &SystemTable<Boot>, msg: &str) -> Output;
+
     /// Expect error, panic with provided message otherwise, discarding output
     fn expect_error(self, msg: &str) -> Error<ErrData>;
 
@@ -78,6 +89,17 @@ impl<Output, ErrData: Debug> ResultExt<Output, ErrData> for Result<Output, ErrDa
         self.expect(msg).expect(msg)
     }
 
+    fn or_reset(self, st: &SystemTable<Boot>, msg: &str) -> Output {
+        let status = match self.map(Completion::split) {
+            Ok((Status::SUCCESS, output)) => return output,
+            Ok((status, _)) => status,
+            Err(e) => e.status(),
+        };
+        // Nothing better can be done if the message cannot be displayed
+        let _ = writeln!(st.stdout(), "{}: {:?}", msg, status);
+        st.runtime_services().reset(ResetType::Cold, status, None)
+    }
+
     fn expect_error(self, msg: &str) -> Error<ErrData> {
         self.map(|completion| completion.status()).expect_err(msg)
     }
@@ -1,5 +1,6 @@
 use super::{Completion, Error, Result};
 use core::fmt::Debug;
+use core::num::TryFromIntError;
 use core::ops;
 
 /// Bit indicating that an UEFI status code is an error
@@ -123,6 +124,47 @@ impl Status {
         self.0 & ERROR_BIT != 0
     }
 
+    /// Returns the subsystem which this status code relates to.
+    ///
+    /// This can be used to classify failures, for example in order to retry
+    /// network operations but not security violations. Success, warnings and
+    /// unknown status codes are classified as `StatusCategory::Generic`.
+    pub fn category(self) -> StatusCategory {
+        match self {
+            Status::VOLUME_CORRUPTED
+            | Status::VOLUME_FULL
+            | Status::NO_MEDIA
+            | Status::MEDIA_CHANGED
+            | Status::WRITE_PROTECTED
+            | Status::END_OF_MEDIA
+            | Status::END_OF_FILE
+            | Status::WARN_DELETE_FAILURE
+            | Status::WARN_WRITE_FAILURE
+            | Status::WARN_FILE_SYSTEM => StatusCategory::Media,
+            Status::NO_RESPONSE
+            | Status::NO_MAPPING
+            | Status::ICMP_ERROR
+            | Status::TFTP_ERROR
+            | Status::PROTOCOL_ERROR
+            | Status::IP_ADDRESS_CONFLICT
+            | Status::HTTP_ERROR => StatusCategory::Network,
+            Status::ACCESS_DENIED | Status::SECURITY_VIOLATION | Status::COMPROMISED_DATA => {
+                StatusCategory::Security
+            }
+            _ => StatusCategory::Generic,
+        }
+    }
+
+    /// Returns all the warning status codes defined by the UEFI specification.
+    pub fn all_warnings() -> impl Iterator<Item = Status> {
+        WARNINGS.iter().copied()
+    }
+
+    /// Returns all the error status codes defined by the UEFI specification.
+    pub fn all_errors() -> impl Iterator<Item = Status> {
+        ERRORS.iter().copied()
+    }
+
     /// Converts this status code into a result with a given value.
     #[inline]
     pub fn into_with_val<T>(self, val: impl FnOnce() -> T) -> Result<T, ()> {
@@ -161,6 +203,67 @@ impl Status {
     }
 }
 
+/// Warning status codes defined by the UEFI specification
+const WARNINGS: [Status; 7] = [
+    Status::WARN_UNKNOWN_GLYPH,
+    Status::WARN_DELETE_FAILURE,
+    Status::WARN_WRITE_FAILURE,
+    Status::WARN_BUFFER_TOO_SMALL,
+    Status::WARN_STALE_DATA,
+    Status::WARN_FILE_SYSTEM,
+    Status::WARN_RESET_REQUIRED,
+];
+
+/// Error status codes defined by the UEFI specification
+const ERRORS: [Status; 33] = [
+    Status::LOAD_ERROR,
+    Status::INVALID_PARAMETER,
+    Status::UNSUPPORTED,
+    Status::BAD_BUFFER_SIZE,
+    Status::BUFFER_TOO_SMALL,
+    Status::NOT_READY,
+    Status::DEVICE_ERROR,
+    Status::WRITE_PROTECTED,
+    Status::OUT_OF_RESOURCES,
+    Status::VOLUME_CORRUPTED,
+    Status::VOLUME_FULL,
+    Status::NO_MEDIA,
+    Status::MEDIA_CHANGED,
+    Status::NOT_FOUND,
+    Status::ACCESS_DENIED,
+    Status::NO_RESPONSE,
+    Status::NO_MAPPING,
+    Status::TIMEOUT,
+    Status::NOT_STARTED,
+    Status::ALREADY_STARTED,
+    Status::ABORTED,
+    Status::ICMP_ERROR,
+    Status::TFTP_ERROR,
+    Status::PROTOCOL_ERROR,
+    Status::INCOMPATIBLE_VERSION,
+    Status::SECURITY_VIOLATION,
+    Status::CRC_ERROR,
+    Status::END_OF_MEDIA,
+    Status::END_OF_FILE,
+    Status::INVALID_LANGUAGE,
+    Status::COMPROMISED_DATA,
+    Status::IP_ADDRESS_CONFLICT,
+    Status::HTTP_ERROR,
+];
+
+/// Subsystem which a status code relates to, as returned by `Status::category()`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum StatusCategory {
+    /// Status codes which are not specific to a subsystem
+    Generic,
+    /// Status codes reported by storage media and file systems
+    Media,
+    /// Status codes reported by network operations
+    Network,
+    /// Status codes reported by security checks
+    Security,
+}
+
 // An UEFI status is equivalent to a Result with no data or rerror payload
 
 impl Into<Result<(), ()>> for Status {
@@ -187,6 +290,15 @@ impl ops::Try for Status {
     }
 }
 
+// Sizes and counts which do not fit in the integer type expected by the
+// firmware are reported like other buffer sizes that it cannot handle.
+
+impl From<TryFromIntError> for Status {
+    fn from(_: TryFromIntError) -> Self {
+        Status::BAD_BUFFER_SIZE
+    }
+}
+
 // FIXME: This conversion will go away along with usage of the ucs2 crate
 
 impl From<ucs2::Error> for Status {
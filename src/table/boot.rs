@@ -1,16 +1,23 @@
 //! UEFI services available during boot.
 
-use super::Header;
+use super::{Boot, Header, Runtime, SystemTable};
 use crate::data_types::Align;
+use crate::proto::misc::timestamp::Timestamp;
 use crate::proto::Protocol;
-use crate::{Event, Guid, Handle, Result, Status};
+use crate::result::Error;
+use crate::{CStr16, Char16, Event, Guid, Handle, Result, ResultExt, Status};
 #[cfg(feature = "exts")]
 use alloc_api::vec::Vec;
 use bitflags::bitflags;
 use core::cell::UnsafeCell;
 use core::ffi::c_void;
+use core::fmt;
+use core::iter::FromIterator;
 use core::mem::{self, MaybeUninit};
-use core::ptr;
+use core::ops::Deref;
+use core::ptr::{self, NonNull};
+use core::slice;
+use log::warn;
 
 /// Contains pointers to all of the boot services.
 #[repr(C)]
@@ -55,13 +62,27 @@ pub struct BootServices {
         out_index: *mut usize,
     ) -> Status,
     signal_event: usize,
-    close_event: usize,
+    close_event: unsafe extern "efiapi" fn(event: Event) -> Status,
     check_event: usize,
 
     // Protocol handlers
-    install_protocol_interface: usize,
-    reinstall_protocol_interface: usize,
-    uninstall_protocol_interface: usize,
+    install_protocol_interface: unsafe extern "efiapi" fn(
+        handle: &mut Handle,
+        guid: &Guid,
+        interface_type: u32,
+        interface: *mut c_void,
+    ) -> Status,
+    reinstall_protocol_interface: unsafe extern "efiapi" fn(
+        handle: Handle,
+        protocol: &Guid,
+        old_interface: *mut c_void,
+        new_interface: *mut c_void,
+    ) -> Status,
+    uninstall_protocol_interface: unsafe extern "efiapi" fn(
+        handle: Handle,
+        protocol: &Guid,
+        interface: *mut c_void,
+    ) -> Status,
     handle_protocol:
         extern "efiapi" fn(handle: Handle, proto: &Guid, out_proto: &mut *mut c_void) -> Status,
     _reserved: usize,
@@ -79,7 +100,12 @@ pub struct BootServices {
     // Image services
     load_image: usize,
     start_image: usize,
-    exit: usize,
+    exit: extern "efiapi" fn(
+        image_handle: Handle,
+        exit_status: Status,
+        exit_data_size: usize,
+        exit_data: *mut Char16,
+    ) -> Status,
     unload_image: usize,
     exit_boot_services:
         unsafe extern "efiapi" fn(image_handle: Handle, map_key: MemoryMapKey) -> Status,
@@ -99,13 +125,36 @@ pub struct BootServices {
     disconnect_controller: usize,
 
     // Protocol open / close services
-    open_protocol: usize,
-    close_protocol: usize,
-    open_protocol_information: usize,
+    open_protocol: unsafe extern "efiapi" fn(
+        handle: Handle,
+        protocol: &Guid,
+        interface: &mut *mut c_void,
+        agent_handle: Handle,
+        controller_handle: Handle,
+        attributes: u32,
+    ) -> Status,
+    close_protocol: unsafe extern "efiapi" fn(
+        handle: Handle,
+        protocol: &Guid,
+        agent_handle: Handle,
+        controller_handle: Handle,
+    ) -> Status,
+    open_protocol_information: unsafe extern "efiapi" fn(
+        handle: Handle,
+        protocol: &Guid,
+        entry_buffer: &mut *mut OpenProtocolInfoEntry,
+        entry_count: &mut usize,
+    ) -> Status,
 
     // Library services
     protocols_per_handle: usize,
-    locate_handle_buffer: usize,
+    locate_handle_buffer: unsafe extern "efiapi" fn(
+        search_ty: i32,
+        proto: *const Guid,
+        key: *mut c_void,
+        no_handles: &mut usize,
+        buf: &mut *mut Handle,
+    ) -> Status,
     locate_protocol: extern "efiapi" fn(
         proto: &Guid,
         registration: *mut c_void,
@@ -115,7 +164,8 @@ pub struct BootServices {
     uninstall_multiple_protocol_interfaces: usize,
 
     // CRC services
-    calculate_crc32: usize,
+    calculate_crc32:
+        unsafe extern "efiapi" fn(data: *const u8, data_size: usize, crc: &mut u32) -> Status,
 
     // Misc services
     copy_mem: unsafe extern "efiapi" fn(dest: *mut u8, src: *const u8, len: usize),
@@ -163,12 +213,102 @@ impl BootServices {
             AllocateType::MaxAddress(addr) => (1, addr as u64),
             AllocateType::Address(addr) => (2, addr as u64),
         };
-        (self.allocate_pages)(ty, mem_ty, count, &mut addr).into_with_val(|| addr)
+        (self.allocate_pages)(ty, mem_ty, count, &mut addr).into_with_val(|| {
+            page_tracker::record(addr, count);
+            addr
+        })
+    }
+
+    /// Allocates memory pages from the system, and returns both their physical
+    /// address and a zero-initialized byte slice covering them.
+    ///
+    /// The slice is built from the physical address, which assumes that memory
+    /// is identity-mapped, as is the case while boot services are active. The
+    /// physical address remains the one to use for e.g. building page tables.
+    ///
+    /// The pages must be freed with `free_pages()`.
+    ///
+    /// # Safety
+    ///
+    /// The slice must not be used after the pages have been freed, or after
+    /// the mapping of memory has been changed.
+    pub unsafe fn allocate_pages_slice<'buf>(
+        &self,
+        ty: AllocateType,
+        mem_ty: MemoryType,
+        count: usize,
+    ) -> Result<(u64, &'buf mut [u8])> {
+        const PAGE_SIZE: usize = 4096;
+        let size = match count.checked_mul(PAGE_SIZE) {
+            Some(size) => size,
+            None => return Err(Status::INVALID_PARAMETER.into()),
+        };
+        self.allocate_pages(ty, mem_ty, count).map_inner(|addr| {
+            let buffer = addr as usize as *mut u8;
+            self.memset(buffer, size, 0);
+            (addr, slice::from_raw_parts_mut(buffer, size))
+        })
+    }
+
+    /// Allocates `count` memory pages at the physical address `addr`.
+    ///
+    /// This is the same as `allocate_pages()` with `AllocateType::Address`,
+    /// except that a failure is diagnosed by looking up the memory map: the
+    /// error data is the type of the first memory range which overlaps the
+    /// requested pages and is not free, if there is any.
+    ///
+    /// The pages must be freed with `free_pages()`.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if the requested pages are not free
+    /// - `OutOfResources` if the pages could not be allocated
+    /// - `InvalidParameter` if `addr` is not page-aligned or `mem_ty` is invalid
+    pub fn allocate_pages_at(
+        &self,
+        addr: u64,
+        mem_ty: MemoryType,
+        count: usize,
+    ) -> Result<(), Option<MemoryType>> {
+        match self.allocate_pages(AllocateType::Address(addr as usize), mem_ty, count) {
+            Ok(completion) => Ok(completion.map(|_| ())),
+            Err(err) => Err(Error::new(err.status(), self.occupied_type(addr, count))),
+        }
+    }
+
+    /// Type of the first non-free memory range overlapping `count` pages at
+    /// `addr`, according to the current memory map
+    fn occupied_type(&self, addr: u64, count: usize) -> Option<MemoryType> {
+        let end = addr.saturating_add((count as u64).saturating_mul(4096));
+        let overlaps = |desc: &&MemoryDescriptor| {
+            let desc_end = desc
+                .phys_start
+                .saturating_add(desc.page_count.saturating_mul(4096));
+            desc.phys_start < end && addr < desc_end
+        };
+
+        let size = self.memory_map_size() + 8 * mem::size_of::<MemoryDescriptor>();
+        let buffer = self
+            .allocate_pool(MemoryType::LOADER_DATA, size)
+            .warning_as_error()
+            .ok()?;
+        let map = unsafe { slice::from_raw_parts_mut(buffer, size) };
+        let ty = match self.memory_map(map) {
+            Ok(completion) => completion
+                .log()
+                .1
+                .filter(|desc| desc.ty != MemoryType::CONVENTIONAL)
+                .find(overlaps)
+                .map(|desc| desc.ty),
+            Err(_) => None,
+        };
+        let _ = self.free_pool(buffer);
+        ty
     }
 
     /// Frees memory pages allocated by UEFI.
     pub fn free_pages(&self, addr: u64, count: usize) -> Result {
-        (self.free_pages)(addr, count).into()
+        (self.free_pages)(addr, count).into_with_val(|| page_tracker::forget(addr, count))
     }
 
     /// Retrieves the size, in bytes, of the current memory map.
@@ -209,6 +349,30 @@ impl BootServices {
         &self,
         buffer: &'buf mut [u8],
     ) -> Result<(MemoryMapKey, MemoryMapIter<'buf>)> {
+        self.memory_map_into(buffer).discard_errdata()
+    }
+
+    /// Retrieves the current memory map into a buffer which cannot be grown,
+    /// such as a stack buffer.
+    ///
+    /// This is a version of `memory_map` which does not panic or allocate when
+    /// the buffer is too small, but reports the size which the buffer would
+    /// need to have as error data. As allocating memory changes the memory
+    /// map, this size is a lower bound if the caller then allocates a buffer.
+    ///
+    /// The same buffer requirements as for `memory_map` apply. The descriptors
+    /// are iterated using the descriptor size reported by the firmware, which
+    /// may be larger than a `MemoryDescriptor`.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if the buffer is too small, along with the required
+    ///   size in bytes
+    /// - `InvalidParameter` for invalid buffers, without error data
+    pub fn memory_map_into<'buf>(
+        &self,
+        buffer: &'buf mut [u8],
+    ) -> Result<(MemoryMapKey, MemoryMapIter<'buf>), Option<usize>> {
         let mut map_size = buffer.len();
         MemoryDescriptor::assert_aligned(buffer);
         #[allow(clippy::cast_ptr_alignment)]
@@ -232,27 +396,118 @@ impl BootServices {
                 &mut entry_version,
             )
         }
-        .into_with_val(move || {
-            let len = map_size / entry_size;
-            let iter = MemoryMapIter {
-                buffer,
-                entry_size,
-                index: 0,
-                len,
-            };
-            (map_key, iter)
-        })
+        .into_with(
+            move || {
+                let len = map_size / entry_size;
+                let iter = MemoryMapIter {
+                    buffer,
+                    entry_size,
+                    index: 0,
+                    len,
+                };
+                (map_key, iter)
+            },
+            |status| {
+                if status == Status::BUFFER_TOO_SMALL {
+                    Some(map_size)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Retrieves the current memory map and tallies its pages by usage.
+    ///
+    /// This is a single pass over the memory map which does not allocate, and
+    /// the same buffer requirements as for `memory_map` apply.
+    pub fn memory_map_summary(&self, buffer: &mut [u8]) -> Result<MemorySummary> {
+        self.memory_map(buffer)
+            .map_inner(|(_key, iter)| iter.collect())
     }
 
     /// Allocates from a memory pool. The pointer will be 8-byte aligned.
+    ///
+    /// A successful allocation never returns a null pointer, which is
+    /// reported as `OutOfResources` if the firmware provides one.
     pub fn allocate_pool(&self, mem_ty: MemoryType, size: usize) -> Result<*mut u8> {
         let mut buffer = ptr::null_mut();
-        (self.allocate_pool)(mem_ty, size, &mut buffer).into_with_val(|| buffer)
+        let status = (self.allocate_pool)(mem_ty, size, &mut buffer);
+        if !status.is_error() && buffer.is_null() {
+            return Err(Status::OUT_OF_RESOURCES.into());
+        }
+        status.into_with_val(|| {
+            pool_tracker::record(buffer);
+            buffer
+        })
     }
 
     /// Frees memory allocated from a pool.
+    ///
+    /// In debug builds, freeing the same allocation twice logs a warning and
+    /// fails with `InvalidParameter`, instead of corrupting the firmware's
+    /// heap. Only addresses which were handed out by the wrappers of this
+    /// crate and then freed are flagged, and any later allocation at the same
+    /// address clears them. Other pointers are handed to the firmware as is.
+    /// Buffers which the firmware allocates behind the back of these wrappers
+    /// should still be adopted with `PoolBox::from_raw()`, as they may reuse
+    /// the address of a freed buffer.
     pub fn free_pool(&self, addr: *mut u8) -> Result {
-        (self.free_pool)(addr).into()
+        if pool_tracker::is_freed(addr) {
+            warn!("Pool allocation at {:p} was freed twice", addr);
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+        (self.free_pool)(addr).into_with_val(|| pool_tracker::forget(addr))
+    }
+
+    /// Allocates a zero-initialized array of `count` values of type `T` from
+    /// a memory pool.
+    ///
+    /// The array must be freed with `free_slice()`. Nothing is allocated for
+    /// an empty array, or for an array of zero-sized values. Types which need
+    /// more than the 8-byte alignment of pool allocations are rejected with
+    /// `INVALID_PARAMETER`.
+    ///
+    /// # Safety
+    ///
+    /// `T` must be valid when all of its bytes are zero, as is the case of
+    /// integers and of most FFI structs, but not of references. The slice
+    /// must not be used after it has been freed.
+    pub unsafe fn allocate_slice<'buf, T>(
+        &self,
+        mem_ty: MemoryType,
+        count: usize,
+    ) -> Result<&'buf mut [T]> {
+        // Pool allocations are only 8-byte aligned
+        if mem::align_of::<T>() > 8 {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+        let size = match count.checked_mul(mem::size_of::<T>()) {
+            Some(size) => size,
+            None => return Err(Status::INVALID_PARAMETER.into()),
+        };
+        if size == 0 {
+            let dangling = NonNull::<T>::dangling().as_ptr();
+            return Ok(slice::from_raw_parts_mut(dangling, count).into());
+        }
+        self.allocate_pool(mem_ty, size).map_inner(|buffer| {
+            self.memset(buffer, size, 0);
+            slice::from_raw_parts_mut(buffer.cast(), count)
+        })
+    }
+
+    /// Frees an array allocated with `allocate_slice()`.
+    ///
+    /// # Safety
+    ///
+    /// `slice` must be the whole array returned by `allocate_slice()`, which
+    /// must not be used anymore afterwards.
+    pub unsafe fn free_slice<T>(&self, slice: &mut [T]) -> Result {
+        // Such slices were not allocated, see `allocate_slice()`
+        if mem::size_of_val(slice) == 0 {
+            return Ok(().into());
+        }
+        self.free_pool(slice.as_mut_ptr().cast())
     }
 
     /// Creates an event
@@ -346,6 +601,79 @@ impl BootServices {
         )
     }
 
+    /// Stops execution until one of the given events is signaled
+    ///
+    /// This is a higher-level version of `wait_for_event`, which returns a
+    /// reference to the signaled event instead of its index. This allows the
+    /// caller to tell events apart by identity (using `core::ptr::eq`) rather
+    /// than by keeping track of their position in the slice.
+    ///
+    /// The same requirements as for `wait_for_event` apply. Up to
+    /// `MAX_WAIT_EVENTS` events are waited upon without allocating memory;
+    /// larger sets need the `exts` feature, and are rejected with
+    /// `INVALID_PARAMETER` otherwise, as is an empty set.
+    pub fn wait_any<'a>(&self, events: &'a [&'a Event]) -> Result<&'a Event> {
+        if events.is_empty() {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
+
+        with_event_array(events.iter().map(|&&event| event), None, |buffer| {
+            self.wait_for_event(buffer).discard_errdata()
+        })
+        .map_inner(|index| events[index])
+    }
+
+    /// Stops execution until an event is signaled, or until `timeout_us`
+    /// microseconds have elapsed
+    ///
+    /// This is a version of `wait_for_event` which returns `None` on timeout,
+    /// and the index of the signaled event otherwise. The same requirements
+    /// apply, as well as the limits of `wait_any`, where the internal timer
+    /// event implementing the timeout counts as one more event. An empty
+    /// slice of events can be used to just wait for the timeout.
+    pub fn wait_for_event_timeout(
+        &self,
+        events: &mut [Event],
+        timeout_us: u64,
+    ) -> Result<Option<usize>> {
+        let count = events.len();
+        let trigger = TimerTrigger::Relative(timeout_us.saturating_mul(10));
+        self.with_timer(events, trigger, |buffer| {
+            self.wait_for_event(buffer).discard_errdata()
+        })
+        .map_inner(|index| if index < count { Some(index) } else { None })
+    }
+
+    /// Creates a timer event set to `trigger`, then runs `f` on a contiguous
+    /// array made of `events` followed by that timer, as expected by
+    /// `wait_for_event`
+    ///
+    /// The timer comes last, so that events which are signaled at the same
+    /// time as the timer are still reported. Its index is `events.len()`.
+    fn with_timer<F, R>(&self, events: &[Event], trigger: TimerTrigger, f: F) -> Result<R>
+    where
+        F: FnOnce(&mut [Event]) -> Result<R>,
+    {
+        let timer = unsafe { self.create_event(EventType::TIMER, Tpl::APPLICATION, None) }
+            .warning_as_error()?;
+        let result = self
+            .set_timer(timer, trigger)
+            .and_then(|_| with_event_array(events.iter().copied(), Some(timer), f));
+
+        // Closing also cancels the timer
+        let closed = self.close_event(timer);
+        let value = result?;
+        closed.warning_as_error()?;
+        Ok(value)
+    }
+
+    /// Closes an event, cancelling any pending notification and timer
+    ///
+    /// The event must not be used anymore afterwards.
+    pub fn close_event(&self, event: Event) -> Result {
+        unsafe { (self.close_event)(event) }.into()
+    }
+
     /// Sets the trigger for `EventType::TIMER` event.
     pub fn set_timer(&self, event: Event, trigger_time: TimerTrigger) -> Result {
         let (ty, time) = match trigger_time {
@@ -365,14 +693,213 @@ impl BootServices {
     /// provides no mechanism to protect against concurrent usage. Such
     /// protections must be implemented by user-level code, for example via a
     /// global `HashSet`.
+    ///
+    /// A protocol which the firmware reports with a null interface pointer is
+    /// treated as missing, and `NotFound` is returned.
     pub fn handle_protocol<P: Protocol>(&self, handle: Handle) -> Result<&UnsafeCell<P>> {
         let mut ptr = ptr::null_mut();
-        (self.handle_protocol)(handle, &P::GUID, &mut ptr).into_with_val(|| {
-            let ptr = ptr as *mut P as *mut UnsafeCell<P>;
-            unsafe { &*ptr }
+        let status = (self.handle_protocol)(handle, &P::GUID, &mut ptr);
+        protocol_ref(status, ptr)
+    }
+
+    /// Opens a protocol on a handle, on behalf of the image or driver `agent`.
+    ///
+    /// Unlike `handle_protocol()`, this lets the firmware keep track of who
+    /// uses the protocol, as reported by `open_protocol_information()`, and
+    /// supports exclusive access. `controller` must be given by drivers which
+    /// open the protocol on behalf of a controller. The protocol is closed
+    /// when the returned guard is dropped.
+    ///
+    /// `OpenProtocolAttributes::TEST_PROTOCOL` yields no interface, and is
+    /// thus reported as `NotFound`. Use `proto::open_if()` to test for the
+    /// presence of a protocol.
+    ///
+    /// # Errors
+    ///
+    /// - `Unsupported` if the handle does not support the protocol
+    /// - `AccessDenied` or `AlreadyStarted` if the protocol is already open in
+    ///   a way which conflicts with `attributes`
+    /// - `InvalidParameter` if a handle is invalid
+    pub fn open_protocol<P: Protocol>(
+        &self,
+        handle: Handle,
+        agent: Handle,
+        controller: Option<Handle>,
+        attributes: OpenProtocolAttributes,
+    ) -> Result<ScopedProtocol<'_, P>> {
+        let controller = controller.unwrap_or_else(|| unsafe { mem::zeroed() });
+        let mut ptr = ptr::null_mut();
+        let status = unsafe {
+            (self.open_protocol)(
+                handle,
+                &P::GUID,
+                &mut ptr,
+                agent,
+                controller,
+                attributes.bits(),
+            )
+        };
+        if !status.is_error() && ptr.is_null() {
+            // The firmware has recorded the opening all the same, so it must
+            // be undone before reporting `NotFound`. Nothing sensible can be
+            // done about a failure to close the protocol.
+            let _ = unsafe { (self.close_protocol)(handle, &P::GUID, agent, controller) };
+        }
+        protocol_ref(status, ptr).map_inner(|interface| ScopedProtocol {
+            boot_services: self,
+            interface,
+            handle,
+            agent,
+            controller,
         })
     }
 
+    /// Opens the protocol `P` on the first handle which supports it, on
+    /// behalf of the image or driver `agent`.
+    ///
+    /// Unlike `locate_protocol()`, the protocol is properly opened as with
+    /// `open_protocol()`, and the handle it was found on is known.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` if no handle supports the protocol
+    /// - the errors of `open_protocol()`
+    pub fn first_protocol<P: Protocol>(&self, agent: Handle) -> Result<ScopedProtocol<'_, P>> {
+        let (status, handles) = self
+            .locate_handle_buffer(SearchType::from_proto::<P>())?
+            .split();
+        let handle = *handles.first().ok_or(Status::NOT_FOUND)?;
+        self.open_protocol(
+            handle,
+            agent,
+            None,
+            OpenProtocolAttributes::BY_HANDLE_PROTOCOL,
+        )
+        .map(|protocol| protocol.with_status(status))
+    }
+
+    /// Installs a protocol interface on a device handle.
+    ///
+    /// If `handle` is `None`, a new handle is created and returned, otherwise
+    /// the interface is added to the existing handle, which is returned.
+    ///
+    /// # Safety
+    ///
+    /// The interface must remain valid, and must match the layout expected by
+    /// the users of the protocol identified by `protocol`, until it is
+    /// uninstalled.
+    pub unsafe fn install_protocol_interface(
+        &self,
+        handle: Option<Handle>,
+        protocol: &Guid,
+        interface: *mut c_void,
+    ) -> Result<Handle> {
+        // A null handle asks the firmware to create a new one
+        let mut handle = handle.unwrap_or_else(|| mem::zeroed());
+        (self.install_protocol_interface)(&mut handle, protocol, NATIVE_INTERFACE, interface)
+            .into_with_val(|| handle)
+    }
+
+    /// Installs several protocol interfaces on a device handle, as a whole.
+    ///
+    /// This does the same as the firmware's variadic
+    /// `InstallMultipleProtocolInterfaces()`, which Rust cannot call: the
+    /// interfaces are installed one after the other at `Tpl::NOTIFY`, and if
+    /// one of them cannot be installed, those which were already installed
+    /// are uninstalled before the error is returned.
+    ///
+    /// If `handle` is `None`, a new handle is created and returned, otherwise
+    /// the interfaces are added to the existing handle, which is returned.
+    ///
+    /// # Errors
+    ///
+    /// * `uefi::Status::INVALID_PARAMETER` - no handle and no interface were
+    ///   given, or an interface is already installed on the handle.
+    ///
+    /// # Safety
+    ///
+    /// The requirements of `install_protocol_interface()` apply to each of
+    /// the interfaces. The caller must run at a priority level no higher than
+    /// `Tpl::NOTIFY`.
+    pub unsafe fn install_multiple_protocol_interfaces(
+        &self,
+        handle: Option<Handle>,
+        protocols: &[(Guid, *const c_void)],
+    ) -> Result<Handle> {
+        let _guard = self.raise_tpl(Tpl::NOTIFY);
+        let mut handle = handle;
+        for (installed, (protocol, interface)) in protocols.iter().enumerate() {
+            match self.install_protocol_interface(handle, protocol, *interface as *mut c_void) {
+                Ok(completion) => handle = Some(completion.log()),
+                Err(error) => {
+                    if let Some(handle) = handle {
+                        for (protocol, interface) in protocols[..installed].iter().rev() {
+                            // Nothing better can be done if rolling back fails
+                            let _ = self.uninstall_protocol_interface(
+                                handle,
+                                protocol,
+                                *interface as *mut c_void,
+                            );
+                        }
+                    }
+                    return Err(error);
+                }
+            }
+        }
+        match handle {
+            Some(handle) => Ok(handle.into()),
+            None => Err(Status::INVALID_PARAMETER.into()),
+        }
+    }
+
+    /// Replaces a protocol interface on a device handle with a new one.
+    ///
+    /// Unlike uninstalling the old interface and installing the new one, this
+    /// is atomic: the handle never appears to lack the protocol. Drivers which
+    /// had the old interface open are disconnected, and controllers are then
+    /// reconnected so that drivers can start using the new interface.
+    ///
+    /// # Errors
+    ///
+    /// * `uefi::Status::NOT_FOUND` - `old` is not installed on the handle.
+    /// * `uefi::Status::ACCESS_DENIED` - `old` is still in use by a driver
+    ///   which refused to release it.
+    ///
+    /// # Safety
+    ///
+    /// The new interface must remain valid until it is uninstalled.
+    pub unsafe fn reinstall_protocol_interface<P: Protocol>(
+        &self,
+        handle: Handle,
+        old: &P,
+        new: &P,
+    ) -> Result {
+        (self.reinstall_protocol_interface)(
+            handle,
+            &P::GUID,
+            old as *const P as *mut c_void,
+            new as *const P as *mut c_void,
+        )
+        .into()
+    }
+
+    /// Removes a protocol interface from a device handle.
+    ///
+    /// The handle is deleted when its last protocol interface is removed.
+    ///
+    /// # Safety
+    ///
+    /// Users of the interface may keep pointers to it, which must not be used
+    /// after it has been uninstalled.
+    pub unsafe fn uninstall_protocol_interface(
+        &self,
+        handle: Handle,
+        protocol: &Guid,
+        interface: *mut c_void,
+    ) -> Result {
+        (self.uninstall_protocol_interface)(handle, protocol, interface).into()
+    }
+
     /// Enumerates all handles installed on the system which match a certain query.
     ///
     /// You should first call this function with `None` for the output buffer,
@@ -393,12 +920,7 @@ impl BootServices {
             None => (0, NULL_BUFFER),
         };
 
-        // Obtain the needed data from the parameters.
-        let (ty, guid, key) = match search_ty {
-            SearchType::AllHandles => (0, ptr::null(), ptr::null_mut()),
-            SearchType::ByProtocol(guid) => (2, guid as *const _, ptr::null_mut()),
-        };
-
+        let (ty, guid, key) = search_ty.to_raw();
         let status = unsafe { (self.locate_handle)(ty, guid, key, &mut buffer_size, buffer) };
 
         // Must convert the returned size (in bytes) to length (number of elements).
@@ -410,6 +932,61 @@ impl BootServices {
         }
     }
 
+    /// Enumerates all handles installed on the system which match a certain query.
+    ///
+    /// Unlike `locate_handle`, the firmware allocates a buffer of the right
+    /// size for the handles, which is freed when the returned `HandleBuffer`
+    /// is dropped.
+    pub fn locate_handle_buffer(&self, search_ty: SearchType) -> Result<HandleBuffer<'_>> {
+        let mut count = 0;
+        let mut buffer = ptr::null_mut();
+        let (ty, guid, key) = search_ty.to_raw();
+        unsafe { (self.locate_handle_buffer)(ty, guid, key, &mut count, &mut buffer) }
+            .into_with_val(|| {
+                pool_tracker::record(buffer.cast());
+                HandleBuffer {
+                    boot_services: self,
+                    count,
+                    buffer,
+                }
+            })
+    }
+
+    /// Exits an image, returning control to the agent which started it, such
+    /// as the UEFI shell or the boot manager.
+    ///
+    /// If `status` is an error, `data` is passed to that agent as a
+    /// description of what went wrong, which the shell displays. It is ignored
+    /// on success, as specified by UEFI.
+    ///
+    /// The memory of the image is freed by the firmware, so any event whose
+    /// notification function lives in the image must have been closed
+    /// beforehand. This function only returns if `image` is not the handle of
+    /// a started image, in which case it panics.
+    pub fn exit(&self, image: Handle, status: Status, data: Option<&CStr16>) -> ! {
+        let (size, data) = match data {
+            Some(data) if status.is_error() => self.exit_data(data),
+            _ => None,
+        }
+        .unwrap_or((0, ptr::null_mut()));
+        let error = (self.exit)(image, status, size, data);
+        panic!("Failed to exit the image: {:?}", error);
+    }
+
+    /// Copies exit data into pool memory, which is then owned by the firmware
+    fn exit_data(&self, data: &CStr16) -> Option<(usize, *mut Char16)> {
+        let chars = data.to_u16_slice_with_nul();
+        let size = mem::size_of_val(chars);
+        let buffer = self
+            .allocate_pool(MemoryType::LOADER_DATA, size)
+            .warning_as_error()
+            .ok()? as *mut u16;
+        unsafe {
+            ptr::copy_nonoverlapping(chars.as_ptr(), buffer, chars.len());
+        }
+        Some((size, buffer.cast()))
+    }
+
     /// Exits the UEFI boot services
     ///
     /// This unsafe method is meant to be an implementation detail of the safe
@@ -435,6 +1012,52 @@ impl BootServices {
         assert_eq!((self.stall)(time), Status::SUCCESS);
     }
 
+    /// Stalls the processor for `micros` microseconds, unless `event` is
+    /// signaled before that, e.g. a key press for a skippable delay.
+    ///
+    /// Returns `true` if the delay was cut short by the event, and `false` if
+    /// it fully elapsed. The requirements of `wait_for_event` apply.
+    pub fn stall_or_event(&self, micros: u64, event: &Event) -> Result<bool> {
+        self.wait_for_event_timeout(&mut [*event], micros)
+            .map_inner(|index| index.is_some())
+    }
+
+    /// Briefly pauses a busy loop, e.g. one polling an input device.
+    ///
+    /// This stalls for a microsecond, which gives the firmware a chance to
+    /// run its timer handlers, then hints the processor that the caller is
+    /// spinning (through `pause` on x86, or `yield` on ARM).
+    pub fn cpu_relax(&self) {
+        self.stall(1);
+        core::sync::atomic::spin_loop_hint();
+    }
+
+    /// Stalls the processor for an amount of time, measured using the counter
+    /// of a `Timestamp` protocol.
+    ///
+    /// The time is in microseconds. Some firmwares implement `stall()` with a
+    /// coarse timer, which makes it too imprecise for short delays, such as
+    /// those required by bit-banged I/O. This function instead polls the
+    /// timestamp counter, keeping the CPU busy for the whole delay. If no
+    /// timestamp protocol is given, or if its properties are unavailable, it
+    /// falls back to `stall()`.
+    pub fn precise_stall(&self, timestamp: Option<&Timestamp>, time: usize) {
+        let (timestamp, properties) =
+            match timestamp.map(|ts| (ts, ts.get_properties().warning_as_error())) {
+                Some((ts, Ok(properties))) if properties.frequency != 0 => (ts, properties),
+                _ => return self.stall(time),
+            };
+
+        let ticks = properties.micros_to_ticks(time as u64);
+        let mut elapsed = 0;
+        let mut last = timestamp.get_timestamp();
+        while elapsed < ticks {
+            let now = timestamp.get_timestamp();
+            elapsed += properties.ticks_between(last, now);
+            last = now;
+        }
+    }
+
     /// Set the watchdog timer.
     ///
     /// UEFI will start a 5-minute countdown after an UEFI image is loaded.
@@ -482,10 +1105,44 @@ impl BootServices {
     /// The caveats of `BootServices::handle_protocol()` also apply here.
     pub fn locate_protocol<P: Protocol>(&self) -> Result<&UnsafeCell<P>> {
         let mut ptr = ptr::null_mut();
-        (self.locate_protocol)(&P::GUID, ptr::null_mut(), &mut ptr).into_with_val(|| {
-            let ptr = ptr as *mut P as *mut UnsafeCell<P>;
-            unsafe { &*ptr }
-        })
+        let status = (self.locate_protocol)(&P::GUID, ptr::null_mut(), &mut ptr);
+        protocol_ref(status, ptr)
+    }
+
+    /// Locates a protocol implementation and runs `f` on it, returning the
+    /// result of `f`.
+    ///
+    /// This is a convenient way to use a protocol once, as the reference to
+    /// the protocol cannot escape from the closure. The caveats of
+    /// `BootServices::handle_protocol()` apply while `f` runs.
+    pub fn with_protocol<P: Protocol, R>(&self, f: impl FnOnce(&mut P) -> R) -> Result<R> {
+        self.locate_protocol::<P>()
+            .map_inner(|protocol| f(unsafe { &mut *protocol.get() }))
+    }
+
+    /// Queries a handle for a protocol and runs `f` on it, returning the result
+    /// of `f`.
+    ///
+    /// This is the `handle_protocol()` counterpart of `with_protocol()`.
+    pub fn with_protocol_on<P: Protocol, R>(
+        &self,
+        handle: Handle,
+        f: impl FnOnce(&mut P) -> R,
+    ) -> Result<R> {
+        self.handle_protocol::<P>(handle)
+            .map_inner(|protocol| f(unsafe { &mut *protocol.get() }))
+    }
+
+    /// Computes the CRC-32 checksum of a buffer, as used by UEFI table headers.
+    ///
+    /// See the `crc` module for computing the same checksum incrementally.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if `data` is empty
+    pub fn calculate_crc32(&self, data: &[u8]) -> Result<u32> {
+        let mut crc = 0;
+        unsafe { (self.calculate_crc32)(data.as_ptr(), data.len(), &mut crc) }.into_with_val(|| crc)
     }
 
     /// Copies memory from source to destination. The buffers can overlap.
@@ -539,6 +1196,370 @@ impl BootServices {
             .into_with_val(|| buffer)
             .map(|completion| completion.with_status(status2))
     }
+
+    /// Lists the agents which currently have a protocol open on a handle.
+    ///
+    /// This is mostly useful for debugging, e.g. for finding out which driver
+    /// holds an exclusive access to a protocol after a failed attempt at
+    /// opening it with `uefi::Status::ACCESS_DENIED`.
+    pub fn open_protocol_information(
+        &self,
+        handle: Handle,
+        protocol: &Guid,
+    ) -> Result<Vec<OpenProtocolInfoEntry>> {
+        let mut entries = ptr::null_mut();
+        let mut count = 0;
+        let status =
+            unsafe { (self.open_protocol_information)(handle, protocol, &mut entries, &mut count) };
+
+        status.into_with_val(|| {
+            if entries.is_null() {
+                return Vec::new();
+            }
+            pool_tracker::record(entries.cast());
+            let info = unsafe { slice::from_raw_parts(entries, count) }.to_vec();
+            // The entries have been copied, so failing to free them only leaks memory
+            let _ = self.free_pool(entries as *mut u8);
+            info
+        })
+    }
+}
+
+/// Status codes which `retry_on` considers to be transient failures
+pub const TRANSIENT_STATUSES: &[Status] =
+    &[Status::NOT_READY, Status::TIMEOUT, Status::NO_RESPONSE];
+
+/// Runs an operation until it succeeds or fails with a non-transient error
+///
+/// The operation is attempted at most `attempts` times, stalling the processor
+/// for `backoff_us` microseconds between attempts. Only the errors listed in
+/// `TRANSIENT_STATUSES` lead to another attempt, other errors are returned
+/// immediately, as is the error of the last attempt.
+///
+/// Use `retry_on_statuses` to pick another set of transient errors.
+pub fn retry_on<F, T>(bt: &BootServices, attempts: usize, backoff_us: usize, f: F) -> Result<T>
+where
+    F: FnMut() -> Result<T>,
+{
+    retry_on_statuses(bt, TRANSIENT_STATUSES, attempts, backoff_us, f)
+}
+
+/// Runs an operation until it succeeds or fails with an error not in `transient`
+///
+/// This works like `retry_on`, but with a user-provided set of transient errors.
+pub fn retry_on_statuses<F, T>(
+    bt: &BootServices,
+    transient: &[Status],
+    attempts: usize,
+    backoff_us: usize,
+    mut f: F,
+) -> Result<T>
+where
+    F: FnMut() -> Result<T>,
+{
+    assert!(attempts > 0, "At least one attempt must be made");
+    let mut attempt = 1;
+    loop {
+        match f() {
+            Err(e) if attempt < attempts && transient.contains(&e.status()) => {
+                attempt += 1;
+                bt.stall(backoff_us);
+            }
+            result => return result,
+        }
+    }
+}
+
+/// Runs `f` on a contiguous array made of `events` followed by `extra`, as
+/// expected by `BootServices::wait_for_event()`
+///
+/// Up to `MAX_WAIT_EVENTS` events are copied on the stack. Larger sets are
+/// copied on the heap if the `exts` feature is enabled, and rejected with
+/// `INVALID_PARAMETER` otherwise, as is an empty set.
+pub(crate) fn with_event_array<I, F, R>(events: I, extra: Option<Event>, f: F) -> Result<R>
+where
+    I: ExactSizeIterator<Item = Event>,
+    F: FnOnce(&mut [Event]) -> Result<R>,
+{
+    let len = events.len() + extra.iter().count();
+    if len == 0 {
+        return Err(Status::INVALID_PARAMETER.into());
+    }
+
+    let events = events.chain(extra);
+    if len <= MAX_WAIT_EVENTS {
+        // Unused slots are never handed to the firmware
+        let mut buffer = [unsafe { mem::zeroed::<Event>() }; MAX_WAIT_EVENTS];
+        for (slot, event) in buffer.iter_mut().zip(events) {
+            *slot = event;
+        }
+        return f(&mut buffer[..len]);
+    }
+
+    #[cfg(feature = "exts")]
+    return f(&mut events.collect::<Vec<_>>());
+
+    #[cfg(not(feature = "exts"))]
+    return Err(Status::INVALID_PARAMETER.into());
+}
+
+/// Decision taken by the input handler of `run_loop`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum LoopControl {
+    /// Keep running the loop
+    Continue,
+    /// Exit the loop
+    Break,
+}
+
+/// Runs the main loop of a pre-boot user interface
+///
+/// A frame is rendered by calling `render` with its number right away, then
+/// every `frame_us` microseconds. In between, whenever one of `events` (e.g.
+/// the `wait_for_key_event()` of a text input protocol) is signaled, `handle`
+/// is called with its index. The loop ends when `handle` returns
+/// `LoopControl::Break`, and the number of rendered frames is returned.
+///
+/// Frames are paced by a periodic timer event, so no frame is rendered while
+/// input events are handled, and frames which are missed are not caught up
+/// with. The same requirements as for `wait_for_event` apply, as well as the
+/// limits of `BootServices::wait_any()`, where the frame timer counts as one
+/// more event.
+pub fn run_loop<R, H>(
+    bt: &BootServices,
+    events: &[Event],
+    frame_us: u64,
+    mut render: R,
+    mut handle: H,
+) -> Result<usize>
+where
+    R: FnMut(usize),
+    H: FnMut(usize) -> LoopControl,
+{
+    let count = events.len();
+    let trigger = TimerTrigger::Periodic(frame_us.saturating_mul(10));
+    let mut frames = 0;
+    bt.with_timer(events, trigger, |buffer| -> Result {
+        render(frames);
+        frames += 1;
+        loop {
+            let index = bt
+                .wait_for_event(buffer)
+                .discard_errdata()
+                .warning_as_error()?;
+            if index == count {
+                render(frames);
+                frames += 1;
+            } else if handle(index) == LoopControl::Break {
+                return Ok(().into());
+            }
+        }
+    })?
+    .log();
+    Ok(frames.into())
+}
+
+/// Number of times `exit_boot_services` tries to exit boot services before
+/// giving up
+pub const EXIT_BOOT_SERVICES_ATTEMPTS: usize = 4;
+
+/// Exits boot services, taking care of the memory map bookkeeping which this
+/// requires.
+///
+/// Storage for the memory map is allocated from the `LOADER_DATA` pool, with
+/// some slack for the entries which the allocation itself may add. No memory
+/// can be allocated between the final retrieval of the memory map and the exit
+/// of boot services, which would invalidate the map, so this storage is
+/// allocated once and reused for every attempt.
+///
+/// If the firmware reports that the memory map changed in the meantime, it is
+/// retrieved again and exiting is retried, up to `EXIT_BOOT_SERVICES_ATTEMPTS`
+/// times in total. On success, the runtime view of the system table is
+/// returned along with the final memory map, whose storage is never freed. On
+/// failure, that storage is returned to the pool.
+///
+/// See `SystemTable<Boot>::exit_boot_services()` for more details.
+///
+/// # Errors
+///
+/// - `InvalidParameter` if the memory map kept changing during all attempts
+/// - `OutOfResources` if the memory map storage could not be allocated
+pub fn exit_boot_services(
+    st: SystemTable<Boot>,
+    image: Handle,
+) -> Result<(SystemTable<Runtime>, MemoryMapIter<'static>)> {
+    // Boot services are still there if exiting them fails, and are needed to
+    // give the memory map storage back in that case
+    let bt_st = unsafe { st.unsafe_clone() };
+    let bt = bt_st.boot_services();
+    let size = bt.memory_map_size() + 8 * mem::size_of::<MemoryDescriptor>();
+    let ptr = bt
+        .allocate_pool(MemoryType::LOADER_DATA, size)
+        .warning_as_error()?;
+    let buffer = unsafe { slice::from_raw_parts_mut(ptr, size) };
+    let result = st.exit_boot_services_with_attempts(image, buffer, EXIT_BOOT_SERVICES_ATTEMPTS);
+    if result.is_err() {
+        let _ = bt.free_pool(ptr);
+    }
+    result
+}
+
+/// Number of pages allocated by `BootServices::allocate_pages()` which have
+/// not been freed yet
+///
+/// Allocations are only tracked in debug builds, in release builds this is
+/// always 0. At most 64 allocations are tracked at a time, further ones are
+/// not accounted for.
+pub fn leaked_page_count() -> usize {
+    page_tracker::page_count()
+}
+
+/// Panics if some pages allocated by `BootServices::allocate_pages()` have
+/// not been freed yet
+///
+/// This is meant to be called before handing off control to an OS, to catch
+/// forgotten staging buffers. It does nothing in release builds.
+pub fn assert_no_leaked_pages() {
+    let leaked = leaked_page_count();
+    assert_eq!(leaked, 0, "{} allocated pages were never freed", leaked);
+}
+
+/// Bookkeeping of the page allocations, for leak detection in debug builds
+#[cfg(debug_assertions)]
+mod page_tracker {
+    /// Maximal number of allocations which are tracked simultaneously
+    pub const CAPACITY: usize = 64;
+
+    /// Base address and page count of the live allocations
+    ///
+    /// UEFI applications are single-threaded, and boot services are only
+    /// called at a TPL where our code cannot be interrupted by itself.
+    static mut ALLOCATIONS: [Option<(u64, usize)>; CAPACITY] = [None; CAPACITY];
+
+    pub fn record(addr: u64, count: usize) {
+        let allocations = unsafe { &mut ALLOCATIONS };
+        if let Some(slot) = allocations.iter_mut().find(|slot| slot.is_none()) {
+            *slot = Some((addr, count));
+        }
+    }
+
+    /// Size of a page, in bytes
+    const PAGE_SIZE: u64 = 4096;
+
+    /// Forgets the freed pages, which may only be part of an allocation, so
+    /// that allocations are shrunk or split to keep track of the other pages
+    pub fn forget(addr: u64, count: usize) {
+        let allocations = unsafe { &mut ALLOCATIONS };
+        let end = addr.saturating_add((count as u64).saturating_mul(PAGE_SIZE));
+        for index in 0..CAPACITY {
+            let (base, pages) = match allocations[index] {
+                Some(allocation) => allocation,
+                None => continue,
+            };
+            let top = base + pages as u64 * PAGE_SIZE;
+            if end <= base || top <= addr {
+                continue;
+            }
+
+            // Keep the pages below and above the freed ones
+            let below = if base < addr {
+                Some((base, ((addr - base) / PAGE_SIZE) as usize))
+            } else {
+                None
+            };
+            let above = if end < top {
+                Some((end, ((top - end) / PAGE_SIZE) as usize))
+            } else {
+                None
+            };
+            allocations[index] = below.or(above);
+            if below.is_some() && above.is_some() {
+                if let Some(slot) = allocations.iter_mut().find(|slot| slot.is_none()) {
+                    *slot = above;
+                }
+            }
+        }
+    }
+
+    pub fn page_count() -> usize {
+        let allocations = unsafe { &ALLOCATIONS };
+        allocations.iter().flatten().map(|(_, count)| count).sum()
+    }
+}
+
+/// Bookkeeping of the page allocations, disabled in release builds
+#[cfg(not(debug_assertions))]
+mod page_tracker {
+    pub fn record(_addr: u64, _count: usize) {}
+
+    pub fn forget(_addr: u64, _count: usize) {}
+
+    pub fn page_count() -> usize {
+        0
+    }
+}
+
+/// Bookkeeping of the pool allocations, for double free detection in debug
+/// builds
+///
+/// Besides the allocations of `BootServices::allocate_pool()`, this records
+/// the pool buffers which the firmware hands over to us, as these may reuse
+/// the address of a buffer which was freed earlier.
+#[cfg(debug_assertions)]
+mod pool_tracker {
+    /// Maximal number of allocations which are tracked simultaneously
+    const CAPACITY: usize = 64;
+
+    /// Address of the tracked allocations, and truth that they were freed
+    ///
+    /// The same single-threading argument as for the page tracker applies.
+    static mut ALLOCATIONS: [Option<(usize, bool)>; CAPACITY] = [None; CAPACITY];
+
+    fn find(addr: *const u8) -> Option<&'static mut Option<(usize, bool)>> {
+        let allocations = unsafe { &mut ALLOCATIONS };
+        allocations
+            .iter_mut()
+            .find(|slot| matches!(slot, Some((base, _)) if *base == addr as usize))
+    }
+
+    pub fn record(addr: *mut u8) {
+        let allocations = unsafe { &mut ALLOCATIONS };
+        // Records of freed allocations are recycled when the table is full
+        let slot = if let Some(slot) = find(addr) {
+            Some(slot)
+        } else if let Some(slot) = allocations.iter_mut().find(|slot| slot.is_none()) {
+            Some(slot)
+        } else {
+            allocations
+                .iter_mut()
+                .find(|slot| matches!(slot, Some((_, true))))
+        };
+        if let Some(slot) = slot {
+            *slot = Some((addr as usize, false));
+        }
+    }
+
+    pub fn forget(addr: *mut u8) {
+        if let Some(slot) = find(addr) {
+            *slot = Some((addr as usize, true));
+        }
+    }
+
+    pub fn is_freed(addr: *const u8) -> bool {
+        matches!(find(addr), Some(Some((_, true))))
+    }
+}
+
+/// Bookkeeping of the pool allocations, disabled in release builds
+#[cfg(not(debug_assertions))]
+mod pool_tracker {
+    pub fn record(_addr: *mut u8) {}
+
+    pub fn forget(_addr: *mut u8) {}
+
+    pub fn is_freed(_addr: *const u8) -> bool {
+        false
+    }
 }
 
 impl super::Table for BootServices {
@@ -570,6 +1591,13 @@ pub enum Tpl: usize => {
     HIGH_LEVEL  = 31,
 }}
 
+/// Interface type of native protocol interfaces, the only one currently defined
+const NATIVE_INTERFACE: u32 = 0;
+
+/// Number of events which `BootServices::wait_any()` and related functions can
+/// wait upon without allocating memory
+pub const MAX_WAIT_EVENTS: usize = 32;
+
 /// RAII guard for task priority level changes
 ///
 /// Will automatically restore the former task priority level when dropped.
@@ -643,6 +1671,33 @@ pub enum MemoryType: u32 => {
     PERSISTENT_MEMORY       = 14,
 }}
 
+impl fmt::Display for MemoryType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match *self {
+            MemoryType::RESERVED => "Reserved",
+            MemoryType::LOADER_CODE => "LoaderCode",
+            MemoryType::LOADER_DATA => "LoaderData",
+            MemoryType::BOOT_SERVICES_CODE => "BootServicesCode",
+            MemoryType::BOOT_SERVICES_DATA => "BootServicesData",
+            MemoryType::RUNTIME_SERVICES_CODE => "RuntimeServicesCode",
+            MemoryType::RUNTIME_SERVICES_DATA => "RuntimeServicesData",
+            MemoryType::CONVENTIONAL => "Conventional",
+            MemoryType::UNUSABLE => "Unusable",
+            MemoryType::ACPI_RECLAIM => "ACPIReclaim",
+            MemoryType::ACPI_NON_VOLATILE => "ACPINonVolatile",
+            MemoryType::MMIO => "MMIO",
+            MemoryType::MMIO_PORT_SPACE => "MMIOPortSpace",
+            MemoryType::PAL_CODE => "PalCode",
+            MemoryType::PERSISTENT_MEMORY => "PersistentMemory",
+            // The upper ranges are reserved for firmware and OS vendors
+            MemoryType(ty @ 0x7000_0000..=0x7FFF_FFFF) => return write!(f, "OEM({:#x})", ty),
+            MemoryType(ty @ 0x8000_0000..=0xFFFF_FFFF) => return write!(f, "OS({:#x})", ty),
+            MemoryType(ty) => return write!(f, "Unknown({:#x})", ty),
+        };
+        f.write_str(name)
+    }
+}
+
 /// Memory descriptor version number
 pub const MEMORY_DESCRIPTOR_VERSION: u32 = 1;
 
@@ -677,6 +1732,34 @@ impl Default for MemoryDescriptor {
     }
 }
 
+impl MemoryDescriptor {
+    /// Truth that this memory range is used by runtime services, and must thus
+    /// be mapped by the OS when using them.
+    pub fn is_runtime(&self) -> bool {
+        self.att.contains(MemoryAttribute::RUNTIME)
+    }
+
+    /// Truth that the physical address `addr` lies within this memory range.
+    pub fn contains(&self, addr: u64) -> bool {
+        // Dividing the offset avoids overflowing on huge page counts
+        match addr.checked_sub(self.phys_start) {
+            Some(offset) => offset / 4096 < self.page_count,
+            None => false,
+        }
+    }
+
+    /// Returns a copy of this descriptor, mapped at virtual address `virt`.
+    ///
+    /// This is meant for building the memory map passed to
+    /// `RuntimeServices::set_virtual_address_map()`.
+    pub fn with_virtual(&self, virt: u64) -> MemoryDescriptor {
+        MemoryDescriptor {
+            virt_start: virt,
+            ..*self
+        }
+    }
+}
+
 impl Align for MemoryDescriptor {
     fn alignment() -> usize {
         mem::align_of::<Self>()
@@ -760,6 +1843,118 @@ impl<'buf> Iterator for MemoryMapIter<'buf> {
 
 impl<'buf> ExactSizeIterator for MemoryMapIter<'buf> {}
 
+/// Page counts of a memory map, grouped by broad memory usage.
+///
+/// Every memory type, including the ones which are unknown to uefi-rs, falls
+/// into exactly one of the categories, so `total()` matches the sum of the
+/// page counts of the underlying memory map.
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub struct MemorySummary {
+    /// Free usable memory.
+    pub conventional: u64,
+    /// Code and data of loaded UEFI applications.
+    pub loader: u64,
+    /// Code and data of the boot drivers.
+    pub boot_services: u64,
+    /// Code and data of the runtime drivers.
+    pub runtime_services: u64,
+    /// Memory holding ACPI tables, reclaimable or not.
+    pub acpi: u64,
+    /// Reserved, unusable, memory-mapped I/O and any other memory.
+    pub reserved: u64,
+}
+
+impl MemorySummary {
+    /// Returns the total number of pages in the memory map.
+    pub fn total(&self) -> u64 {
+        self.conventional
+            + self.loader
+            + self.boot_services
+            + self.runtime_services
+            + self.acpi
+            + self.reserved
+    }
+}
+
+impl<'buf> FromIterator<&'buf MemoryDescriptor> for MemorySummary {
+    fn from_iter<I: IntoIterator<Item = &'buf MemoryDescriptor>>(iter: I) -> Self {
+        let mut summary = MemorySummary::default();
+        for desc in iter {
+            let category = match desc.ty {
+                MemoryType::CONVENTIONAL => &mut summary.conventional,
+                MemoryType::LOADER_CODE | MemoryType::LOADER_DATA => &mut summary.loader,
+                MemoryType::BOOT_SERVICES_CODE | MemoryType::BOOT_SERVICES_DATA => {
+                    &mut summary.boot_services
+                }
+                MemoryType::RUNTIME_SERVICES_CODE | MemoryType::RUNTIME_SERVICES_DATA => {
+                    &mut summary.runtime_services
+                }
+                MemoryType::ACPI_RECLAIM | MemoryType::ACPI_NON_VOLATILE => &mut summary.acpi,
+                _ => &mut summary.reserved,
+            };
+            *category += desc.page_count;
+        }
+        summary
+    }
+}
+
+/// Selects the descriptors of the memory ranges which are used by runtime
+/// services, i.e. those which must be relocated when switching runtime
+/// services to virtual addressing.
+///
+/// The descriptors are copied as reported by the firmware, use
+/// `MemoryDescriptor::with_virtual()` to assign them a virtual address.
+pub fn runtime_descriptors<'buf, I>(map: I) -> impl Iterator<Item = MemoryDescriptor> + 'buf
+where
+    I: IntoIterator<Item = &'buf MemoryDescriptor>,
+    I::IntoIter: 'buf,
+{
+    map.into_iter().filter(|desc| desc.is_runtime()).copied()
+}
+
+/// Finds the descriptor of the memory range containing the physical address
+/// `addr`, if the memory map describes it.
+///
+/// This can be used to check that an address points to the expected kind of
+/// memory, e.g. before handing a kernel load address over to an OS.
+pub fn region_containing<'buf, I>(map: I, addr: u64) -> Option<&'buf MemoryDescriptor>
+where
+    I: IntoIterator<Item = &'buf MemoryDescriptor>,
+{
+    map.into_iter().find(|desc| desc.contains(addr))
+}
+
+/// Computes a 64-bit fingerprint of a memory map.
+///
+/// Equal maps have equal fingerprints, and any change to a descriptor
+/// changes the fingerprint with high probability. This is a cheap way to
+/// check whether the memory map actually changed between two retrievals,
+/// e.g. when retrying to exit boot services. No memory is allocated.
+pub fn memory_map_fingerprint<'buf, I>(map: I) -> u64
+where
+    I: IntoIterator<Item = &'buf MemoryDescriptor>,
+{
+    // 64-bit FNV-1a, over the meaningful fields of each descriptor
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+    let hash_bytes = |hash: u64, bytes: &[u8]| {
+        bytes.iter().fold(hash, |hash, &byte| {
+            (hash ^ u64::from(byte)).wrapping_mul(PRIME)
+        })
+    };
+    map.into_iter().fold(OFFSET_BASIS, |hash, desc| {
+        let hash = hash_bytes(hash, &desc.ty.0.to_le_bytes());
+        [
+            desc.phys_start,
+            desc.virt_start,
+            desc.page_count,
+            desc.att.bits(),
+        ]
+        .iter()
+        .fold(hash, |hash, field| hash_bytes(hash, &field.to_le_bytes()))
+    })
+}
+
 /// The type of handle search to perform.
 #[derive(Debug, Copy, Clone)]
 pub enum SearchType<'guid> {
@@ -778,6 +1973,229 @@ impl<'guid> SearchType<'guid> {
     pub fn from_proto<P: Protocol>() -> Self {
         SearchType::ByProtocol(&P::GUID)
     }
+
+    /// Search type, protocol and search key parameters of the handle searches
+    fn to_raw(self) -> (i32, *const Guid, *mut c_void) {
+        match self {
+            SearchType::AllHandles => (0, ptr::null(), ptr::null_mut()),
+            SearchType::ByProtocol(guid) => (2, guid as *const _, ptr::null_mut()),
+        }
+    }
+}
+
+/// An agent which has a protocol open, as reported by
+/// `BootServices::open_protocol_information()`
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct OpenProtocolInfoEntry {
+    /// Handle of the image or driver which opened the protocol
+    pub agent_handle: Handle,
+    /// Handle of the controller which requires the protocol, if the protocol
+    /// was opened by a driver
+    pub controller_handle: Handle,
+    /// The way the protocol was opened
+    pub attributes: OpenProtocolAttributes,
+    /// Number of times the protocol was opened in this way by this agent
+    pub open_count: u32,
+}
+
+bitflags! {
+    /// The ways in which a protocol can be opened.
+    pub struct OpenProtocolAttributes: u32 {
+        /// Opened by `BootServices::handle_protocol()` or a similar function
+        const BY_HANDLE_PROTOCOL = 0x01;
+        /// Opened to get the protocol interface, without usage tracking
+        const GET_PROTOCOL = 0x02;
+        /// Opened to test for the presence of the protocol
+        const TEST_PROTOCOL = 0x04;
+        /// Opened by a bus driver on behalf of one of its child controllers
+        const BY_CHILD_CONTROLLER = 0x08;
+        /// Opened by a driver which manages the controller
+        const BY_DRIVER = 0x10;
+        /// Opened for exclusive access
+        const EXCLUSIVE = 0x20;
+    }
+}
+
+/// Turns a protocol interface returned by the firmware into a reference
+///
+/// Some firmware report success along with a null interface pointer, which
+/// would make for a null reference, so this is reported as `NotFound`.
+fn protocol_ref<'boot, P>(status: Status, ptr: *mut c_void) -> Result<&'boot UnsafeCell<P>> {
+    if !status.is_error() && ptr.is_null() {
+        return Err(Status::NOT_FOUND.into());
+    }
+    status.into_with_val(|| unsafe { &*(ptr as *mut P as *mut UnsafeCell<P>) })
+}
+
+/// Buffer of handles allocated by `BootServices::locate_handle_buffer()`
+///
+/// Dereferences to the slice of handles, and frees the buffer when dropped.
+pub struct HandleBuffer<'boot> {
+    boot_services: &'boot BootServices,
+    count: usize,
+    buffer: *mut Handle,
+}
+
+impl Deref for HandleBuffer<'_> {
+    type Target = [Handle];
+
+    fn deref(&self) -> &[Handle] {
+        if self.buffer.is_null() {
+            &[]
+        } else {
+            unsafe { slice::from_raw_parts(self.buffer, self.count) }
+        }
+    }
+}
+
+impl Drop for HandleBuffer<'_> {
+    fn drop(&mut self) {
+        if !self.buffer.is_null() {
+            // Nothing sensible can be done about a failure to free the buffer
+            let _ = self.boot_services.free_pool(self.buffer as *mut u8);
+        }
+    }
+}
+
+/// A protocol opened by `BootServices::open_protocol()`
+///
+/// Dereferences to the protocol interface, and closes the protocol when
+/// dropped. The caveats of `BootServices::handle_protocol()` apply to the
+/// interface.
+pub struct ScopedProtocol<'boot, P: Protocol> {
+    boot_services: &'boot BootServices,
+    interface: &'boot UnsafeCell<P>,
+    handle: Handle,
+    agent: Handle,
+    controller: Handle,
+}
+
+impl<P: Protocol> ScopedProtocol<'_, P> {
+    /// Returns the handle which the protocol was opened on.
+    pub fn handle(&self) -> Handle {
+        self.handle
+    }
+}
+
+impl<P: Protocol> Deref for ScopedProtocol<'_, P> {
+    type Target = UnsafeCell<P>;
+
+    fn deref(&self) -> &UnsafeCell<P> {
+        self.interface
+    }
+}
+
+impl<P: Protocol> Drop for ScopedProtocol<'_, P> {
+    fn drop(&mut self) {
+        // Nothing sensible can be done about a failure to close the protocol
+        let _ = unsafe {
+            (self.boot_services.close_protocol)(self.handle, &P::GUID, self.agent, self.controller)
+        };
+    }
+}
+
+/// A value allocated from a memory pool, e.g. by `allocate_pool()`
+///
+/// Dereferences to the value, and frees the memory when dropped.
+pub struct PoolBox<'boot, T> {
+    boot_services: &'boot BootServices,
+    ptr: NonNull<T>,
+}
+
+impl<'boot, T> PoolBox<'boot, T> {
+    /// Takes ownership of a value allocated from a memory pool.
+    ///
+    /// # Safety
+    ///
+    /// `ptr` must point to a valid `T`, which was allocated with
+    /// `boot_services.allocate_pool()` or by the firmware from a memory pool,
+    /// and is not used anywhere else.
+    pub unsafe fn from_raw(boot_services: &'boot BootServices, ptr: NonNull<T>) -> Self {
+        pool_tracker::record(ptr.as_ptr().cast());
+        PoolBox { boot_services, ptr }
+    }
+}
+
+impl<T> Deref for PoolBox<'_, T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        unsafe { self.ptr.as_ref() }
+    }
+}
+
+impl<T> Drop for PoolBox<'_, T> {
+    fn drop(&mut self) {
+        // Nothing sensible can be done about a failure to free the buffer
+        let _ = self.boot_services.free_pool(self.ptr.as_ptr().cast());
+    }
+}
+
+/// Number of protocols which a `ProtocolCache` remembers
+const PROTOCOL_CACHE_SIZE: usize = 8;
+
+/// Remembers the protocols located with `BootServices::locate_protocol()`.
+///
+/// Locating a protocol requires a search through the protocol database of the
+/// firmware, which is wasteful when e.g. a render loop needs the graphics
+/// output protocol on every frame. This cache only searches for protocols it
+/// has not seen yet, and remembers the most recent ones.
+///
+/// The cache is not aware of changes to the handle database, so it must be
+/// cleared with `invalidate()` when protocols may have been reinstalled or
+/// uninstalled, e.g. after connecting or disconnecting a controller.
+pub struct ProtocolCache<'boot> {
+    boot_services: &'boot BootServices,
+    entries: [Option<(Guid, NonNull<c_void>)>; PROTOCOL_CACHE_SIZE],
+    next_slot: usize,
+    lookups: usize,
+}
+
+impl<'boot> ProtocolCache<'boot> {
+    /// Creates an empty cache.
+    pub fn new(boot_services: &'boot BootServices) -> Self {
+        ProtocolCache {
+            boot_services,
+            entries: [None; PROTOCOL_CACHE_SIZE],
+            next_slot: 0,
+            lookups: 0,
+        }
+    }
+
+    /// Returns the first protocol instance of type `P`, from the cache if it
+    /// was located before.
+    ///
+    /// The errors are those of `BootServices::locate_protocol()`, which are
+    /// not cached.
+    pub fn locate<P: Protocol>(&mut self) -> Result<&'boot UnsafeCell<P>> {
+        let cached = self
+            .entries
+            .iter()
+            .flatten()
+            .find(|(guid, _)| *guid == P::GUID);
+        if let Some((_, ptr)) = cached {
+            return Ok(unsafe { &*(ptr.as_ptr() as *mut P as *mut UnsafeCell<P>) }.into());
+        }
+
+        self.lookups += 1;
+        let (status, protocol) = self.boot_services.locate_protocol::<P>()?.split();
+        self.entries[self.next_slot] = Some((P::GUID, NonNull::from(protocol).cast()));
+        self.next_slot = (self.next_slot + 1) % PROTOCOL_CACHE_SIZE;
+        status.into_with_val(|| protocol)
+    }
+
+    /// Forgets all the cached protocols.
+    pub fn invalidate(&mut self) {
+        self.entries = [None; PROTOCOL_CACHE_SIZE];
+        self.next_slot = 0;
+    }
+
+    /// Returns how many times the firmware was asked to locate a protocol,
+    /// i.e. how many lookups missed the cache.
+    pub fn firmware_lookups(&self) -> usize {
+        self.lookups
+    }
 }
 
 bitflags! {
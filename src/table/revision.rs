@@ -17,6 +17,18 @@ impl Revision {
         Revision(value)
     }
 
+    /// Decodes a revision from its packed representation, with the major
+    /// revision in the high 16 bits and the minor revision in the low 16 bits,
+    /// as found in the headers of UEFI tables.
+    pub fn from_bits(raw: u32) -> Self {
+        Revision(raw)
+    }
+
+    /// Returns the packed representation of this revision, see `from_bits()`.
+    pub fn to_bits(self) -> u32 {
+        self.0
+    }
+
     /// Returns the major revision.
     pub fn major(self) -> u16 {
         (self.0 >> 16) as u16
@@ -1,9 +1,15 @@
 //! UEFI services available at runtime, even after the OS boots.
 
-use super::Header;
+use super::{Header, Revision};
+#[cfg(feature = "exts")]
+use crate::data_types::CString16;
 use crate::table::boot::MemoryDescriptor;
-use crate::{Result, Status};
+use crate::{CStr16, Char16, Completion, Guid, Result, Status};
+#[cfg(feature = "exts")]
+use alloc_api::vec::Vec;
 use bitflags::bitflags;
+use core::cmp::Ordering;
+use core::fmt;
 use core::mem::MaybeUninit;
 use core::ptr;
 
@@ -25,7 +31,20 @@ pub struct RuntimeServices {
         desc_version: u32,
         virtual_map: *mut MemoryDescriptor,
     ) -> Status,
-    _pad2: [usize; 5],
+    _pad2: [usize; 2],
+    get_next_variable_name: unsafe extern "efiapi" fn(
+        variable_name_size: &mut usize,
+        variable_name: *mut u16,
+        vendor_guid: &mut Guid,
+    ) -> Status,
+    set_variable: unsafe extern "efiapi" fn(
+        variable_name: *const Char16,
+        vendor_guid: &Guid,
+        attributes: u32,
+        data_size: usize,
+        data: *const u8,
+    ) -> Status,
+    _pad3: usize,
     reset: unsafe extern "efiapi" fn(
         rt: ResetType,
 
@@ -33,6 +52,17 @@ pub struct RuntimeServices {
         data_size: usize,
         data: *const u8,
     ) -> !,
+    update_capsule: unsafe extern "efiapi" fn(
+        capsule_header_array: *const &CapsuleHeader,
+        capsule_count: usize,
+        scatter_gather_list: u64,
+    ) -> Status,
+    query_capsule_capabilities: unsafe extern "efiapi" fn(
+        capsule_header_array: *const &CapsuleHeader,
+        capsule_count: usize,
+        maximum_capsule_size: *mut u64,
+        reset_type: *mut u32,
+    ) -> Status,
 }
 
 impl RuntimeServices {
@@ -56,11 +86,23 @@ impl RuntimeServices {
     /// During runtime, if a PC-AT CMOS device is present in the platform, the
     /// caller must synchronize access to the device before calling `set_time`.
     ///
+    /// Times which do not pass the checks of `TimeBuilder::build()`, such as
+    /// the 29th of February of a non-leap year, are rejected before reaching
+    /// the firmware, as some real time clocks misbehave when given them.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if `time` is not a valid time
+    /// - `DeviceError` if the time could not be set due to a hardware error
+    ///
     /// # Safety
     ///
     /// Undefined behavior could happen if multiple tasks try to
     /// use this function at the same time without synchronisation.
     pub unsafe fn set_time(&mut self, time: &Time) -> Result {
+        if time.validate().is_err() {
+            return Err(Status::INVALID_PARAMETER.into());
+        }
         (self.set_time)(time).into()
     }
 
@@ -81,6 +123,101 @@ impl RuntimeServices {
         (self.set_virtual_address_map)(map_size, entry_size, entry_version, map_ptr).into()
     }
 
+    /// Fetches the name and vendor GUID of the variable following a given one,
+    /// in the enumeration order of the firmware.
+    ///
+    /// `name` must hold the null-terminated name of the previous variable,
+    /// and `vendor` its vendor GUID. They are replaced with those of the next
+    /// variable. Start with an empty name in order to get the first variable.
+    ///
+    /// # Errors
+    ///
+    /// - `NotFound` once all variables have been enumerated
+    /// - `BufferTooSmall` if `name` cannot hold the name of the next variable,
+    ///   whose size in UCS-2 characters, including the terminator, is
+    ///   provided as error data. `name` and `vendor` are left unchanged.
+    /// - `InvalidParameter` if `name` is not null-terminated, or if it does
+    ///   not match an existing variable
+    /// - `DeviceError` if the variable storage failed
+    pub fn get_next_variable_name(
+        &self,
+        name: &mut [u16],
+        vendor: &mut Guid,
+    ) -> Result<(), Option<usize>> {
+        let mut size = name.len() * 2;
+        let status = unsafe { (self.get_next_variable_name)(&mut size, name.as_mut_ptr(), vendor) };
+        status.into_with(
+            || (),
+            |status| {
+                if status == Status::BUFFER_TOO_SMALL {
+                    Some((size + 1) / 2)
+                } else {
+                    None
+                }
+            },
+        )
+    }
+
+    /// Sets the value of a variable, creating it if it does not exist.
+    ///
+    /// A variable is identified by its name and its vendor GUID. Writing
+    /// empty `data` deletes the variable, unless `APPEND_WRITE` is set.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if the attributes are inconsistent, or if the name
+    ///   is empty
+    /// - `OutOfResources` if there is not enough storage for the variable
+    /// - `DeviceError` if the variable storage failed
+    /// - `WriteProtected` if the variable is read-only
+    /// - `SecurityViolation` if the variable requires authentication, and the
+    ///   write was not properly authenticated
+    /// - `NotFound` if the variable to be deleted does not exist
+    pub fn set_variable(
+        &self,
+        name: &CStr16,
+        vendor: &Guid,
+        attributes: VariableAttributes,
+        data: &[u8],
+    ) -> Result {
+        unsafe {
+            (self.set_variable)(
+                name.as_ptr(),
+                vendor,
+                attributes.bits(),
+                data.len(),
+                data.as_ptr(),
+            )
+        }
+        .into()
+    }
+
+    /// Sets the value of a variable which is protected by time-based
+    /// authentication, such as the secure boot keys (`PK`, `KEK`, `db`...)
+    ///
+    /// The `TIME_BASED_AUTHENTICATED_WRITE_ACCESS` attribute is added to the
+    /// given attributes. `signed_data` must start with an authentication
+    /// descriptor (`EFI_VARIABLE_AUTHENTICATION_2`) signing the new value of
+    /// the variable, followed by the value itself. This function does not
+    /// perform the signing.
+    ///
+    /// # Errors
+    ///
+    /// - `SecurityViolation` if the signature is missing or invalid, if it was
+    ///   not produced by an authorized key, or if its timestamp is older than
+    ///   that of the current value of the variable
+    /// - The errors of `set_variable`
+    pub fn set_variable_authenticated(
+        &self,
+        name: &CStr16,
+        vendor: &Guid,
+        attributes: VariableAttributes,
+        signed_data: &[u8],
+    ) -> Result {
+        let attributes = attributes | VariableAttributes::TIME_BASED_AUTHENTICATED_WRITE_ACCESS;
+        self.set_variable(name, vendor, attributes, signed_data)
+    }
+
     /// Resets the computer.
     pub fn reset(&self, rt: ResetType, status: Status, data: Option<&[u8]>) -> ! {
         let (size, data) = match data {
@@ -96,6 +233,181 @@ impl RuntimeServices {
 
         unsafe { (self.reset)(rt, status, size, data) }
     }
+
+    /// Passes capsules to the firmware, e.g. in order to update it.
+    ///
+    /// Depending on the capsule flags, the capsules are either processed
+    /// immediately, or after the next system reset. In the latter case,
+    /// `scatter_gather` must be the physical address of a scatter-gather list
+    /// describing where the capsules are stored in memory, which the firmware
+    /// will look up after the reset.
+    ///
+    /// Use `query_capsule_capabilities` beforehand in order to check if the
+    /// capsules are supported, and which kind of reset they need.
+    ///
+    /// # Safety
+    ///
+    /// The firmware reads the scatter-gather list and the memory it points to
+    /// as physical addresses, which cannot be checked here. Capsules can also
+    /// modify the firmware itself.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if a capsule needs a scatter-gather list and none
+    ///   was provided, or if the capsule flags are inconsistent
+    /// - `DeviceError` if the capsule update was started but failed
+    /// - `Unsupported` if the capsules are not supported, or if the firmware
+    ///   predates the capsule services of UEFI 2.0
+    /// - `OutOfResources` if the capsules are too large to be processed
+    pub unsafe fn update_capsule(
+        &self,
+        capsules: &[&CapsuleHeader],
+        scatter_gather: Option<u64>,
+    ) -> Result {
+        if !self.has_capsule_services() {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        (self.update_capsule)(
+            capsules.as_ptr(),
+            capsules.len(),
+            scatter_gather.unwrap_or(0),
+        )
+        .into()
+    }
+
+    /// Checks if the given capsules can be passed to `update_capsule`.
+    ///
+    /// Returns the maximal size of a capsule which the firmware supports, and
+    /// the type of reset which is required to process the capsules.
+    ///
+    /// # Errors
+    ///
+    /// - `InvalidParameter` if `capsules` is empty
+    /// - `Unsupported` if the capsules are not supported, or if the firmware
+    ///   predates the capsule services of UEFI 2.0
+    /// - `OutOfResources` if the capsules are too large to be processed
+    pub fn query_capsule_capabilities(
+        &self,
+        capsules: &[&CapsuleHeader],
+    ) -> Result<(u64, ResetType)> {
+        if !self.has_capsule_services() {
+            return Err(Status::UNSUPPORTED.into());
+        }
+        let mut max_size = 0;
+        let mut reset_type = 0;
+        let (status, ()) = unsafe {
+            (self.query_capsule_capabilities)(
+                capsules.as_ptr(),
+                capsules.len(),
+                &mut max_size,
+                &mut reset_type,
+            )
+        }
+        .into_with_val(|| ())?
+        .split();
+        match ResetType::from_raw(reset_type) {
+            Some(reset_type) => Ok(Completion::new(status, (max_size, reset_type))),
+            None => Err(Status::DEVICE_ERROR.into()),
+        }
+    }
+
+    /// Truth that this table contains the capsule services, which were added
+    /// in version 2.0 of the UEFI specification.
+    fn has_capsule_services(&self) -> bool {
+        self.header.revision >= Revision::new(2, 0)
+    }
+}
+
+#[cfg(feature = "exts")]
+impl RuntimeServices {
+    /// Returns an iterator over the names and vendor GUIDs of all variables.
+    ///
+    /// The enumeration is stopped by the first error, which is yielded.
+    /// Creating or deleting variables while enumerating them is not
+    /// supported by the firmware, and may cause the enumeration to fail.
+    pub fn variable_names(&self) -> VariableNames<'_> {
+        VariableNames {
+            rt: self,
+            name: alloc_api::vec![0; 64],
+            vendor: Guid::from_values(0, 0, 0, 0, [0; 6]),
+            done: false,
+        }
+    }
+
+    /// Returns an iterator over the names of the variables of a vendor, e.g.
+    /// `GLOBAL_VARIABLE_GUID` for the standard variables like `BootOrder`.
+    ///
+    /// This is a filtered version of `variable_names()`, errors are thus
+    /// reported in the same way.
+    pub fn variables_with_vendor(
+        &self,
+        vendor: &Guid,
+    ) -> impl Iterator<Item = Result<CString16>> + '_ {
+        let vendor = *vendor;
+        self.variable_names()
+            .filter_map(move |result| match result {
+                Ok(completion) => {
+                    let (status, (name, name_vendor)) = completion.split();
+                    if name_vendor == vendor {
+                        Some(Ok(Completion::new(status, name)))
+                    } else {
+                        None
+                    }
+                }
+                Err(err) => Some(Err(err)),
+            })
+    }
+}
+
+/// Iterator over the variables of the firmware, see
+/// `RuntimeServices::variable_names()`.
+#[cfg(feature = "exts")]
+pub struct VariableNames<'rt> {
+    rt: &'rt RuntimeServices,
+    name: Vec<u16>,
+    vendor: Guid,
+    done: bool,
+}
+
+#[cfg(feature = "exts")]
+impl Iterator for VariableNames<'_> {
+    type Item = Result<(CString16, Guid)>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        loop {
+            match self
+                .rt
+                .get_next_variable_name(&mut self.name, &mut self.vendor)
+            {
+                Ok(completion) => {
+                    let len = self.name.iter().position(|&c| c == 0);
+                    let name =
+                        len.and_then(|len| CStr16::from_u16_with_nul(&self.name[..=len]).ok());
+                    return match name {
+                        Some(name) => Some(Ok(completion.map(|()| (name.into(), self.vendor)))),
+                        None => {
+                            self.done = true;
+                            Some(Err(Status::DEVICE_ERROR.into()))
+                        }
+                    };
+                }
+                Err(err) => match err.split() {
+                    (Status::BUFFER_TOO_SMALL, Some(len)) => self.name.resize(len, 0),
+                    (Status::NOT_FOUND, _) => {
+                        self.done = true;
+                        return None;
+                    }
+                    (status, _) => {
+                        self.done = true;
+                        return Some(Err(status.into()));
+                    }
+                },
+            }
+        }
+    }
 }
 
 impl super::Table for RuntimeServices {
@@ -166,6 +478,35 @@ impl Time {
         }
     }
 
+    /// Start building a time, from 1900-01-01 00:00:00 in local time
+    ///
+    /// Unlike `Time::new()`, the builder checks that the day exists in the
+    /// month, accounting for leap years.
+    pub fn builder() -> TimeBuilder {
+        TimeBuilder {
+            time: Time::new(1900, 1, 1, 0, 0, 0, 0, 2047, Daylight::empty()),
+        }
+    }
+
+    /// Check that every field of this time is within its range
+    fn validate(&self) -> core::result::Result<(), TimeError> {
+        let check = |valid: bool, error| if valid { Ok(()) } else { Err(error) };
+        check(self.year >= 1900 && self.year <= 9999, TimeError::Year)?;
+        check(self.month >= 1 && self.month <= 12, TimeError::Month)?;
+        check(
+            self.day >= 1 && self.day <= days_in_month(self.year, self.month),
+            TimeError::Day,
+        )?;
+        check(self.hour <= 23, TimeError::Hour)?;
+        check(self.minute <= 59, TimeError::Minute)?;
+        check(self.second <= 59, TimeError::Second)?;
+        check(self.nanosecond <= 999_999_999, TimeError::Nanosecond)?;
+        check(
+            (self.time_zone >= -1440 && self.time_zone <= 1440) || self.time_zone == 2047,
+            TimeError::TimeZone,
+        )
+    }
+
     /// Query the year
     pub fn year(&self) -> u16 {
         self.year
@@ -214,6 +555,147 @@ impl Time {
     pub fn daylight(&self) -> Daylight {
         self.daylight
     }
+
+    /// Key used to order times chronologically
+    ///
+    /// Times with a known time zone are normalized to UTC, whereas times with
+    /// an unspecified time zone are compared as they are.
+    fn chrono_key(&self) -> (i64, u8, u32) {
+        // Days since 0000-03-01 in the proleptic Gregorian calendar, counting
+        // years from March so that leap days are at the end of the year.
+        let (month, day) = (i64::from(self.month), i64::from(self.day));
+        let year = i64::from(self.year) - if month <= 2 { 1 } else { 0 };
+        let era = year / 400;
+        let year_of_era = year - era * 400;
+        let day_of_year = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+        let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+        let days = era * 146_097 + day_of_era;
+
+        // The UEFI specification defines local time as UTC - TimeZone.
+        let offset = self.time_zone().map_or(0, i64::from);
+        let minutes = days * 1440 + i64::from(self.hour) * 60 + i64::from(self.minute) + offset;
+
+        (minutes, self.second, self.nanosecond)
+    }
+}
+
+/// Number of days in a month of the Gregorian calendar
+fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+/// Checked construction of a `Time`, see `Time::builder()`
+#[derive(Debug, Copy, Clone)]
+pub struct TimeBuilder {
+    time: Time,
+}
+
+impl TimeBuilder {
+    /// Set the date
+    pub fn date(mut self, year: u16, month: u8, day: u8) -> Self {
+        self.time.year = year;
+        self.time.month = month;
+        self.time.day = day;
+        self
+    }
+
+    /// Set the time of the day
+    pub fn time(mut self, hour: u8, minute: u8, second: u8) -> Self {
+        self.time.hour = hour;
+        self.time.minute = minute;
+        self.time.second = second;
+        self
+    }
+
+    /// Set the nanosecond
+    pub fn nanosecond(mut self, nanosecond: u32) -> Self {
+        self.time.nanosecond = nanosecond;
+        self
+    }
+
+    /// Set the time offset in minutes from UTC, instead of using local time
+    ///
+    /// The offset must be a concrete one, between -1440 and 1440 minutes.
+    pub fn time_zone(mut self, time_zone: i16) -> Self {
+        // The "unspecified" sentinel would silently turn this into a local
+        // time, so it is mapped to an out-of-range value to be rejected.
+        self.time.time_zone = if time_zone == 2047 {
+            i16::MAX
+        } else {
+            time_zone
+        };
+        self
+    }
+
+    /// Set the daylight savings time information
+    pub fn daylight(mut self, daylight: Daylight) -> Self {
+        self.time.daylight = daylight;
+        self
+    }
+
+    /// Check the fields, and build the time if they are all valid
+    pub fn build(self) -> core::result::Result<Time, TimeError> {
+        self.time.validate().map(|()| self.time)
+    }
+}
+
+/// Field of a `Time` which is out of range, as reported by
+/// `TimeBuilder::build()`
+#[derive(Debug, Copy, Clone, Eq, PartialEq)]
+pub enum TimeError {
+    /// The year is not between 1900 and 9999
+    Year,
+    /// The month is not between 1 and 12
+    Month,
+    /// The day does not exist in the month
+    Day,
+    /// The hour is not between 0 and 23
+    Hour,
+    /// The minute is not between 0 and 59
+    Minute,
+    /// The second is not between 0 and 59
+    Second,
+    /// The nanosecond is not below one billion
+    Nanosecond,
+    /// The time zone offset is not between -1440 and 1440 minutes
+    TimeZone,
+}
+
+impl fmt::Display for Time {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02} {:02}:{:02}:{:02}",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+/// Times are equal if they refer to the same instant, regardless of the time
+/// zone they are expressed in.
+impl PartialEq for Time {
+    fn eq(&self, other: &Time) -> bool {
+        self.chrono_key() == other.chrono_key()
+    }
+}
+
+impl Eq for Time {}
+
+impl PartialOrd for Time {
+    fn partial_cmp(&self, other: &Time) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Time {
+    fn cmp(&self, other: &Time) -> Ordering {
+        self.chrono_key().cmp(&other.chrono_key())
+    }
 }
 
 /// Real time clock capabilities
@@ -249,7 +731,94 @@ pub enum ResetType {
     /// The additional data must be a pointer to
     /// a null-terminated string followed by an UUID.
     PlatformSpecific,
-    // SAFETY: This enum is never exposed to the user, but only fed as input to
-    //         the firmware. Therefore, unexpected values can never come from
-    //         the firmware, and modeling this as a Rust enum seems safe.
+    // SAFETY: Reset types reported by the firmware are converted using
+    //         `ResetType::from_raw`, which rejects unexpected values. Therefore,
+    //         modeling this as a Rust enum seems safe.
+}
+
+impl ResetType {
+    /// Convert a reset type reported by the firmware
+    fn from_raw(raw: u32) -> Option<Self> {
+        match raw {
+            0 => Some(ResetType::Cold),
+            1 => Some(ResetType::Warm),
+            2 => Some(ResetType::Shutdown),
+            3 => Some(ResetType::PlatformSpecific),
+            _ => None,
+        }
+    }
+}
+
+bitflags! {
+    /// Attributes of a variable
+    pub struct VariableAttributes: u32 {
+        /// The variable is stored in non-volatile memory, and persists across
+        /// system resets
+        const NON_VOLATILE = 0x01;
+        /// The variable can be accessed while boot services are active
+        const BOOTSERVICE_ACCESS = 0x02;
+        /// The variable can be accessed after boot services have been exited.
+        /// Requires `BOOTSERVICE_ACCESS`.
+        const RUNTIME_ACCESS = 0x04;
+        /// The variable is a hardware error record
+        const HARDWARE_ERROR_RECORD = 0x08;
+        /// Writes must be authenticated with a counter-based signature.
+        /// Deprecated by the UEFI specification.
+        const AUTHENTICATED_WRITE_ACCESS = 0x10;
+        /// Writes must be authenticated with a time-based signature
+        const TIME_BASED_AUTHENTICATED_WRITE_ACCESS = 0x20;
+        /// The data is appended to the current value of the variable, instead
+        /// of replacing it
+        const APPEND_WRITE = 0x40;
+        /// Writes must be authenticated following the enhanced authenticated
+        /// variable format
+        const ENHANCED_AUTHENTICATED_ACCESS = 0x80;
+    }
+}
+
+/// Header of a capsule, which is a block of data passed to the firmware
+/// through `RuntimeServices::update_capsule`, e.g. a firmware update.
+///
+/// The header is followed in memory by the capsule contents, whose format is
+/// defined by the capsule GUID.
+#[derive(Debug, Copy, Clone)]
+#[repr(C)]
+pub struct CapsuleHeader {
+    /// Identifies the format of the capsule contents
+    pub guid: Guid,
+    /// Size of the header in bytes, which may be larger than this structure
+    pub header_size: u32,
+    /// Flags describing how the capsule should be processed
+    pub flags: CapsuleFlags,
+    /// Size of the capsule in bytes, including the header
+    pub image_size: u32,
+}
+
+impl CapsuleHeader {
+    /// Build the header of a capsule of `image_size` bytes, header included
+    pub fn new(guid: Guid, flags: CapsuleFlags, image_size: u32) -> Self {
+        CapsuleHeader {
+            guid,
+            header_size: core::mem::size_of::<CapsuleHeader>() as u32,
+            flags,
+            image_size,
+        }
+    }
+}
+
+bitflags! {
+    /// Flags describing how a capsule should be processed.
+    ///
+    /// The lower 16 bits are specific to the capsule GUID.
+    pub struct CapsuleFlags: u32 {
+        /// The capsule is processed after the next system reset, instead of
+        /// immediately. A scatter-gather list must be provided.
+        const PERSIST_ACROSS_RESET = 0x0001_0000;
+        /// The capsule is made available through the configuration table
+        /// after the reset. Requires `PERSIST_ACROSS_RESET`.
+        const POPULATE_SYSTEM_TABLE = 0x0002_0000;
+        /// The firmware resets the system by itself once the capsule has been
+        /// passed to it. Requires `PERSIST_ACROSS_RESET`.
+        const INITIATE_RESET = 0x0004_0000;
+    }
 }
@@ -1,10 +1,13 @@
 use super::boot::{BootServices, MemoryMapIter};
 use super::runtime::RuntimeServices;
 use super::{cfg, Header, Revision};
-use crate::proto::console::text;
+use crate::data_types::chars::NUL_16;
+use crate::proto::console::control::{ConsoleControl, ScreenMode};
+use crate::proto::console::text::{self, Key};
 use crate::{CStr16, Char16, Handle, Result, ResultExt, Status};
 use core::marker::PhantomData;
 use core::slice;
+use log::warn;
 
 /// Marker trait used to provide different views of the UEFI System Table
 pub trait SystemTableView {}
@@ -62,11 +65,22 @@ impl<View: SystemTableView> SystemTable<View> {
         self.table.header.revision
     }
 
+    /// Returns the header of this table, which describes its signature,
+    /// revision, size and CRC-32.
+    pub fn header(&self) -> &Header {
+        &self.table.header
+    }
+
     /// Returns the config table entries, a linear array of structures
     /// pointing to other system-specific tables.
     pub fn config_table(&self) -> &[cfg::ConfigTableEntry] {
         unsafe { slice::from_raw_parts(self.table.cfg_table, self.table.nr_cfg) }
     }
+
+    /// Returns the number of entries in the config table.
+    pub fn config_entry_count(&self) -> usize {
+        self.table.nr_cfg
+    }
 }
 
 // These parts of the UEFI System Table interface may only be used until boot
@@ -78,12 +92,102 @@ impl SystemTable<Boot> {
         unsafe { &mut *self.table.stdin }
     }
 
+    /// Reads a line of text from the standard input, echoing it to the
+    /// standard output.
+    ///
+    /// Reading stops when Enter is pressed, and Backspace erases the last
+    /// character. Special keys are ignored, as are characters typed once
+    /// `buf` is full. One element of `buf` is used for the null terminator.
+    ///
+    /// # Errors
+    ///
+    /// - `BufferTooSmall` if `buf` cannot even hold the null terminator
+    /// - `DeviceError` if the input or output device failed
+    pub fn read_line<'buf>(&self, buf: &'buf mut [Char16]) -> Result<&'buf CStr16> {
+        const BACKSPACE: u16 = 0x08;
+        const CARRIAGE_RETURN: u16 = 0x0D;
+
+        if buf.is_empty() {
+            return Err(Status::BUFFER_TOO_SMALL.into());
+        }
+
+        let mut len = 0;
+        loop {
+            self.boot_services()
+                .wait_for_event(&mut [self.stdin().wait_for_key_event()])
+                .discard_errdata()?
+                .log();
+            let c = match self.stdin().read_key()?.log() {
+                Some(Key::Printable(c)) => c,
+                _ => continue,
+            };
+            match c.into() {
+                CARRIAGE_RETURN => {
+                    self.echo(&['\r' as u16, '\n' as u16, 0])?.log();
+                    break;
+                }
+                BACKSPACE if len > 0 => {
+                    len -= 1;
+                    self.echo(&[BACKSPACE, ' ' as u16, BACKSPACE, 0])?.log();
+                }
+                BACKSPACE | 0 => {}
+                code if len + 1 < buf.len() => {
+                    buf[len] = c;
+                    len += 1;
+                    self.echo(&[code, 0])?.log();
+                }
+                _ => {}
+            }
+        }
+
+        buf[len] = NUL_16;
+        let codes = unsafe { slice::from_raw_parts(buf.as_ptr() as *const u16, len + 1) };
+        // Only non-null characters were stored before the terminator
+        Ok(unsafe { CStr16::from_u16_with_nul_unchecked(codes) }.into())
+    }
+
+    /// Writes null-terminated UCS-2 codes to the standard output
+    fn echo(&self, codes: &[u16]) -> Result {
+        let text = unsafe { CStr16::from_u16_with_nul_unchecked(codes) };
+        self.stdout().output_string(text)
+    }
+
     /// Returns the standard output protocol.
     pub fn stdout(&self) -> &mut text::Output {
         let stdout_ptr = self.table.stdout as *const _ as *mut _;
         unsafe { &mut *stdout_ptr }
     }
 
+    /// Puts the standard output in a good state for displaying text.
+    ///
+    /// If the firmware provides the legacy `ConsoleControl` protocol, the
+    /// screen is switched to text mode. As this protocol is often poorly
+    /// implemented, failing to do so is only logged as a warning. The standard
+    /// output is then reset, switched to its largest text mode, and cleared.
+    ///
+    /// # Errors
+    ///
+    /// - `DeviceError` if the output device failed
+    pub fn setup_console(&self) -> Result {
+        if let Ok(control) = self.boot_services().locate_protocol::<ConsoleControl>() {
+            let control = unsafe { &mut *control.log().get() };
+            if let Err(e) = control.set_mode(ScreenMode::TEXT).warning_as_error() {
+                warn!("Failed to switch the screen to text mode: {:?}", e.status());
+            }
+        }
+
+        let stdout = self.stdout();
+        stdout.reset(false)?.log();
+        let largest = stdout
+            .modes()
+            .map(|mode| mode.log())
+            .max_by_key(|mode| mode.columns() * mode.rows());
+        if let Some(mode) = largest {
+            stdout.set_mode(mode)?.log();
+        }
+        stdout.clear()
+    }
+
     /// Returns the standard error protocol.
     pub fn stderr(&self) -> &mut text::Output {
         let stderr_ptr = self.table.stderr as *const _ as *mut _;
@@ -135,11 +239,29 @@ impl SystemTable<Boot> {
         self,
         image: Handle,
         mmap_buf: &'buf mut [u8],
+    ) -> Result<(SystemTable<Runtime>, MemoryMapIter<'buf>)> {
+        self.exit_boot_services_with_attempts(image, mmap_buf, usize::MAX)
+    }
+
+    /// Version of `exit_boot_services` which gives up after `attempts` tries
+    /// to exit boot services, returning the `InvalidParameter` error.
+    pub(super) fn exit_boot_services_with_attempts<'buf>(
+        self,
+        image: Handle,
+        mmap_buf: &'buf mut [u8],
+        attempts: usize,
     ) -> Result<(SystemTable<Runtime>, MemoryMapIter<'buf>)> {
         unsafe {
             let boot_services = self.boot_services();
 
+            // The firmware memory routines must not be used from this point,
+            // since even a failed attempt may shut some boot services down.
+            crate::mem::exit_boot_services();
+
+            let mut attempt = 0;
             loop {
+                attempt += 1;
+
                 // Fetch a memory map, propagate errors and split the completion
                 // FIXME: This sad pointer hack works around a current
                 //        limitation of the NLL analysis (see Rust bug 51526).
@@ -151,7 +273,7 @@ impl SystemTable<Boot> {
                 let result = boot_services.exit_boot_services(image, mmap_key);
 
                 // Did we fail because the memory map was updated concurrently?
-                if result.status() == Status::INVALID_PARAMETER {
+                if result.status() == Status::INVALID_PARAMETER && attempt < attempts {
                     // If so, fetch another memory map and try again
                     continue;
                 } else {
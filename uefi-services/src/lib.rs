@@ -24,10 +24,9 @@ extern crate uefi;
 #[macro_use]
 extern crate log;
 
-use core::ptr::NonNull;
-
 use uefi::prelude::*;
 use uefi::table::boot::{EventType, Tpl};
+use uefi::table::runtime::RuntimeServices;
 use uefi::table::{Boot, SystemTable};
 use uefi::{Event, Result};
 
@@ -38,23 +37,65 @@ use uefi::{Event, Result};
 /// UEFI's ExitBootServices entry point for more details.
 static mut SYSTEM_TABLE: Option<SystemTable<Boot>> = None;
 
+/// Reference to the runtime services table, which outlives the system table
+/// reference as it remains valid after exit from boot services.
+static mut RUNTIME_SERVICES: Option<&'static RuntimeServices> = None;
+
+/// Event which disables this library on exit from UEFI boot services
+///
+/// Its notify function lives in this image, so it must be closed before the
+/// image is unloaded.
+static mut EXIT_EVENT: Option<Event> = None;
+
 /// Global logger object
 static mut LOGGER: Option<uefi::logger::Logger> = None;
 
-/// Obtains a pointer to the system table.
+/// Obtains a reference to the system table.
 ///
 /// This is meant to be used by higher-level libraries,
 /// which want a convenient way to access the system table singleton.
 ///
-/// `init` must have been called first by the UEFI app.
+/// The returned reference must not be kept around, as it must not be used
+/// after boot services have been exited.
+///
+/// # Panics
 ///
-/// The returned pointer is only valid until boot services are exited.
-pub fn system_table() -> NonNull<SystemTable<Boot>> {
+/// Panics if `init` has not been called first by the UEFI app, or if boot
+/// services have been exited or `deinit` has been called since.
+pub fn system_table() -> &'static SystemTable<Boot> {
     unsafe {
-        let table_ref = SYSTEM_TABLE
+        SYSTEM_TABLE
             .as_ref()
-            .expect("The system table handle is not available");
-        NonNull::new(table_ref as *const _ as *mut _).unwrap()
+            .expect("The system table is not available, as uefi_services is not initialized")
+    }
+}
+
+/// Returns the boot services table.
+///
+/// This allows deeply nested code to e.g. allocate memory without being
+/// passed the system table. The same caveats as for `system_table` apply.
+///
+/// # Panics
+///
+/// Panics if `init` has not been called, or if boot services have been
+/// exited or `deinit` has been called since.
+pub fn boot_services() -> &'static BootServices {
+    system_table().boot_services()
+}
+
+/// Returns the runtime services table.
+///
+/// Unlike the boot services table, this one remains available after boot
+/// services have been exited, which is when it is needed the most.
+///
+/// # Panics
+///
+/// Panics if `init` has not been called.
+pub fn runtime_services() -> &'static RuntimeServices {
+    unsafe {
+        RUNTIME_SERVICES.expect(
+            "The runtime services are not available, as uefi_services was never initialized",
+        )
     }
 }
 
@@ -71,11 +112,13 @@ pub fn init(st: &SystemTable<Boot>) -> Result {
 
         // Setup the system table singleton
         SYSTEM_TABLE = Some(st.unsafe_clone());
+        RUNTIME_SERVICES = Some(&*(st.runtime_services() as *const RuntimeServices));
 
         // Setup logging and memory allocation
         let boot_services = st.boot_services();
         init_logger(st);
         uefi::alloc::init(boot_services);
+        uefi::mem::init(boot_services);
 
         // Schedule these tools to be disabled on exit from UEFI boot services
         boot_services
@@ -84,7 +127,7 @@ pub fn init(st: &SystemTable<Boot>) -> Result {
                 Tpl::NOTIFY,
                 Some(exit_boot_services),
             )
-            .map_inner(|_| ())
+            .map_inner(|event| EXIT_EVENT = Some(event))
     }
 }
 
@@ -108,6 +151,39 @@ unsafe fn init_logger(st: &SystemTable<Boot>) {
     log::set_max_level(log::LevelFilter::Info);
 }
 
+/// Tear down the UEFI utility library.
+///
+/// This disables logging, which silently discards all subsequent messages,
+/// and memory allocation, which fails from then on. The system table reference
+/// returned by `system_table` also becomes unavailable, and `boot_services`
+/// panics from then on. The runtime services table remains available.
+///
+/// This is done automatically when boot services are exited, but calling this
+/// function right before exiting them makes sure that nothing, such as an
+/// exit boot services event of another component, uses these tools too late.
+///
+/// This also closes the event which does so on exit from boot services, as its
+/// notify function would otherwise outlive this image if it is unloaded, e.g.
+/// by `BootServices::exit()`.
+///
+/// The library cannot be initialized again afterwards.
+pub fn deinit() {
+    unsafe {
+        if let (Some(event), Some(st)) = (EXIT_EVENT.take(), SYSTEM_TABLE.as_ref()) {
+            // Nothing sensible can be done about a failure to close the event
+            let _ = st.boot_services().close_event(event);
+        }
+        SYSTEM_TABLE = None;
+        if let Some(ref mut logger) = LOGGER {
+            logger.disable();
+        }
+    }
+    // The logger cannot be unregistered, but it can be bypassed.
+    log::set_max_level(log::LevelFilter::Off);
+    uefi::alloc::exit_boot_services();
+    uefi::mem::exit_boot_services();
+}
+
 /// Notify the utility library that boot services are not safe to call anymore
 fn exit_boot_services(_e: Event) {
     // DEBUG: The UEFI spec does not guarantee that this printout will work, as
@@ -116,13 +192,13 @@ fn exit_boot_services(_e: Event) {
     //        check that the callback does get called.
     //
     // info!("Shutting down the UEFI utility library");
+
+    // Memory must not be freed from here, so the event is left open, which is
+    // harmless as it is never signaled again.
     unsafe {
-        SYSTEM_TABLE = None;
-        if let Some(ref mut logger) = LOGGER {
-            logger.disable();
-        }
+        EXIT_EVENT = None;
     }
-    uefi::alloc::exit_boot_services();
+    deinit();
 }
 
 #[lang = "eh_personality"]
@@ -164,11 +240,10 @@ fn panic_handler(info: &core::panic::PanicInfo) -> ! {
         }
     }
 
-    // If the system table is available, use UEFI's standard shutdown mechanism
-    if let Some(st) = unsafe { SYSTEM_TABLE.as_ref() } {
+    // If runtime services are available, use UEFI's standard shutdown mechanism
+    if let Some(rt) = unsafe { RUNTIME_SERVICES } {
         use uefi::table::runtime::ResetType;
-        st.runtime_services()
-            .reset(ResetType::Shutdown, uefi::Status::ABORTED, None);
+        rt.reset(ResetType::Shutdown, uefi::Status::ABORTED, None);
     }
 
     // If we don't have any shutdown mechanism handy, the best we can do is loop
@@ -189,6 +189,9 @@ def run_qemu():
         # the UEFI stdout and stdin to that port too.
         '-serial', 'stdio',
 
+        # Provide a hardware random number generator, for the RNG protocol test
+        '-device', 'virtio-rng-pci',
+
         # Map the QEMU exit signal to port f4
         '-device', 'isa-debug-exit,iobase=0xf4,iosize=0x04',
 
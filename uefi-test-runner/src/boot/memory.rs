@@ -1,18 +1,31 @@
 use uefi::prelude::*;
-use uefi::table::boot::{AllocateType, BootServices, MemoryDescriptor, MemoryType};
+use uefi::table::boot::{self, AllocateType, BootServices, MemoryDescriptor, MemoryType, PoolBox};
 
 use crate::alloc::vec::Vec;
 use core::mem;
+use core::ptr::NonNull;
 
 pub fn test(bt: &BootServices) {
     info!("Testing memory functions");
 
     allocate_pages(bt);
+    allocate_pages_at(bt);
+    page_leaks(bt);
+    pool_double_free(bt);
+    allocate_slice(bt);
+    allocate_pages_slice(bt);
     vec_alloc();
     alloc_alignment();
     memmove(bt);
+    mem_fallback(bt);
 
     memory_map(bt);
+    memory_map_into_small_buffer(bt);
+    memory_map_summary(bt);
+    region_containing(bt);
+    memory_map_fingerprint(bt);
+    memory_type_display();
+    runtime_descriptors(bt);
 }
 
 fn allocate_pages(bt: &BootServices) {
@@ -37,6 +50,137 @@ fn allocate_pages(bt: &BootServices) {
     bt.free_pages(pgs, 1).unwrap_success();
 }
 
+fn allocate_pages_at(bt: &BootServices) {
+    info!("Allocating pages at a fixed address");
+
+    let ty = AllocateType::AnyPages;
+    let mem_ty = MemoryType::LOADER_DATA;
+    let addr = bt
+        .allocate_pages(ty, mem_ty, 1)
+        .expect_success("Failed to allocate a page of memory");
+
+    // The page is taken, so the conflict must be reported
+    let err = bt
+        .allocate_pages_at(addr, MemoryType::LOADER_CODE, 1)
+        .expect_err("Allocated the same page twice");
+    assert_eq!(err.status(), Status::NOT_FOUND);
+    assert_eq!(*err.data(), Some(MemoryType::LOADER_DATA));
+
+    // Once freed, the page can be allocated at the same address
+    bt.free_pages(addr, 1).unwrap_success();
+    bt.allocate_pages_at(addr, mem_ty, 1)
+        .expect_success("Failed to allocate a free page at a fixed address");
+    bt.free_pages(addr, 1).unwrap_success();
+}
+
+fn page_leaks(bt: &BootServices) {
+    info!("Checking page leak detection");
+
+    let ty = AllocateType::AnyPages;
+    let mem_ty = MemoryType::LOADER_DATA;
+    let pgs = bt
+        .allocate_pages(ty, mem_ty, 2)
+        .expect_success("Failed to allocate pages of memory");
+    bt.free_pages(pgs, 2).unwrap_success();
+    boot::assert_no_leaked_pages();
+
+    // Deliberately leak the pages for a moment
+    let pgs = bt
+        .allocate_pages(ty, mem_ty, 3)
+        .expect_success("Failed to allocate pages of memory");
+    let expected = if cfg!(debug_assertions) { 3 } else { 0 };
+    assert_eq!(boot::leaked_page_count(), expected, "Leak not detected");
+    bt.free_pages(pgs, 3).unwrap_success();
+    boot::assert_no_leaked_pages();
+
+    // Pages which remain after freeing part of an allocation are still tracked
+    let pgs = bt
+        .allocate_pages(ty, mem_ty, 4)
+        .expect_success("Failed to allocate pages of memory");
+    bt.free_pages(pgs + 4096, 2).unwrap_success();
+    let expected = if cfg!(debug_assertions) { 2 } else { 0 };
+    assert_eq!(
+        boot::leaked_page_count(),
+        expected,
+        "Partial free not tracked"
+    );
+    bt.free_pages(pgs, 1).unwrap_success();
+    bt.free_pages(pgs + 3 * 4096, 1).unwrap_success();
+    boot::assert_no_leaked_pages();
+}
+
+fn pool_double_free(bt: &BootServices) {
+    info!("Checking pool double free detection");
+
+    // Without detection, which only happens in debug builds, freeing memory
+    // twice would corrupt the firmware's heap
+    if !cfg!(debug_assertions) {
+        return;
+    }
+
+    let ptr = bt
+        .allocate_pool(MemoryType::LOADER_DATA, 64)
+        .expect_success("Failed to allocate from a pool");
+    bt.free_pool(ptr)
+        .expect_success("Failed to free pool memory");
+    let error = bt.free_pool(ptr).expect_error("Double free not detected");
+    assert_eq!(error.status(), Status::INVALID_PARAMETER);
+
+    // Firmware usually hands the same address out again, which must then not
+    // be mistaken for a double free
+    let again = bt
+        .allocate_pool(MemoryType::LOADER_DATA, 64)
+        .expect_success("Failed to allocate from a pool");
+    bt.free_pool(again)
+        .expect_success("Failed to free reused pool memory");
+
+    // The same goes for buffers adopted from the firmware
+    let adopted = bt
+        .allocate_pool(MemoryType::LOADER_DATA, 64)
+        .expect_success("Failed to allocate from a pool");
+    drop(unsafe { PoolBox::from_raw(bt, NonNull::new(adopted).unwrap()) });
+    let error = bt
+        .free_pool(adopted)
+        .expect_error("Double free not detected");
+    assert_eq!(error.status(), Status::INVALID_PARAMETER);
+}
+
+fn allocate_slice(bt: &BootServices) {
+    info!("Allocating a typed slice from a pool");
+
+    let values = unsafe { bt.allocate_slice::<u32>(MemoryType::LOADER_DATA, 100) }
+        .expect_success("Failed to allocate a slice");
+    assert_eq!(values.len(), 100);
+    assert!(values.iter().all(|&value| value == 0), "Slice not zeroed");
+
+    for (i, value) in values.iter_mut().enumerate() {
+        *value = i as u32 * 3;
+    }
+    assert_eq!(values[99], 297);
+
+    unsafe { bt.free_slice(values) }.expect_success("Failed to free a slice");
+
+    // Empty slices are not allocated
+    let empty = unsafe { bt.allocate_slice::<u32>(MemoryType::LOADER_DATA, 0) }
+        .expect_success("Failed to allocate an empty slice");
+    assert!(empty.is_empty());
+    unsafe { bt.free_slice(empty) }.expect_success("Failed to free an empty slice");
+}
+
+fn allocate_pages_slice(bt: &BootServices) {
+    info!("Allocating pages as a byte slice");
+
+    let (addr, bytes) =
+        unsafe { bt.allocate_pages_slice(AllocateType::AnyPages, MemoryType::LOADER_DATA, 2) }
+            .expect_success("Failed to allocate pages");
+    assert_eq!(bytes.len(), 8192);
+    assert_eq!(bytes.as_ptr() as u64, addr, "Pages are not identity-mapped");
+    assert!(bytes.iter().all(|&byte| byte == 0), "Pages not zeroed");
+
+    bt.free_pages(addr, 2)
+        .expect_success("Failed to free pages");
+}
+
 // Simple test to ensure our custom allocator works with the `alloc` crate.
 fn vec_alloc() {
     info!("Allocating a vector through the `alloc` crate");
@@ -81,6 +225,67 @@ fn memmove(bt: &BootServices) {
     assert_eq!(dest, src, "Failed to copy memory");
 }
 
+// Test that the `uefi::mem` helpers work without the firmware routines.
+fn mem_fallback(bt: &BootServices) {
+    info!("Testing the boot services-free `copy_mem` / `set_mem` functions");
+
+    // Make the helpers behave as if boot services had been exited
+    uefi::mem::exit_boot_services();
+
+    let mut buffer = [0u8; 8];
+    unsafe {
+        uefi::mem::set_mem(buffer.as_mut_ptr(), 4, 1);
+    }
+    assert_eq!(buffer, [1, 1, 1, 1, 0, 0, 0, 0], "Failed to set memory");
+
+    // Overlapping copies must behave like `memmove` in both directions
+    let mut buffer = [1u8, 2, 3, 4, 5, 6];
+    unsafe {
+        uefi::mem::copy_mem(buffer.as_mut_ptr().add(2), buffer.as_ptr(), 4);
+    }
+    assert_eq!(buffer, [1, 2, 1, 2, 3, 4], "Failed to copy memory upwards");
+    unsafe {
+        uefi::mem::copy_mem(buffer.as_mut_ptr(), buffer.as_ptr().add(2), 4);
+    }
+    assert_eq!(
+        buffer,
+        [1, 2, 3, 4, 3, 4],
+        "Failed to copy memory downwards"
+    );
+
+    // Go back to using the firmware routines
+    unsafe {
+        uefi::mem::init(bt);
+    }
+}
+
+fn memory_map_into_small_buffer(bt: &BootServices) {
+    info!("Retrieving the memory map into a small buffer");
+
+    // Room for two descriptors, on the stack
+    let mut storage = [MemoryDescriptor::default(); 2];
+    let buffer = unsafe {
+        core::slice::from_raw_parts_mut(
+            storage.as_mut_ptr().cast::<u8>(),
+            mem::size_of_val(&storage),
+        )
+    };
+    let buffer_len = buffer.len();
+
+    let err = match bt.memory_map_into(buffer) {
+        Ok(_) => panic!("Memory map unexpectedly fits in two descriptors"),
+        Err(err) => err,
+    };
+    let (status, required) = err.split();
+    assert_eq!(status, Status::BUFFER_TOO_SMALL);
+    let required = required.expect("Required memory map size not reported");
+    assert!(required > buffer_len, "Implausible memory map size");
+    assert!(
+        required <= bt.memory_map_size() + 8 * mem::size_of::<MemoryDescriptor>(),
+        "Implausible memory map size"
+    );
+}
+
 fn memory_map(bt: &BootServices) {
     info!("Testing memory map functions");
 
@@ -114,3 +319,119 @@ fn memory_map(bt: &BootServices) {
     assert_eq!(phys_start, 0, "Memory does not start at address 0");
     assert!(page_count != 0, "Memory map entry has zero size");
 }
+
+fn region_containing(bt: &BootServices) {
+    info!("Looking up memory regions by address");
+
+    let buf_sz = bt.memory_map_size() + 8 * mem::size_of::<MemoryDescriptor>();
+    let mut buffer = vec![0; buf_sz];
+    let (_key, desc_iter) = bt
+        .memory_map(&mut buffer)
+        .expect_success("Failed to retrieve UEFI memory map");
+    let map: Vec<_> = desc_iter.collect();
+
+    let conventional = map
+        .iter()
+        .find(|desc| desc.ty == MemoryType::CONVENTIONAL)
+        .expect("No conventional memory in the memory map");
+    let start = conventional.phys_start;
+    let end = start + conventional.page_count * 4096;
+
+    for &addr in &[start, start + 123, end - 1] {
+        let desc = boot::region_containing(map.iter().copied(), addr)
+            .expect("Conventional memory address not found in the memory map");
+        assert_eq!(desc.phys_start, start);
+        assert_eq!(desc.ty, MemoryType::CONVENTIONAL);
+    }
+    if let Some(desc) = boot::region_containing(map.iter().copied(), end) {
+        assert_ne!(desc.phys_start, start, "Memory range end is not exclusive");
+    }
+}
+
+fn memory_map_fingerprint(bt: &BootServices) {
+    info!("Fingerprinting the memory map");
+
+    let buf_sz = bt.memory_map_size() + 8 * mem::size_of::<MemoryDescriptor>();
+    let mut buffer = vec![0; buf_sz];
+    let (_key, desc_iter) = bt
+        .memory_map(&mut buffer)
+        .expect_success("Failed to retrieve UEFI memory map");
+    let mut map: Vec<MemoryDescriptor> = desc_iter.copied().collect();
+
+    let fingerprint = boot::memory_map_fingerprint(&map);
+    let copy = map.clone();
+    assert_eq!(boot::memory_map_fingerprint(&copy), fingerprint);
+
+    map[0].page_count += 1;
+    assert_ne!(boot::memory_map_fingerprint(&map), fingerprint);
+    map[0].page_count -= 1;
+    map.swap(0, 1);
+    assert_ne!(boot::memory_map_fingerprint(&map), fingerprint);
+    assert_ne!(boot::memory_map_fingerprint(&map[1..]), fingerprint);
+}
+
+fn memory_map_summary(bt: &BootServices) {
+    info!("Testing memory map summary");
+
+    let buf_sz = bt.memory_map_size() + 8 * mem::size_of::<MemoryDescriptor>();
+    let mut buffer = vec![0; buf_sz];
+
+    // Count the pages of the memory map by hand, then summarize the same map.
+    // Nothing may allocate in between, or the two maps could differ.
+    let (_key, desc_iter) = bt
+        .memory_map(&mut buffer)
+        .expect_success("Failed to retrieve UEFI memory map");
+    let page_count: u64 = desc_iter.map(|desc| desc.page_count).sum();
+    let summary = bt
+        .memory_map_summary(&mut buffer)
+        .expect_success("Failed to summarize UEFI memory map");
+
+    assert!(summary.conventional > 0, "No conventional memory available");
+    assert_eq!(
+        summary.total(),
+        page_count,
+        "Memory summary does not cover the whole memory map"
+    );
+}
+
+fn memory_type_display() {
+    assert_eq!(format!("{}", MemoryType::CONVENTIONAL), "Conventional");
+    assert_eq!(format!("{}", MemoryType::ACPI_RECLAIM), "ACPIReclaim");
+    assert_eq!(format!("{}", MemoryType(0x7000_0001)), "OEM(0x70000001)");
+    assert_eq!(format!("{}", MemoryType(0x8000_0001)), "OS(0x80000001)");
+}
+
+fn runtime_descriptors(bt: &BootServices) {
+    info!("Testing runtime memory descriptors");
+
+    let buf_sz = bt.memory_map_size() + 8 * mem::size_of::<MemoryDescriptor>();
+    let mut buffer = vec![0; buf_sz];
+    let (_key, desc_iter) = bt
+        .memory_map(&mut buffer)
+        .expect_success("Failed to retrieve UEFI memory map");
+    let descriptors: Vec<MemoryDescriptor> = desc_iter.copied().collect();
+
+    // Lay out the runtime regions one after the other in the higher half
+    let mut virt = 0xFFFF_8000_0000_0000;
+    let relocated: Vec<MemoryDescriptor> = boot::runtime_descriptors(&descriptors)
+        .map(|desc| {
+            let relocated = desc.with_virtual(virt);
+            virt += desc.page_count * 4096;
+            relocated
+        })
+        .collect();
+
+    let runtime_count = descriptors.iter().filter(|desc| desc.is_runtime()).count();
+    assert!(
+        runtime_count > 0,
+        "There should be runtime services regions"
+    );
+    assert_eq!(relocated.len(), runtime_count);
+
+    let runtime_ranges = descriptors.iter().filter(|desc| desc.is_runtime());
+    for (desc, original) in relocated.iter().zip(runtime_ranges) {
+        assert_eq!(desc.phys_start, original.phys_start);
+        assert_eq!(desc.page_count, original.page_count);
+        assert!(desc.virt_start >= 0xFFFF_8000_0000_0000);
+    }
+}
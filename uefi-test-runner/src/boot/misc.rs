@@ -1,9 +1,40 @@
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::ffi::c_void;
+use core::{mem, ptr};
+use uefi::crc::Crc32;
 use uefi::prelude::*;
-use uefi::table::boot::{BootServices, EventType, TimerTrigger, Tpl};
+use uefi::proto::console::text::Output;
+use uefi::proto::misc::timestamp::Timestamp;
+use uefi::proto::Protocol;
+use uefi::table::boot::{
+    self, BootServices, EventType, LoopControl, MemoryType, TimerTrigger, Tpl,
+};
+use uefi::{Guid, Identify};
 
 pub fn test(bt: &BootServices) {
     info!("Testing timer...");
     test_timer(bt);
+    info!("Testing waiting for any event...");
+    test_wait_any(bt);
+    info!("Testing waiting for an event with a timeout...");
+    test_wait_timeout(bt);
+    info!("Testing retry of transient errors...");
+    test_retry(bt);
+    info!("Testing the user interface run loop...");
+    test_run_loop(bt);
+    info!("Testing busy loop pauses...");
+    test_cpu_relax(bt);
+    info!("Testing precise stall...");
+    test_precise_stall(bt);
+    info!("Testing protocol reinstallation...");
+    test_reinstall_protocol(bt);
+    info!("Testing multiple protocol installation...");
+    test_install_multiple_protocols(bt);
+    info!("Testing null pointers returned by the firmware...");
+    test_null_interfaces(bt);
+    info!("Testing CRC-32 computation...");
+    test_crc32(bt);
     info!("Testing watchdog...");
     test_watchdog(bt);
 }
@@ -23,3 +54,347 @@ fn test_timer(bt: &BootServices) {
     bt.wait_for_event(&mut events)
         .expect_success("Wait for event failed");
 }
+
+fn test_wait_any(bt: &BootServices) {
+    let create_timer = || {
+        unsafe { bt.create_event(EventType::TIMER, Tpl::APPLICATION, None) }
+            .expect_success("Failed to create TIMER event")
+    };
+    let short_timer = create_timer();
+    let long_timer = create_timer();
+    bt.set_timer(short_timer, TimerTrigger::Relative(5_0 /*00 ns */))
+        .expect_success("Failed to set timer");
+    bt.set_timer(long_timer, TimerTrigger::Relative(10_000_000 /* 1 s */))
+        .expect_success("Failed to set timer");
+
+    let events = [&long_timer, &short_timer];
+    let signaled = bt
+        .wait_any(&events)
+        .expect_success("Wait for any event failed");
+    assert!(
+        ptr::eq(signaled, &short_timer),
+        "The shortest timer should have been signaled first"
+    );
+
+    bt.set_timer(long_timer, TimerTrigger::Cancel)
+        .expect_success("Failed to cancel timer");
+    bt.close_event(short_timer)
+        .expect_success("Failed to close event");
+    bt.close_event(long_timer)
+        .expect_success("Failed to close event");
+}
+
+fn test_wait_timeout(bt: &BootServices) {
+    let signaled = bt
+        .wait_for_event_timeout(&mut [], 200_000)
+        .expect_success("Wait for event with timeout failed");
+    assert_eq!(signaled, None, "Only the timeout could have expired");
+
+    let timer = unsafe { bt.create_event(EventType::TIMER, Tpl::APPLICATION, None) }
+        .expect_success("Failed to create TIMER event");
+    bt.set_timer(timer, TimerTrigger::Relative(5_0 /*00 ns */))
+        .expect_success("Failed to set timer");
+    let signaled = bt
+        .wait_for_event_timeout(&mut [timer], 1_000_000)
+        .expect_success("Wait for event with timeout failed");
+    assert_eq!(signaled, Some(0), "The timer should have been signaled");
+    bt.close_event(timer)
+        .expect_success("Failed to close event");
+}
+
+fn test_retry(bt: &BootServices) {
+    let mut attempts = 0;
+    let value = boot::retry_on(bt, 5, 10, || {
+        attempts += 1;
+        if attempts < 3 {
+            Err(Status::NOT_READY.into())
+        } else {
+            Ok(42.into())
+        }
+    })
+    .expect_success("Retried operation failed");
+    assert_eq!(value, 42, "Retried operation returned the wrong value");
+    assert_eq!(attempts, 3, "Operation was not attempted three times");
+}
+
+fn test_run_loop(bt: &BootServices) {
+    // A fast periodic timer stands for the input, e.g. key presses
+    let input = unsafe { bt.create_event(EventType::TIMER, Tpl::APPLICATION, None) }
+        .expect_success("Failed to create TIMER event");
+    bt.set_timer(input, TimerTrigger::Periodic(10_000 /* 1 ms */))
+        .expect_success("Failed to set timer");
+
+    let rendered = Cell::new(0);
+    let mut handled = 0;
+    let frames = boot::run_loop(
+        bt,
+        &[input],
+        5_000,
+        |frame| {
+            assert_eq!(frame, rendered.get(), "Frames rendered out of order");
+            rendered.set(frame + 1);
+        },
+        |index| {
+            assert_eq!(index, 0, "Unknown input event signaled");
+            handled += 1;
+            if handled < 5 {
+                LoopControl::Continue
+            } else {
+                LoopControl::Break
+            }
+        },
+    )
+    .expect_success("Run loop failed");
+    assert_eq!(handled, 5, "Run loop did not stop when asked to");
+    assert!(frames >= 1, "The first frame was not rendered");
+    assert_eq!(frames, rendered.get(), "Wrong number of rendered frames");
+
+    bt.close_event(input)
+        .expect_success("Failed to close event");
+}
+
+fn test_precise_stall(bt: &BootServices) {
+    const STALL_US: usize = 500;
+
+    let timestamp = match bt.locate_protocol::<Timestamp>() {
+        Ok(timestamp) => unsafe {
+            &*timestamp
+                .expect("Warnings encountered while opening timestamp protocol")
+                .get()
+        },
+        Err(_) => {
+            warn!("Timestamp protocol is not supported, testing the fallback");
+            bt.precise_stall(None, STALL_US);
+            return;
+        }
+    };
+    let properties = timestamp
+        .get_properties()
+        .expect_success("Failed to query timestamp properties");
+
+    let start = timestamp.get_timestamp();
+    bt.precise_stall(Some(timestamp), STALL_US);
+    let end = timestamp.get_timestamp();
+
+    // The stall must not be shorter than requested, and should not be much
+    // longer than that either. The margin is generous, as emulators and host
+    // load can delay the end of the stall.
+    const MARGIN_US: u64 = 10_000;
+    let expected = properties.micros_to_ticks(STALL_US as u64);
+    let elapsed = properties.ticks_between(start, end);
+    let tolerance = properties.micros_to_ticks(MARGIN_US);
+    assert!(
+        elapsed >= expected,
+        "Stalled for too short a time: {} ticks instead of {}",
+        elapsed,
+        expected
+    );
+    assert!(
+        elapsed <= expected + tolerance,
+        "Stalled for too long: {} ticks instead of {}",
+        elapsed,
+        expected
+    );
+}
+
+/// Protocol which is only used to test protocol installation
+struct TestProtocol {
+    data: u32,
+}
+
+unsafe impl Identify for TestProtocol {
+    const GUID: Guid = Guid::from_values(
+        0x1a97_2918,
+        0x3f69,
+        0x4b5d,
+        0x8cb4,
+        [0xe5, 0x5d, 0x5f, 0x1c, 0x6c, 0x2a],
+    );
+}
+
+impl Protocol for TestProtocol {}
+
+/// Second protocol which is only used to test protocol installation
+struct OtherTestProtocol {
+    data: u32,
+}
+
+unsafe impl Identify for OtherTestProtocol {
+    const GUID: Guid = Guid::from_values(
+        0x6f0c_3b2e,
+        0x9d41,
+        0x4e7a,
+        0xa5d3,
+        [0x2b, 0x80, 0x47, 0xc1, 0x9e, 0x15],
+    );
+}
+
+impl Protocol for OtherTestProtocol {}
+
+fn test_reinstall_protocol(bt: &BootServices) {
+    let old = TestProtocol { data: 1 };
+    let new = TestProtocol { data: 2 };
+
+    unsafe {
+        let handle = bt
+            .install_protocol_interface(None, &TestProtocol::GUID, &old as *const _ as *mut c_void)
+            .expect_success("Failed to install protocol");
+
+        bt.reinstall_protocol_interface(handle, &old, &new)
+            .expect_success("Failed to reinstall protocol");
+
+        let located = bt
+            .locate_protocol::<TestProtocol>()
+            .expect_success("Failed to locate reinstalled protocol");
+        assert_eq!(
+            (*located.get()).data,
+            2,
+            "Located the old protocol instance"
+        );
+
+        bt.uninstall_protocol_interface(
+            handle,
+            &TestProtocol::GUID,
+            &new as *const _ as *mut c_void,
+        )
+        .expect_success("Failed to uninstall protocol");
+    }
+}
+
+fn test_install_multiple_protocols(bt: &BootServices) {
+    let first = TestProtocol { data: 3 };
+    let second = OtherTestProtocol { data: 4 };
+    let protocols = [
+        (TestProtocol::GUID, &first as *const _ as *const c_void),
+        (
+            OtherTestProtocol::GUID,
+            &second as *const _ as *const c_void,
+        ),
+    ];
+
+    unsafe {
+        let handle = bt
+            .install_multiple_protocol_interfaces(None, &protocols)
+            .expect_success("Failed to install protocols");
+
+        let located = bt
+            .handle_protocol::<TestProtocol>(handle)
+            .expect_success("Failed to find first protocol");
+        assert_eq!((*located.get()).data, 3);
+        let located = bt
+            .handle_protocol::<OtherTestProtocol>(handle)
+            .expect_success("Failed to find second protocol");
+        assert_eq!((*located.get()).data, 4);
+
+        // If an interface cannot be installed, those which were installed
+        // before it must be uninstalled.
+        let (protocol, interface) = protocols[0];
+        bt.uninstall_protocol_interface(handle, &protocol, interface as *mut c_void)
+            .expect_success("Failed to uninstall protocol");
+        let result = bt.install_multiple_protocol_interfaces(Some(handle), &protocols);
+        assert_eq!(
+            result.map(|_| ()).map_err(|e| e.status()),
+            Err(Status::INVALID_PARAMETER),
+            "Installed the second protocol twice"
+        );
+        assert!(
+            bt.handle_protocol::<TestProtocol>(handle).is_err(),
+            "Failed to roll back the installation of the first protocol"
+        );
+
+        let (protocol, interface) = protocols[1];
+        bt.uninstall_protocol_interface(handle, &protocol, interface as *mut c_void)
+            .expect_success("Failed to uninstall protocol");
+    }
+}
+
+fn test_cpu_relax(bt: &BootServices) {
+    for _ in 0..100 {
+        bt.cpu_relax();
+    }
+}
+
+// Misbehaving firmware which reports success but returns null pointers
+extern "efiapi" fn null_handle_protocol(
+    _handle: Handle,
+    _protocol: &Guid,
+    interface: &mut *mut c_void,
+) -> Status {
+    *interface = ptr::null_mut();
+    Status::SUCCESS
+}
+
+extern "efiapi" fn null_locate_protocol(
+    _protocol: &Guid,
+    _registration: *mut c_void,
+    interface: &mut *mut c_void,
+) -> Status {
+    *interface = ptr::null_mut();
+    Status::SUCCESS
+}
+
+extern "efiapi" fn null_allocate_pool(
+    _pool_type: MemoryType,
+    _size: usize,
+    buffer: &mut *mut u8,
+) -> Status {
+    *buffer = ptr::null_mut();
+    Status::SUCCESS
+}
+
+fn test_null_interfaces(bt: &BootServices) {
+    // Build a copy of the boot services table in which some functions are
+    // replaced. Function pointers follow the 24-byte table header, in the
+    // order of the UEFI specification.
+    const WORDS: usize = mem::size_of::<BootServices>() / mem::size_of::<usize>();
+    const FIRST_FN: usize = 24 / mem::size_of::<usize>();
+    let mut table = [0usize; WORDS];
+    unsafe {
+        ptr::copy_nonoverlapping(
+            bt as *const BootServices as *const usize,
+            table.as_mut_ptr(),
+            WORDS,
+        );
+    }
+    table[FIRST_FN + 5] = null_allocate_pool as *const () as usize;
+    table[FIRST_FN + 16] = null_handle_protocol as *const () as usize;
+    table[FIRST_FN + 37] = null_locate_protocol as *const () as usize;
+    let mock = unsafe { &*(table.as_ptr() as *const BootServices) };
+
+    let handle = bt
+        .find_handles::<Output>()
+        .expect_success("Failed to find a text output handle")[0];
+    assert_eq!(
+        error_status(mock.handle_protocol::<Output>(handle)),
+        Err(Status::NOT_FOUND)
+    );
+    assert_eq!(
+        error_status(mock.locate_protocol::<Output>()),
+        Err(Status::NOT_FOUND)
+    );
+    assert_eq!(
+        error_status(mock.allocate_pool(MemoryType::LOADER_DATA, 16)),
+        Err(Status::OUT_OF_RESOURCES)
+    );
+}
+
+fn error_status<T>(result: uefi::Result<T>) -> Result<(), Status> {
+    result.map(|_| ()).map_err(|err| err.status())
+}
+
+fn test_crc32(bt: &BootServices) {
+    let data: Vec<u8> = (0..1000u32).map(|i| (i * 7 + i / 13) as u8).collect();
+
+    let one_shot =
+        Crc32::from_firmware(bt, &data).expect_success("Failed to compute firmware CRC-32");
+    let mut crc = Crc32::new();
+    for chunk in &[&data[..1], &data[1..600], &data[600..]] {
+        crc.update(chunk);
+    }
+    assert_eq!(crc.finalize(), one_shot);
+
+    // Standard check value of CRC-32
+    let mut crc = Crc32::new();
+    crc.update(b"123456789");
+    assert_eq!(crc.finalize(), 0xCBF4_3926);
+}
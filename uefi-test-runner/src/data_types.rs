@@ -0,0 +1,287 @@
+use alloc::string::{String, ToString};
+use core::convert::TryFrom;
+use core::ptr;
+use uefi::data_types::{
+    self, CStr16, CStr8, CString16, CString8, EncodeOptions, FromSliceWithNulError, LineEnding,
+    StrDecodeError, StrEncodeError,
+};
+use uefi::{Char16, Ucs2Str, Utf16Str};
+
+pub fn test() {
+    info!("Testing string types");
+    ucs2_str();
+    cstring16_push();
+    cstr_matching();
+    line_endings();
+    decode_in_chunks();
+    null_pointers();
+    console_width();
+    lone_surrogates();
+    utf16_str();
+    latin1_str();
+    cstring8();
+}
+
+fn ucs2_str() {
+    // A length-delimited buffer, followed by unrelated data
+    let buffer = [0x48, 0x69, 0x21, 0xD800];
+    let s = Ucs2Str::from_u16(&buffer[..3]).expect("Failed to wrap length-delimited buffer");
+    assert_eq!(s.len(), 3);
+
+    let mut chars = s.chars();
+    for &expected in &['H', 'i', '!'] {
+        let c: char = chars.next().expect("String ended too early").into();
+        assert_eq!(c, expected);
+    }
+    assert!(chars.next().is_none(), "String should have ended");
+
+    assert_eq!(
+        Ucs2Str::from_u16(&buffer).err(),
+        Some(FromSliceWithNulError::InvalidChar(3))
+    );
+    assert_eq!(
+        Ucs2Str::from_u16(&[0x48, 0]).err(),
+        Some(FromSliceWithNulError::InteriorNul(1))
+    );
+}
+
+fn cstring16_push() {
+    let mut path = CString16::new();
+    path.push_str("EFI").unwrap();
+    path.push('\\').unwrap();
+    path.push_str("Boot").unwrap();
+    assert_eq!(path, CString16::try_from("EFI\\Boot").unwrap());
+    assert_eq!(path.to_u16_slice_with_nul().last(), Some(&0));
+
+    // Failed pushes leave the string untouched
+    assert_eq!(path.push('\u{1F600}'), Err(StrEncodeError::InvalidChar(0)));
+    assert_eq!(path.push_str("ab\0c"), Err(StrEncodeError::InteriorNul(2)));
+    assert_eq!(path, CString16::try_from("EFI\\Boot").unwrap());
+
+    // Strings can be collected from characters
+    let upper: CString16 = "abc".chars().map(|c| c.to_ascii_uppercase()).collect();
+    assert_eq!(upper, CString16::try_from("ABC").unwrap());
+    assert_eq!(upper.to_u16_slice_with_nul(), [0x41, 0x42, 0x43, 0]);
+    assert_eq!(
+        CString16::try_from_chars("ab\u{1F600}".chars()),
+        Err(StrEncodeError::InvalidChar(2))
+    );
+    assert_eq!(
+        CString16::try_from_chars("a\0".chars()),
+        Err(StrEncodeError::InteriorNul(1))
+    );
+}
+
+fn cstr_matching() {
+    let path = CString16::try_from("EFI\\Boot\\BootX64.efi").unwrap();
+    assert!(path.starts_with("EFI\\"));
+    assert!(path.ends_with(".efi"));
+    assert!(!path.ends_with(".EFI"));
+    assert!(!path.starts_with("EFI\\Boot\\BootX64.efi\\"));
+
+    let mut components = path.split_on('\\');
+    for &expected in &["EFI", "Boot", "BootX64.efi"] {
+        let expected = CString16::try_from(expected).unwrap();
+        assert_eq!(components.next(), Some(expected.to_u16_slice()));
+    }
+    assert_eq!(components.next(), None);
+
+    // The terminator is never a separator
+    assert_eq!(path.split_on('\0').count(), 1);
+
+    let name = CStr8::from_bytes_with_nul(b"Boot0001\0").unwrap();
+    assert!(name.starts_with("Boot") && name.ends_with("0001"));
+    let pieces = name.split_on('0').filter(|piece| !piece.is_empty());
+    assert!(pieces.eq([&b"Boot"[..], b"1"].iter().copied()));
+}
+
+fn line_endings() {
+    let input = "a\nb\r\nc";
+    let encode = |line_ending| {
+        let options = EncodeOptions {
+            line_ending,
+            ..EncodeOptions::default()
+        };
+        let mut buffer = [0; 16];
+        let len = data_types::encode_with_options(input, &mut buffer, options).unwrap();
+        String::from_utf16(&buffer[..len]).unwrap()
+    };
+    assert_eq!(encode(LineEnding::CrLf), "a\r\nb\r\nc");
+    assert_eq!(encode(LineEnding::Lf), "a\nb\nc");
+    assert_eq!(encode(LineEnding::Verbatim), input);
+
+    // Existing CR LF sequences are left alone
+    let mut buffer = [0; 4];
+    assert_eq!(data_types::encode("a\r\nb", &mut buffer), Ok(4));
+    assert_eq!(String::from_utf16(&buffer).unwrap(), "a\r\nb");
+
+    // The default is the console's line ending
+    assert_eq!(data_types::encode("a\n", &mut buffer), Ok(3));
+    assert_eq!(
+        data_types::encode("ab\n", &mut buffer),
+        Err(StrEncodeError::BufferOverflow)
+    );
+
+    // Unsupported characters can be replaced
+    assert_eq!(
+        data_types::encode("\u{1F600}", &mut buffer),
+        Err(StrEncodeError::InvalidChar(0))
+    );
+    let options = EncodeOptions {
+        replacement: Some(Char16::try_from('?').unwrap_or_default()),
+        ..EncodeOptions::default()
+    };
+    assert_eq!(
+        data_types::encode_with_options("\u{1F600}!", &mut buffer, options),
+        Ok(2)
+    );
+    assert_eq!(buffer[..2], [u16::from(b'?'), u16::from(b'!')]);
+}
+
+fn decode_in_chunks() {
+    let input = CString16::try_from("ab\r\nécd").unwrap();
+    let decode = |input, size, line_ending| {
+        let mut buffer = [0; 16];
+        data_types::decode(input, &mut buffer[..size], line_ending)
+            .map(|(s, rest)| (s.to_string(), rest.map(|rest| rest.to_string())))
+    };
+
+    // Everything fits
+    let all = decode(&input, 16, LineEnding::Lf).unwrap();
+    assert_eq!(all, ("ab\nécd".to_string(), None));
+    let verbatim = decode(&input, 16, LineEnding::Verbatim).unwrap();
+    assert_eq!(verbatim.0, "ab\r\nécd");
+
+    // A translated CR LF is not split at the end of the buffer
+    let (s, rest) = decode(&input, 2, LineEnding::Lf).unwrap();
+    assert_eq!((s.as_str(), rest.as_deref()), ("ab", Some("\r\nécd")));
+    let (s, rest) = decode(&input, 3, LineEnding::Lf).unwrap();
+    assert_eq!((s.as_str(), rest.as_deref()), ("ab\n", Some("écd")));
+    let (s, rest) = decode(&input, 3, LineEnding::Verbatim).unwrap();
+    assert_eq!((s.as_str(), rest.as_deref()), ("ab\r", Some("\nécd")));
+
+    // Neither is a multi-byte character
+    let (s, rest) = decode(&input, 4, LineEnding::Lf).unwrap();
+    assert_eq!((s.as_str(), rest.as_deref()), ("ab\n", Some("écd")));
+    let (s, rest) = decode(&input, 5, LineEnding::Lf).unwrap();
+    assert_eq!((s.as_str(), rest.as_deref()), ("ab\né", Some("cd")));
+
+    // Decoding the rest in a loop gives the whole string back
+    let mut decoded = String::new();
+    let mut rest = Some(&*input);
+    while let Some(input) = rest {
+        let mut buffer = [0; 2];
+        let (s, next) = data_types::decode(input, &mut buffer, LineEnding::Lf).unwrap();
+        decoded.push_str(s);
+        rest = next;
+    }
+    assert_eq!(decoded, all.0);
+
+    // No progress can be made with a too small buffer
+    assert_eq!(
+        decode(&input, 0, LineEnding::Lf),
+        Err(StrDecodeError::BufferOverflow)
+    );
+    let empty = CString16::new();
+    assert_eq!(decode(&empty, 0, LineEnding::Lf), Ok((String::new(), None)));
+}
+
+fn null_pointers() {
+    assert!(unsafe { CStr8::from_ptr_opt(ptr::null()) }.is_none());
+    assert!(unsafe { CStr16::from_ptr_opt(ptr::null()) }.is_none());
+
+    let name = CString16::try_from("BootX64.efi").unwrap();
+    let wrapped = unsafe { CStr16::from_ptr_opt(name.as_ptr()) };
+    assert_eq!(wrapped, Some(&*name));
+}
+
+fn console_width() {
+    let width = |s: &str| CString16::try_from(s).unwrap().console_width();
+    assert_eq!(width(""), 0);
+    assert_eq!(width("abc"), 3);
+    // Full-width Latin letter, ideographs, and Hangul syllables
+    assert_eq!(width("\u{FF21}"), 2);
+    assert_eq!(width("日本語"), 6);
+    assert_eq!(width("a한b"), 4);
+    // Narrow accented letters and half-width katakana
+    assert_eq!(width("é\u{FF76}"), 2);
+}
+
+fn lone_surrogates() {
+    // Firmware strings are not validated, and may contain UTF-16 surrogates
+    let codes = [0x41, 0xD800, 0x42, 0xDFFF, 0];
+    let s = unsafe { CStr16::from_ptr(codes.as_ptr().cast()) };
+    let decoded: String = s.chars().collect();
+    assert_eq!(decoded, "A\u{FFFD}B\u{FFFD}");
+    assert_eq!(format!("{}", s), decoded);
+    let c: char = (*s.iter().nth(1).unwrap()).into();
+    assert_eq!(c, core::char::REPLACEMENT_CHARACTER);
+}
+
+fn utf16_str() {
+    // "a😀b", where the emoji is encoded as a surrogate pair
+    let codes = [0x61, 0xD83D, 0xDE00, 0x62];
+    let s = Utf16Str::from_u16(&codes).expect("Failed to wrap UTF-16 buffer");
+    assert_eq!(s.len(), 4);
+    assert!(s.chars().eq(['a', '\u{1F600}', 'b'].iter().copied()));
+    assert_eq!(s.to_string(), "a\u{1F600}b");
+
+    // Unpaired surrogates are rejected
+    assert_eq!(
+        Utf16Str::from_u16(&[0x61, 0xD83D, 0x62]).err(),
+        Some(FromSliceWithNulError::InvalidChar(1))
+    );
+    assert_eq!(
+        Utf16Str::from_u16(&[0x61, 0x62, 0xDE00]).err(),
+        Some(FromSliceWithNulError::InvalidChar(2))
+    );
+    assert_eq!(
+        Utf16Str::from_u16(&[0x61, 0]).err(),
+        Some(FromSliceWithNulError::InteriorNul(1))
+    );
+}
+
+fn latin1_str() {
+    // "café" in Latin-1, where 0xE9 is a single byte
+    let s = CStr8::from_bytes_with_nul(&[0x63, 0x61, 0x66, 0xE9, 0]).unwrap();
+    let decoded: String = s.chars().collect();
+    assert_eq!(decoded, "caf\u{E9}");
+    assert_eq!(&decoded.as_bytes()[3..], &[0xC3, 0xA9]);
+    assert_eq!(s.to_string(), decoded);
+}
+
+fn cstring8() {
+    let ascii = CString8::try_from("EFI\\Boot").unwrap();
+    assert_eq!(ascii.to_bytes_with_nul(), b"EFI\\Boot\0");
+    assert_eq!(ascii.to_string(), "EFI\\Boot");
+
+    // Accented letters are single Latin-1 bytes
+    let accented = CString8::try_from("café").unwrap();
+    assert_eq!(accented.to_bytes(), [0x63, 0x61, 0x66, 0xE9]);
+    assert_eq!(accented.to_string(), "café");
+    let wrapped = unsafe { CStr8::from_ptr(accented.as_ptr()) };
+    assert_eq!(CString8::from(wrapped), accented);
+
+    // Characters beyond Latin-1 and null characters are rejected
+    assert_eq!(
+        CString8::try_from("\u{20AC}"),
+        Err(StrEncodeError::InvalidChar(0))
+    );
+    assert_eq!(
+        CString8::try_from("ab\0c"),
+        Err(StrEncodeError::InteriorNul(2))
+    );
+    let mut s = CString8::new();
+    s.push('a').unwrap();
+    assert_eq!(s.push('\0'), Err(StrEncodeError::InteriorNul(0)));
+    assert_eq!(s.push_str("b\u{3B1}"), Err(StrEncodeError::InvalidChar(1)));
+    assert_eq!(s.to_bytes_with_nul(), b"a\0");
+
+    // Interior nulls are rejected by the 16-bit strings too
+    assert_eq!(
+        CString16::try_from("é\0"),
+        Err(StrEncodeError::InteriorNul(1))
+    );
+    let round_trip = CString16::try_from("café").unwrap();
+    assert_eq!(round_trip.to_string(), "café");
+}
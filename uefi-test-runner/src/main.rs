@@ -8,13 +8,14 @@ extern crate log;
 #[macro_use]
 extern crate alloc;
 
-use core::mem;
 use uefi::prelude::*;
 use uefi::proto::console::serial::Serial;
-use uefi::table::boot::MemoryDescriptor;
 
 mod boot;
+mod data_types;
 mod proto;
+mod result;
+mod runtime;
 
 #[entry]
 fn efi_main(image: Handle, st: SystemTable<Boot>) -> Status {
@@ -29,12 +30,27 @@ fn efi_main(image: Handle, st: SystemTable<Boot>) -> Status {
     // Ensure the tests are run on a version of UEFI we support.
     check_revision(st.uefi_revision());
 
+    // Check the structure of the system table itself.
+    check_system_table(&st);
+
+    // Check the global tables provided by the utility library.
+    check_globals(&st);
+
+    // Test the status code helpers.
+    result::test();
+
+    // Test the string and character types.
+    data_types::test();
+
+    // Test the runtime service types.
+    runtime::test(st.runtime_services());
+
     // Test all the boot services.
     let bt = st.boot_services();
     boot::test(bt);
 
     // Test all the supported protocols.
-    proto::test(&st);
+    proto::test(image, &st);
 
     // TODO: test the runtime services.
     // These work before boot services are exited, but we'd probably want to
@@ -53,6 +69,32 @@ fn check_revision(rev: uefi::table::Revision) {
         minor >= 30,
         "Old version of UEFI 2, some features might not be available."
     );
+
+    assert_eq!(uefi::table::Revision::from_bits(rev.to_bits()), rev);
+
+    let packed = uefi::table::Revision::from_bits(0x0002_0046);
+    assert_eq!((packed.major(), packed.minor()), (2, 70));
+    assert_eq!(packed, uefi::table::Revision::new(2, 70));
+    assert_eq!(packed.to_bits(), 0x0002_0046);
+}
+
+fn check_system_table(st: &SystemTable<Boot>) {
+    use uefi::table::Table;
+
+    let header = st.header();
+    assert_eq!(header.signature, SystemTable::<Boot>::SIGNATURE);
+    assert_eq!(header.revision, st.uefi_revision());
+    assert_eq!(st.config_entry_count(), st.config_table().len());
+}
+
+fn check_globals(st: &SystemTable<Boot>) {
+    let bt = uefi_services::boot_services();
+    assert!(core::ptr::eq(bt, st.boot_services()));
+    let rt = uefi_services::runtime_services();
+    assert!(core::ptr::eq(rt, st.runtime_services()));
+
+    let global = uefi_services::system_table();
+    assert_eq!(global.firmware_revision(), st.firmware_revision());
 }
 
 /// Ask the test runner to check the current screen output against a reference
@@ -112,13 +154,26 @@ fn shutdown(image: uefi::Handle, st: SystemTable<Boot>) -> ! {
         info!("Testing complete, shutting down...");
     }
 
+    // Exit the image as a proof that it works, if asked to
+    if cfg!(feature = "shell") {
+        info!("Returning to the shell...");
+        uefi_services::deinit();
+        st.boot_services().exit(image, Status::SUCCESS, None);
+    }
+
+    // Tear down the utility library, after which logging must be a no-op.
+    uefi_services::deinit();
+    assert!(!log_enabled!(log::Level::Error), "Logging is still enabled");
+    error!("This message should not be displayed");
+
+    // Runtime services remain available
+    let rt = uefi_services::runtime_services();
+    assert!(core::ptr::eq(rt, st.runtime_services()));
+
     // Exit boot services as a proof that it works :)
-    let max_mmap_size =
-        st.boot_services().memory_map_size() + 8 * mem::size_of::<MemoryDescriptor>();
-    let mut mmap_storage = vec![0; max_mmap_size].into_boxed_slice();
-    let (st, _iter) = st
-        .exit_boot_services(image, &mut mmap_storage[..])
+    let (st, mmap) = uefi::table::boot::exit_boot_services(st, image)
         .expect_success("Failed to exit boot services");
+    assert!(mmap.len() > 0, "Final memory map is empty");
 
     // Shut down the system
     let rt = unsafe { st.runtime_services() };
@@ -1,24 +1,114 @@
+use alloc::vec::Vec;
 use uefi::prelude::*;
-use uefi::proto::console::gop::{BltOp, BltPixel, FrameBuffer, GraphicsOutput, PixelFormat};
+use uefi::proto::console::gop::{
+    self, BltOp, BltPixel, BltRegion, CursorOverlay, FrameBuffer, GraphicsOutput, PixelBitmask,
+    PixelFormat, Rect, TextRenderer,
+};
 use uefi::table::boot::BootServices;
 
 pub fn test(bt: &BootServices) {
     info!("Running graphics output protocol test");
+    srgb_conversions();
+    pixel_formats();
+    rect_geometry();
     if let Ok(gop) = bt.locate_protocol::<GraphicsOutput>() {
         let gop = gop.expect("Warnings encountered while opening GOP");
         let gop = unsafe { &mut *gop.get() };
 
+        sorted_modes(gop);
         set_graphics_mode(gop);
         fill_color(gop);
         draw_fb(gop);
 
         crate::check_screenshot(bt, "gop_test");
+
+        render_text(gop);
+        console_scroll(gop);
+        checked_pixels(gop);
+        blt_region(gop);
+        clipped_fill(gop);
+        cursor_overlay(gop);
+        scoped_access(bt);
+        protocol_cache(bt);
+        stale_frame_buffer(gop);
     } else {
         // No tests can be run.
         warn!("UEFI Graphics Output Protocol is not supported");
     }
 }
 
+// Locate the GOP repeatedly, only the first lookup reaching the firmware.
+fn protocol_cache(bt: &BootServices) {
+    use uefi::table::boot::ProtocolCache;
+
+    let mut cache = ProtocolCache::new(bt);
+    let first = cache
+        .locate::<GraphicsOutput>()
+        .expect_success("Failed to locate GOP through the cache");
+    let second = cache
+        .locate::<GraphicsOutput>()
+        .expect_success("Failed to locate GOP through the cache");
+    assert!(core::ptr::eq(first, second));
+    assert_eq!(
+        cache.firmware_lookups(),
+        1,
+        "Second lookup missed the cache"
+    );
+
+    // The protocol is located again once the cache is invalidated
+    cache.invalidate();
+    let third = cache
+        .locate::<GraphicsOutput>()
+        .expect_success("Failed to locate GOP through the cache");
+    assert!(core::ptr::eq(first, third));
+    assert_eq!(cache.firmware_lookups(), 2);
+}
+
+// Change the mode behind the back of a frame buffer, which must notice it.
+fn stale_frame_buffer(gop: &mut GraphicsOutput) {
+    if gop.current_mode_info().pixel_format() == PixelFormat::BltOnly {
+        return;
+    }
+    let (width, height) = gop.current_mode_info().resolution();
+    let mode = gop
+        .modes()
+        .map(|mode| mode.expect("Warnings encountered while querying mode"))
+        .find(|mode| mode.info().resolution() == (width, height))
+        .unwrap();
+
+    // The frame buffer borrows the protocol, so that this cannot happen in
+    // safe code. Frame buffers do not point into the protocol, so going
+    // through a raw pointer mimics the mode being set by another component.
+    let gop: *mut GraphicsOutput = gop;
+    let fb = unsafe { (*gop).frame_buffer() };
+    assert!(!fb.is_stale());
+
+    // Set the current mode again, which returns a fresh frame buffer
+    let fresh = unsafe { (*gop).set_mode(&mode) }
+        .expect_success("Failed to set graphics mode")
+        .expect("No frame buffer in the new mode");
+    assert!(fb.is_stale(), "Stale frame buffer not detected");
+    assert!(!fresh.is_stale());
+}
+
+// Use the protocol from a closure, without keeping a reference to it.
+fn scoped_access(bt: &BootServices) {
+    let resolution = bt
+        .with_protocol::<GraphicsOutput, _>(|gop| gop.current_mode_info().resolution())
+        .expect_success("Failed to use GOP from a closure");
+    assert_eq!(resolution, (1024, 768));
+
+    let handles = bt
+        .find_handles::<GraphicsOutput>()
+        .expect_success("Failed to find GOP handles");
+    let resolution = bt
+        .with_protocol_on::<GraphicsOutput, _>(handles[0], |gop| {
+            gop.current_mode_info().resolution()
+        })
+        .expect_success("Failed to use GOP on a handle from a closure");
+    assert_eq!(resolution, (1024, 768));
+}
+
 // Set a larger graphics mode.
 fn set_graphics_mode(gop: &mut GraphicsOutput) {
     // We know for sure QEMU has a 1024x768 mode.
@@ -35,6 +125,34 @@ fn set_graphics_mode(gop: &mut GraphicsOutput) {
         .expect_success("Failed to set graphics mode");
 }
 
+// List the modes by decreasing resolution, each resolution appearing once.
+fn sorted_modes(gop: &GraphicsOutput) {
+    let modes = gop.sorted_modes();
+    let resolutions: Vec<_> = modes.iter().map(|mode| mode.info().resolution()).collect();
+    for pair in resolutions.windows(2) {
+        let ((w1, h1), (w2, h2)) = (pair[0], pair[1]);
+        assert!(
+            w1 * h1 > w2 * h2 || (w1 * h1 == w2 * h2 && w1 > w2),
+            "Modes are not sorted or not deduplicated"
+        );
+    }
+
+    // Every resolution of the raw mode list is kept, with its best format
+    let rank = |format| match format {
+        PixelFormat::RGB | PixelFormat::BGR => 0,
+        PixelFormat::Bitmask => 1,
+        PixelFormat::BltOnly => 2,
+    };
+    for mode in gop.modes() {
+        let info = *mode.log().info();
+        let kept = modes
+            .iter()
+            .find(|kept| kept.info().resolution() == info.resolution())
+            .expect("A resolution was dropped");
+        assert!(rank(kept.info().pixel_format()) <= rank(info.pixel_format()));
+    }
+}
+
 // Fill the screen with color.
 fn fill_color(gop: &mut GraphicsOutput) {
     let op = BltOp::VideoFill {
@@ -89,3 +207,318 @@ fn draw_fb(gop: &mut GraphicsOutput) {
     fill_rectangle((50, 30), (150, 600), [250, 128, 64]);
     fill_rectangle((400, 120), (750, 450), [16, 128, 255]);
 }
+
+// Draw text into the frame buffer, and check that it shows up on screen.
+fn render_text(gop: &mut GraphicsOutput) {
+    let info = gop.current_mode_info();
+    if info.pixel_format() == PixelFormat::BltOnly {
+        info!("Text rendering is not supported in Blt-only modes");
+        return;
+    }
+
+    let mut renderer = TextRenderer::new(gop.frame_buffer(), info);
+    renderer.set_colors(BltPixel::new(255, 0, 0), BltPixel::new(0, 0, 0));
+    renderer.write_str("ERROR");
+
+    // Read back the five character cells that were drawn
+    let dims = (5 * 8, 16);
+    let mut buffer = vec![BltPixel::new(0, 0, 0); dims.0 * dims.1];
+    gop.blt(BltOp::VideoToBltBuffer {
+        buffer: &mut buffer,
+        src: (0, 0),
+        dest: BltRegion::Full,
+        dims,
+    })
+    .expect_success("Failed to read back rendered text");
+
+    let is_red = |px: &BltPixel| (px.red, px.green, px.blue) == (255, 0, 0);
+    let drawn = buffer.iter().filter(|px| is_red(px)).count();
+    assert!(drawn > 0, "No text was drawn");
+    assert!(drawn < buffer.len(), "Text background was not drawn");
+}
+
+// Write enough lines to scroll the screen, and check that the first one is gone.
+fn console_scroll(gop: &mut GraphicsOutput) {
+    use core::fmt::Write;
+
+    let info = gop.current_mode_info();
+    if info.pixel_format() == PixelFormat::BltOnly {
+        return;
+    }
+
+    let mut console = TextRenderer::new(gop.frame_buffer(), info);
+    console.set_colors(BltPixel::new(255, 0, 0), BltPixel::new(0, 0, 0));
+    console.clear();
+    let (_, rows) = console.dimensions();
+    writeln!(console, "first line").unwrap();
+    for row in 1..rows {
+        writeln!(console, "line {}", row).unwrap();
+    }
+    write!(console, "\tX").unwrap();
+
+    // Read back the first and last text lines
+    let width = 16 * 8;
+    let mut read_line = |row: usize| {
+        let mut buffer = vec![BltPixel::new(0, 0, 0); width * 16];
+        gop.blt(BltOp::VideoToBltBuffer {
+            buffer: &mut buffer,
+            src: (0, row * 16),
+            dest: BltRegion::Full,
+            dims: (width, 16),
+        })
+        .expect_success("Failed to read back console text");
+        buffer
+    };
+    let first = read_line(0);
+    let last = read_line(rows - 1);
+
+    let is_red = |px: &BltPixel| (px.red, px.green, px.blue) == (255, 0, 0);
+    let cell_drawn = |line: &[BltPixel], column: usize| {
+        (0..16).any(|y| (0..8).any(|x| is_red(&line[y * width + column * 8 + x])))
+    };
+    // The first line now holds "line 1", whose fifth cell is a space
+    assert!(cell_drawn(&first, 0), "Text did not scroll up");
+    assert!(!cell_drawn(&first, 4), "First line was not scrolled away");
+    // The tab moved the X to the first tab stop
+    assert!((0..8).all(|column| !cell_drawn(&last, column)));
+    assert!(cell_drawn(&last, 8), "Tab was not expanded");
+}
+
+// Write single pixels, with and without bound checking.
+fn checked_pixels(gop: &mut GraphicsOutput) {
+    let info = gop.current_mode_info();
+    if info.pixel_format() == PixelFormat::BltOnly {
+        info!("Direct pixel access is not supported in Blt-only modes");
+        return;
+    }
+    let (width, height) = info.resolution();
+    let green = BltPixel::new(0, 255, 0);
+
+    let mut fb = gop.frame_buffer();
+    fb.try_write_pixel(width - 1, height - 1, green)
+        .expect_success("Failed to write the last pixel of the screen");
+    unsafe { fb.write_pixel_unchecked(width - 2, height - 1, green) };
+    for &(x, y) in &[(width, 0), (0, height), (usize::MAX, usize::MAX)] {
+        let status = fb.try_write_pixel(x, y, green).map_err(|err| err.status());
+        assert_eq!(status, Err(Status::INVALID_PARAMETER));
+    }
+
+    let mut pixels = [BltPixel::new(0, 0, 0); 2];
+    gop.blt(BltOp::VideoToBltBuffer {
+        buffer: &mut pixels,
+        src: (width - 2, height - 1),
+        dest: BltRegion::Full,
+        dims: (2, 1),
+    })
+    .expect_success("Failed to read back written pixels");
+    for px in &pixels {
+        assert_eq!((px.red, px.green, px.blue), (0, 255, 0));
+    }
+}
+
+// Copy a small region of a larger back buffer to the screen.
+fn blt_region(gop: &mut GraphicsOutput) {
+    let black = BltPixel::new(0, 0, 0);
+    let green = BltPixel::new(0, 255, 0);
+    let is_green = |px: &BltPixel| (px.red, px.green, px.blue) == (0, 255, 0);
+
+    // A 100x100 back buffer, where only the region to copy is green
+    let src = Rect::new(20, 40, 10, 10);
+    let mut buffer = vec![black; 100 * 100];
+    for y in src.y..src.y + src.height {
+        for x in src.x..src.x + src.width {
+            buffer[y * 100 + x] = green;
+        }
+    }
+
+    // Clear the destination and its surroundings, then copy the region
+    gop.blt(BltOp::VideoFill {
+        color: black,
+        dest: (199, 299),
+        dims: (12, 12),
+    })
+    .expect_success("Failed to clear destination");
+    gop.blt_region(&buffer, 100, src, (200, 300))
+        .expect_success("Failed to copy buffer region");
+
+    // The green square must land exactly at the destination
+    let mut screen = vec![black; 12 * 12];
+    gop.blt(BltOp::VideoToBltBuffer {
+        buffer: &mut screen,
+        src: (199, 299),
+        dest: BltRegion::Full,
+        dims: (12, 12),
+    })
+    .expect_success("Failed to read back copied region");
+    for y in 0..12 {
+        for x in 0..12 {
+            let inside = (1..11).contains(&x) && (1..11).contains(&y);
+            assert_eq!(
+                is_green(&screen[y * 12 + x]),
+                inside,
+                "Wrong pixel at ({}, {}) after copying buffer region",
+                x,
+                y
+            );
+        }
+    }
+}
+
+// Fill a rectangle which sticks out of the bottom-right corner of the screen.
+fn clipped_fill(gop: &mut GraphicsOutput) {
+    let (width, height) = gop.current_mode_info().resolution();
+    let red = BltPixel::new(255, 0, 0);
+    gop.fill_rect(Rect::new(width - 4, height - 4, 100, 100), red)
+        .expect_success("Failed to fill a partially visible rectangle");
+    gop.fill_rect(Rect::new(width, 0, 10, 10), red)
+        .expect_success("Failed to fill an off-screen rectangle");
+
+    let mut corner = [BltPixel::new(0, 0, 0); 4 * 4];
+    gop.blt(BltOp::VideoToBltBuffer {
+        buffer: &mut corner,
+        src: (width - 4, height - 4),
+        dest: BltRegion::Full,
+        dims: (4, 4),
+    })
+    .expect_success("Failed to read back the corner");
+    for px in corner.iter() {
+        assert_eq!(
+            (px.red, px.green, px.blue),
+            (255, 0, 0),
+            "Corner not filled"
+        );
+    }
+
+    // Copies are clipped the same way
+    let buffer = [red; 10 * 10];
+    gop.blt_region(&buffer, 10, Rect::new(0, 0, 10, 10), (width - 2, 0))
+        .expect_success("Failed to copy a partially visible region");
+}
+
+// Intersect and clip rectangles.
+fn rect_geometry() {
+    let a = Rect::new(10, 10, 20, 20);
+    let b = Rect::new(25, 5, 20, 10);
+    assert_eq!(a.intersect(&b), Some(Rect::new(25, 10, 5, 5)));
+    assert_eq!(b.intersect(&a), a.intersect(&b));
+    assert_eq!(a.intersect(&a), Some(a));
+    assert!(a.contains(10, 29) && !a.contains(30, 10) && !a.contains(9, 10));
+
+    // Rectangles which only touch do not overlap
+    let disjoint = Rect::new(30, 10, 5, 5);
+    assert_eq!(a.intersect(&disjoint), None);
+    assert_eq!(a.intersect(&Rect::new(100, 100, 1, 1)), None);
+    assert!(Rect::new(3, 3, 0, 5).is_empty());
+    assert_eq!(a.intersect(&Rect::new(15, 15, 0, 0)), None);
+
+    // Clipping to a 640x480 screen
+    let screen = (640, 480);
+    let partial = Rect::new(600, 470, 100, 100);
+    assert_eq!(partial.clip_to(screen), Some(Rect::new(600, 470, 40, 10)));
+    assert_eq!(a.clip_to(screen), Some(a));
+    assert_eq!(Rect::new(640, 0, 10, 10).clip_to(screen), None);
+    let huge = Rect::new(usize::MAX - 1, 0, usize::MAX, 1);
+    assert_eq!(huge.clip_to(screen), None);
+}
+
+// Check the sRGB conversions at the endpoints and for mid-gray.
+fn srgb_conversions() {
+    for &value in &[0, 255] {
+        assert_eq!(gop::srgb_to_linear(value), value);
+        assert_eq!(gop::linear_to_srgb(value), value);
+    }
+    assert_eq!(gop::srgb_to_linear(128), 55);
+    assert_eq!(gop::linear_to_srgb(128), 188);
+    assert_eq!(gop::linear_to_srgb(55), 128);
+
+    let gray = BltPixel::from_srgb(128, 128, 128);
+    assert_eq!((gray.red, gray.green, gray.blue), (55, 55, 55));
+}
+
+// Convert pixels to and from the frame buffer formats.
+fn pixel_formats() {
+    let red = BltPixel::new(255, 0, 0);
+    let bgr = red.to_framebuffer_u32(PixelFormat::BGR, None);
+    let rgb = red.to_framebuffer_u32(PixelFormat::RGB, None);
+    assert_eq!(bgr, 0x00_FF_00_00);
+    assert_eq!(rgb, 0x00_00_00_FF);
+
+    // A 16-bit RGB565 format
+    let mask = PixelBitmask {
+        red: 0xF800,
+        green: 0x07E0,
+        blue: 0x001F,
+        reserved: 0,
+    };
+    let rgb565 = red.to_framebuffer_u32(PixelFormat::Bitmask, Some(mask));
+    assert_eq!(rgb565, 0xF800);
+
+    let color = BltPixel::new(12, 34, 56);
+    for &(format, mask) in &[
+        (PixelFormat::RGB, None),
+        (PixelFormat::BGR, None),
+        (PixelFormat::Bitmask, Some(mask)),
+    ] {
+        let value = red.to_framebuffer_u32(format, mask);
+        let decoded = BltPixel::from_framebuffer_u32(value, format, mask);
+        assert_eq!((decoded.red, decoded.green, decoded.blue), (255, 0, 0));
+        if mask.is_none() {
+            let value = color.to_framebuffer_u32(format, mask);
+            let decoded = BltPixel::from_framebuffer_u32(value, format, mask);
+            assert_eq!((decoded.red, decoded.green, decoded.blue), (12, 34, 56));
+        }
+    }
+
+    // Blt buffers use the BGR layout
+    let pixel = BltPixel::from(0x00_12_34_56);
+    assert_eq!((pixel.red, pixel.green, pixel.blue), (0x12, 0x34, 0x56));
+}
+
+// Move a cursor around, the pixels it covered must be restored.
+fn cursor_overlay(gop: &mut GraphicsOutput) {
+    let blue = BltPixel::new(0, 0, 255);
+    let read_pixel = |gop: &mut GraphicsOutput, x, y| {
+        let mut pixel = [blue];
+        gop.blt(BltOp::VideoToBltBuffer {
+            buffer: &mut pixel,
+            src: (x, y),
+            dest: BltRegion::Full,
+            dims: (1, 1),
+        })
+        .expect_success("Failed to read back pixel");
+        let [pixel] = pixel;
+        (pixel.red, pixel.green, pixel.blue)
+    };
+
+    gop.blt(BltOp::VideoFill {
+        color: blue,
+        dest: (0, 0),
+        dims: (64, 64),
+    })
+    .expect_success("Failed to fill screen region");
+
+    // An opaque white 8x8 square
+    let image = [0xFFFF_FFFF; 8 * 8];
+    let mut cursor = CursorOverlay::new(&image, 8, 8);
+    cursor
+        .show(gop, 10, 10)
+        .expect_success("Failed to show cursor");
+    assert_eq!(read_pixel(gop, 12, 12), (255, 255, 255), "Cursor not drawn");
+
+    cursor
+        .show(gop, 40, 40)
+        .expect_success("Failed to move cursor");
+    assert_eq!(read_pixel(gop, 12, 12), (0, 0, 255), "Pixels not restored");
+    assert_eq!(read_pixel(gop, 47, 47), (255, 255, 255), "Cursor not moved");
+
+    // Only part of the cursor fits at the bottom-right corner of the screen
+    let (width, height) = gop.current_mode_info().resolution();
+    cursor
+        .show(gop, width - 3, height - 2)
+        .expect_success("Failed to move cursor to the corner");
+    let shown = cursor.shown_region().expect("Cursor should be shown");
+    assert_eq!((shown.width, shown.height), (3, 2), "Cursor not clipped");
+    assert_eq!(read_pixel(gop, 47, 47), (0, 0, 255), "Pixels not restored");
+
+    cursor.hide(gop).expect_success("Failed to hide cursor");
+}
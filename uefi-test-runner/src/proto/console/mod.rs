@@ -1,17 +1,42 @@
 use uefi::prelude::*;
+use uefi::proto::console::text::OutputMode;
 
 pub fn test(st: &SystemTable<Boot>) {
     info!("Testing console protocols");
 
     stdout::test(st.stdout());
+    setup_console(st);
 
     let bt = st.boot_services();
+    stdin::test(st);
     serial::test(bt);
     gop::test(bt);
     pointer::test(bt);
 }
 
+// Set up the console, which must end up in its largest text mode.
+fn setup_console(st: &SystemTable<Boot>) {
+    st.setup_console()
+        .expect_success("Failed to set up the console");
+
+    let stdout = st.stdout();
+    let area = |mode: &OutputMode| mode.columns() * mode.rows();
+    let largest = stdout
+        .modes()
+        .map(|mode| area(&mode.unwrap()))
+        .max()
+        .expect("No text mode available");
+    let current = stdout
+        .current_mode()
+        .expect_success("Failed to query the current text mode")
+        .expect("No current text mode");
+    assert_eq!(area(&current), largest, "Console not in its largest mode");
+
+    stdout.reset(false).unwrap_success();
+}
+
 mod gop;
 mod pointer;
 mod serial;
+mod stdin;
 mod stdout;
@@ -1,9 +1,14 @@
+use alloc::vec::Vec;
 use uefi::prelude::*;
-use uefi::proto::console::pointer::Pointer;
+use uefi::proto::console::pointer::{
+    self, AbsolutePointer, Pointer, PointerSource, PointerState, PointerTracker,
+};
 use uefi::table::boot::BootServices;
 
 pub fn test(bt: &BootServices) {
     info!("Running pointer protocol test");
+    tracker();
+
     if let Ok(pointer) = bt.locate_protocol::<Pointer>() {
         let pointer = pointer.expect("Warnings encountered while opening pointer protocol");
         let pointer = unsafe { &mut *pointer.get() };
@@ -24,4 +29,80 @@ pub fn test(bt: &BootServices) {
     } else {
         warn!("No pointer device found");
     }
+
+    wait_for_input(bt);
+}
+
+// Wait for input from all available pointer devices, which should time out.
+fn wait_for_input(bt: &BootServices) {
+    // Waiting for no input at all is an error
+    let error = pointer::wait_for_input(bt, &mut [], Some(10_000))
+        .expect_error("Empty set of sources not rejected");
+    assert_eq!(error.status(), Status::INVALID_PARAMETER);
+
+    let mut sources = Vec::new();
+    if let Ok(pointer) = bt.locate_protocol::<Pointer>() {
+        let pointer = pointer.expect("Warnings encountered while opening pointer protocol");
+        let pointer = unsafe { &mut *pointer.get() };
+        let tracker = PointerTracker::new(pointer.mode().resolution(), (1024, 768), 4);
+        sources.push(PointerSource::Relative(pointer, tracker));
+    }
+    if let Ok(pointer) = bt.locate_protocol::<AbsolutePointer>() {
+        let pointer = pointer.expect("Warnings encountered while opening absolute pointer");
+        let pointer = unsafe { &mut *pointer.get() };
+        sources.push(PointerSource::Absolute(pointer, (1024, 768)));
+    } else {
+        info!("No absolute pointer device found");
+    }
+    if sources.is_empty() {
+        return;
+    }
+
+    let event = pointer::wait_for_input(bt, &mut sources, Some(10_000))
+        .expect_success("Failed to wait for pointer input");
+    if let Some(event) = event {
+        info!("Pointer input: {:?}", event);
+        assert!(event.source < sources.len());
+        assert!(event.position.0 < 1024 && event.position.1 < 768);
+    } else {
+        info!("No pointer input before the timeout");
+    }
+}
+
+// Accumulate synthetic movements, the position must stay on the screen.
+fn tracker() {
+    let state = |dx, dy, dz| PointerState {
+        relative_movement: (dx, dy, dz),
+        button: (true, false),
+    };
+
+    // 4 counts/mm on X and Y, no scroll wheel, and 2 pixels/mm on screen
+    let mut tracker = PointerTracker::new((4, 4, 0), (100, 50), 2);
+    assert_eq!(tracker.position(), (50, 25));
+
+    tracker.update(&state(10, -6, 3));
+    assert_eq!(tracker.position(), (55, 22));
+    assert_eq!(tracker.buttons(), (true, false));
+    assert_eq!(tracker.take_scroll(), 0, "Unsupported axis not ignored");
+
+    // Fractions of pixels add up
+    tracker.update(&state(1, 0, 0));
+    tracker.update(&state(1, 0, 0));
+    assert_eq!(tracker.position(), (56, 22));
+
+    // The position is clamped to the screen edges
+    tracker.update(&state(1000, 1000, 0));
+    assert_eq!(tracker.position(), (99, 49));
+    tracker.update(&state(-1000, -1000, 0));
+    assert_eq!(tracker.position(), (0, 0));
+    tracker.update(&state(2, 2, 0));
+    assert_eq!(tracker.position(), (1, 1));
+
+    // The scroll wheel is tracked separately from the position
+    let mut tracker = PointerTracker::new((4, 4, 1), (100, 50), 2);
+    tracker.update(&state(0, 0, -3));
+    tracker.update(&state(0, 0, 1));
+    assert_eq!(tracker.position(), (50, 25));
+    assert_eq!(tracker.take_scroll(), -2);
+    assert_eq!(tracker.take_scroll(), 0);
 }
@@ -0,0 +1,101 @@
+use uefi::data_types::chars::NUL_16;
+use uefi::prelude::*;
+use uefi::proto::console::text::{InputEx, KeyData};
+
+pub fn test(st: &SystemTable<Boot>) {
+    read_line(st);
+
+    info!("Running extended text input protocol test");
+    let bt = st.boot_services();
+    if let Ok(input) = bt.locate_protocol::<InputEx>() {
+        let input = input.expect("Warnings encountered while opening extended input protocol");
+        let input = unsafe { &mut *input.get() };
+
+        input
+            .reset(false)
+            .expect_success("Failed to reset extended input device");
+
+        // Nobody is there to press a key when running in QEMU, so only wait
+        // for one when running interactively.
+        if !cfg!(feature = "qemu") {
+            info!("Press any key, optionally with modifiers...");
+            bt.wait_for_event(&mut [input.wait_for_key_event()])
+                .discard_errdata()
+                .expect_success("Failed to wait for a key");
+        }
+
+        match input.read_key().expect_success("Failed to read key") {
+            Some(key) => log_key(key),
+            None => info!("No key was pressed"),
+        }
+    } else {
+        warn!("Extended text input protocol is not supported");
+    }
+}
+
+fn read_line(st: &SystemTable<Boot>) {
+    info!("Running text input protocol test");
+    st.stdin()
+        .reset(false)
+        .expect_success("Failed to reset input device");
+
+    // Nobody is there to type a line when running in QEMU
+    if !cfg!(feature = "qemu") {
+        info!("Type a line of text, then press Enter...");
+        let mut buf = [NUL_16; 64];
+        let line = st.read_line(&mut buf).expect_success("Failed to read line");
+        info!("Read line: {}", line);
+    }
+
+    drain(st);
+    skippable_delay(st);
+}
+
+fn skippable_delay(st: &SystemTable<Boot>) {
+    info!("Waiting for a second, or until a key is pressed");
+    let key_event = st.stdin().wait_for_key_event();
+    let skipped = st
+        .boot_services()
+        .stall_or_event(1_000_000, &key_event)
+        .expect_success("Failed to wait for a key or a timeout");
+
+    // Nobody is there to press a key when running in QEMU
+    if cfg!(feature = "qemu") {
+        assert!(!skipped, "The delay was skipped without a key press");
+    } else if skipped {
+        st.stdin().drain().expect_success("Failed to drain input");
+    }
+}
+
+fn drain(st: &SystemTable<Boot>) {
+    info!("Draining buffered keystrokes");
+    let stdin = st.stdin();
+
+    // Give an interactive user a chance to type a few keys beforehand
+    if !cfg!(feature = "qemu") {
+        info!("Type a few keys within the next 3 seconds...");
+        st.boot_services().stall(3_000_000);
+    }
+
+    let discarded = stdin.drain().expect_success("Failed to drain input");
+    if cfg!(feature = "qemu") {
+        assert_eq!(discarded, 0, "Nobody should have typed anything");
+    } else {
+        info!("Discarded {} keystroke(s)", discarded);
+    }
+
+    // Once drained, no keystroke should be left
+    assert_eq!(stdin.drain().expect_success("Failed to drain input"), 0);
+}
+
+fn log_key(key: KeyData) {
+    let state = key.state;
+    info!(
+        "Key {:?} (shift: {}, ctrl: {}, alt: {}, caps lock: {})",
+        key.key,
+        state.shift(),
+        state.ctrl(),
+        state.alt(),
+        state.caps_lock()
+    );
+}
@@ -1,12 +1,40 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt::Write;
+use log::Log;
+use uefi::logger::Logger;
 use uefi::prelude::*;
-use uefi::proto::console::text::{Color, Output};
+use uefi::proto::console::text::{
+    BoundedWriter, BufferedConsole, CaptureOutput, Color, Output, ProgressBar, Spinner, TextOutput,
+};
+use uefi::proto::Protocol;
+use uefi::{CStr16, Identify};
 
 pub fn test(stdout: &mut Output) {
     info!("Running text output protocol test");
 
+    assert_eq!(stdout.guid(), Output::GUID);
+
     change_text_mode(stdout);
+    sync_after_mode_change(stdout);
     change_color(stdout);
+    nested_colors(stdout);
     center_text(stdout);
+    restore_cursor(stdout);
+    redundant_cursor_toggle(stdout);
+    beep(stdout);
+    progress(stdout);
+    bounded_region(stdout);
+    capture();
+    capture_log();
+    buffered(stdout);
+
+    // Check that strict mode enumeration finds the same modes
+    let strict_modes = stdout
+        .try_modes()
+        .expect_success("Failed to query text modes")
+        .count();
+    assert_eq!(strict_modes, stdout.modes().count());
 
     // Print all modes.
     for (index, mode) in stdout.modes().enumerate() {
@@ -35,6 +63,29 @@ fn change_text_mode(stdout: &mut Output) {
         .expect_success("Failed to change text mode");
 }
 
+// Synchronize with the new mode without clearing the screen.
+fn sync_after_mode_change(stdout: &mut Output) {
+    let mode = stdout
+        .current_mode()
+        .expect_success("Failed to query current text mode")
+        .expect("No current text mode");
+    stdout.set_cursor_position(3, 1).unwrap_success();
+    stdout
+        .sync()
+        .expect_success("Failed to synchronize with the text mode");
+
+    assert_eq!(stdout.cursor_position(), (0, 0));
+    let synced = stdout
+        .current_mode()
+        .expect_success("Failed to query current text mode")
+        .expect("No current text mode");
+    assert_eq!(synced.index(), mode.index());
+    assert_eq!(
+        (synced.columns(), synced.rows()),
+        (mode.columns(), mode.rows())
+    );
+}
+
 // Set a new color, and paint the background with it.
 fn change_color(stdout: &mut Output) {
     stdout
@@ -43,6 +94,28 @@ fn change_color(stdout: &mut Output) {
     stdout.clear().expect_success("Failed to clear screen");
 }
 
+// Nest color changes, which are undone in reverse order.
+fn nested_colors(stdout: &mut Output) {
+    let original = stdout.attribute();
+    {
+        let mut error = stdout
+            .with_color(Color::LightRed, Color::Black)
+            .expect_success("Failed to change console color");
+        let outer = error.attribute();
+        assert_ne!(outer, original);
+        assert_eq!(error.saved_attribute(), original);
+        {
+            let detail = error
+                .with_color(Color::Yellow, Color::Black)
+                .expect_success("Failed to change console color");
+            assert_ne!(detail.attribute(), outer);
+            assert_eq!(detail.saved_attribute(), outer);
+        }
+        assert_eq!(error.attribute(), outer);
+    }
+    assert_eq!(stdout.attribute(), original);
+}
+
 // Print a text centered on screen.
 fn center_text(stdout: &mut Output) {
     // Move the cursor.
@@ -66,3 +139,218 @@ fn center_text(stdout: &mut Output) {
             _ => panic!("Failed to hide cursor"),
         });
 }
+
+// Mess with the cursor, then check that it gets restored.
+fn restore_cursor(stdout: &mut Output) {
+    let state = stdout.cursor_state();
+    {
+        let mut stdout = stdout.save_cursor();
+        stdout
+            .set_cursor_position(4, 2)
+            .expect_success("Failed to move cursor");
+        stdout
+            .enable_cursor(!state.visible)
+            .warning_as_error()
+            .unwrap_or_else(|e| match e.status() {
+                Status::UNSUPPORTED => info!("Cursor visibility control unavailable"),
+                _ => panic!("Failed to toggle cursor visibility"),
+            });
+    }
+    assert_eq!(
+        stdout.cursor_state(),
+        state,
+        "Failed to restore cursor state"
+    );
+}
+
+// Requesting the current cursor visibility again is harmless.
+fn redundant_cursor_toggle(stdout: &mut Output) {
+    let original = stdout.cursor_visible();
+
+    // No firmware call is needed, so this works even without cursor control
+    stdout
+        .enable_cursor(original)
+        .expect_success("Failed to keep cursor visibility");
+    assert_eq!(stdout.cursor_visible(), original);
+
+    for &visible in &[!original, !original, original, original] {
+        match stdout.enable_cursor(visible).warning_as_error() {
+            Ok(()) => assert_eq!(stdout.cursor_visible(), visible),
+            Err(e) if e.status() == Status::UNSUPPORTED => {
+                info!("Cursor visibility control unavailable");
+                return;
+            }
+            Err(_) => panic!("Failed to toggle cursor visibility"),
+        }
+    }
+}
+
+// The bell may or may not ring, but must not be an error.
+fn beep(stdout: &mut Output) {
+    stdout.beep().expect("Failed to ring the bell").log();
+}
+
+// Draw progress indicators, which must not move the cursor.
+fn progress(stdout: &mut Output) {
+    let state = stdout.cursor_state();
+
+    let mut spinner = Spinner::new();
+    for _ in 0..8 {
+        spinner
+            .tick(stdout)
+            .expect_success("Failed to draw spinner");
+    }
+
+    // A bar wider than the screen gets truncated
+    let (_, row) = stdout.cursor_position();
+    for width in &[20, 500] {
+        let bar = ProgressBar::new(row, *width);
+        for done in 0..=10 {
+            bar.update(stdout, done, 10)
+                .expect_success("Failed to draw progress bar");
+        }
+    }
+
+    assert_eq!(
+        stdout.cursor_state(),
+        state,
+        "Progress indicators moved the cursor"
+    );
+}
+
+// Write more lines than fit in a region, only the last ones must remain.
+fn bounded_region(stdout: &mut Output) {
+    let state = stdout.cursor_state();
+    let (_, row) = stdout.cursor_position();
+
+    {
+        let mut cells = [0; 3 * 16];
+        let mut region = BoundedWriter::new(stdout, (8, row), (16, 3), &mut cells);
+        region.clear().expect_success("Failed to clear region");
+        write!(region, "one\ntwo\nthree\nfour\nfive, which wraps around").unwrap();
+
+        let expected = ["four            ", "five, which wrap", "s around        "];
+        for (row, text) in expected.iter().enumerate() {
+            assert!(
+                region
+                    .row(row)
+                    .iter()
+                    .copied()
+                    .eq(text.bytes().map(u16::from)),
+                "Unexpected text in row {} of the region",
+                row
+            );
+        }
+
+        // A line feed right after a full line does not add a blank line
+        region.clear().expect_success("Failed to clear region");
+        write!(region, "exactly 16 chars\nnext").unwrap();
+        let expected = ["exactly 16 chars", "next            ", "                "];
+        for (row, text) in expected.iter().enumerate() {
+            assert!(
+                region
+                    .row(row)
+                    .iter()
+                    .copied()
+                    .eq(text.bytes().map(u16::from)),
+                "Unexpected text in row {} of the region",
+                row
+            );
+        }
+    }
+
+    assert_eq!(
+        stdout.cursor_state(),
+        state,
+        "Writing to the region moved the cursor"
+    );
+}
+
+// Formatted text written to a capture buffer is recorded as UCS-2.
+fn capture() {
+    let mut capture = CaptureOutput::new();
+    writeln!(capture, "Answer: {}", 42).expect("Failed to write to capture");
+    write!(capture, "é").expect("Failed to write to capture");
+
+    let expected: Vec<u16> = "Answer: 42\r\né".encode_utf16().collect();
+    assert_eq!(capture.codes(), &expected[..]);
+
+    capture.clear();
+    assert!(capture.codes().is_empty());
+
+    // Numbers can be written without the formatting machinery
+    for &(value, hex, dec) in &[
+        (0xDEAD_BEEF, "DEADBEEF", "3735928559"),
+        (0, "0", "0"),
+        (u64::MAX, "FFFFFFFFFFFFFFFF", "18446744073709551615"),
+    ] {
+        capture.clear();
+        capture
+            .write_hex(value)
+            .expect_success("Failed to write a hexadecimal number");
+        assert_eq!(String::from_utf16(capture.codes()).unwrap(), hex);
+        capture.clear();
+        capture
+            .write_dec(value)
+            .expect_success("Failed to write a decimal number");
+        assert_eq!(String::from_utf16(capture.codes()).unwrap(), dec);
+    }
+}
+
+// The logger can write to any text output, e.g. a capture buffer.
+fn capture_log() {
+    let mut capture = CaptureOutput::new();
+    {
+        let logger = unsafe { Logger::new(&mut capture) };
+        logger.log(
+            &log::Record::builder()
+                .level(log::Level::Warn)
+                .args(format_args!("first\nsecond"))
+                .build(),
+        );
+    }
+
+    let expected: Vec<u16> = "WARN: first\r\nWARN: second\r\n".encode_utf16().collect();
+    assert_eq!(capture.codes(), &expected[..]);
+}
+
+// Buffered text is written out in whole lines, or in chunks of bounded size.
+fn buffered(stdout: &mut Output) {
+    // Records the text and the number of writes it was split into
+    #[derive(Default)]
+    struct CountingOutput {
+        codes: Vec<u16>,
+        writes: usize,
+    }
+
+    impl TextOutput for CountingOutput {
+        fn write_str16(&mut self, string: &CStr16) -> uefi::Result {
+            self.codes.extend_from_slice(string.to_u16_slice());
+            self.writes += 1;
+            Ok(().into())
+        }
+    }
+
+    let mut output = CountingOutput::default();
+    let mut expected = String::new();
+    {
+        let mut console = BufferedConsole::new(&mut output);
+        for line in 0..50 {
+            writeln!(console, "Line {} of 50", line).expect("Failed to write line");
+            expected.push_str(&format!("Line {} of 50\r\n", line));
+        }
+        write!(console, "{}", "x".repeat(300)).expect("Failed to write long text");
+        expected.push_str(&"x".repeat(300));
+    }
+    let expected: Vec<u16> = expected.encode_utf16().collect();
+    assert_eq!(output.codes, expected);
+    // One write per line, then two full buffers and the rest on drop
+    assert_eq!(output.writes, 50 + 3);
+
+    // Characters which UCS-2 cannot represent are rejected
+    let mut console = BufferedConsole::new(&mut output);
+    assert!(write!(console, "\u{1F600}").is_err());
+
+    let mut console = BufferedConsole::new(stdout);
+    writeln!(console, "Buffered output: {}", 42).expect("Failed to write to stdout");
+}
@@ -1,15 +1,132 @@
-use uefi::proto::debug::DebugSupport;
+use core::sync::atomic::{AtomicU64, Ordering};
+use log::Log;
+use uefi::logger::Logger;
+use uefi::prelude::*;
+use uefi::proto::debug::{DebugPort, DebugSupport, ExceptionType, ProcessorArch, SystemContext};
 use uefi::table::boot::BootServices;
 
 pub fn test(bt: &BootServices) {
     info!("Running UEFI debug connection protocol test");
+    arch_check();
+
     if let Ok(debug_support) = bt.locate_protocol::<DebugSupport>() {
         let debug_support =
             debug_support.expect("Warnings encountered while opening debug support protocol");
         let debug_support = unsafe { &mut *debug_support.get() };
 
-        info!("- Architecture: {:?}", debug_support.arch());
+        info!("- Architecture: {}", debug_support.arch());
+        exception_callback(debug_support);
     } else {
         warn!("Debug protocol is not supported");
     }
+
+    if let Ok(debug_port) = bt.locate_protocol::<DebugPort>() {
+        let debug_port = debug_port.expect("Warnings encountered while opening debug port");
+        log_to_debug_port(unsafe { &mut *debug_port.get() });
+    } else {
+        warn!("Debug port is not available");
+    }
+}
+
+// Send a log record to the debugger.
+fn log_to_debug_port(debug_port: &mut DebugPort) {
+    let logger = unsafe { Logger::new(debug_port) };
+    logger.log(
+        &log::Record::builder()
+            .level(log::Level::Info)
+            .args(format_args!("Hello from the uefi-rs test runner"))
+            .build(),
+    );
+}
+
+// Callback registration must be refused when the protocol's architecture does
+// not match the one we were compiled for.
+fn arch_check() {
+    let current = ProcessorArch::current();
+    assert!(current.accepts_native_callbacks());
+    assert!(ProcessorArch::EBC.accepts_native_callbacks());
+
+    let other = if current == ProcessorArch::ITANIUM {
+        ProcessorArch::ARM
+    } else {
+        ProcessorArch::ITANIUM
+    };
+    assert!(!other.accepts_native_callbacks());
+
+    // Every architecture is listed once, with a name of its own
+    let all = ProcessorArch::all();
+    assert!(all.contains(&current));
+    for (i, arch) in all.iter().enumerate() {
+        assert!(!all[..i].contains(arch), "{} is listed twice", arch);
+        assert!(!format!("{}", arch).starts_with("Unknown"));
+    }
+    assert_eq!(all.len(), 9);
+    assert_eq!(format!("{}", ProcessorArch::X86_64), "x86-64");
+    assert_eq!(format!("{}", ProcessorArch::AARCH_64), "AArch64");
+    assert_eq!(format!("{}", ProcessorArch(0x1234)), "Unknown(0x1234)");
+}
+
+/// Instruction pointer of the last breakpoint caught by `breakpoint_callback`
+static BREAKPOINT_IP: AtomicU64 = AtomicU64::new(0);
+
+extern "efiapi" fn breakpoint_callback(_: ExceptionType, context: SystemContext) {
+    if ProcessorArch::current() == ProcessorArch::X86_64 {
+        let rip = unsafe { context.x64() }.rip;
+        BREAKPOINT_IP.store(rip, Ordering::SeqCst);
+    }
+}
+
+// Catch a breakpoint on the boot processor, and read its register state.
+fn exception_callback(debug_support: &mut DebugSupport) {
+    if debug_support.arch() != ProcessorArch::current() {
+        info!("Skipping exception callback test on a foreign architecture");
+        return;
+    }
+
+    let max_processor_index = debug_support
+        .get_maximum_processor_index()
+        .expect_success("Failed to query maximum processor index");
+    let status = unsafe {
+        debug_support.register_exception_callback(
+            max_processor_index + 1,
+            Some(breakpoint_callback),
+            ExceptionType::BREAKPOINT,
+        )
+    }
+    .map(|_| ())
+    .unwrap_err()
+    .status();
+    assert_eq!(status, Status::INVALID_PARAMETER);
+
+    // Registration is refused on a protocol of a foreign architecture, before
+    // reaching the firmware. A copy of the protocol, whose first field is its
+    // architecture, mimics one.
+    let mut foreign = unsafe { core::ptr::read(debug_support) };
+    unsafe {
+        *(&mut foreign as *mut DebugSupport as *mut ProcessorArch) = ProcessorArch::ITANIUM;
+    }
+    assert_eq!(foreign.arch(), ProcessorArch::ITANIUM);
+    let status = unsafe {
+        foreign.register_exception_callback(0, Some(breakpoint_callback), ExceptionType::BREAKPOINT)
+    }
+    .map(|_| ())
+    .unwrap_err()
+    .status();
+    assert_eq!(status, Status::UNSUPPORTED);
+
+    unsafe {
+        debug_support
+            .register_exception_callback(0, Some(breakpoint_callback), ExceptionType::BREAKPOINT)
+            .expect_success("Failed to register exception callback");
+        #[cfg(target_arch = "x86_64")]
+        {
+            asm!("int3" :::: "volatile");
+            let rip = BREAKPOINT_IP.load(Ordering::SeqCst);
+            info!("Breakpoint hit at {:#x}", rip);
+            assert_ne!(rip, 0, "Breakpoint callback was not called");
+        }
+        debug_support
+            .register_exception_callback(0, None, ExceptionType::BREAKPOINT)
+            .expect_success("Failed to unregister exception callback");
+    }
 }
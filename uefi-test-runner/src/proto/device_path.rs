@@ -0,0 +1,138 @@
+use alloc::vec::Vec;
+use core::convert::TryFrom;
+use uefi::data_types::CString16;
+use uefi::prelude::*;
+use uefi::proto::device_path::{self, DevicePath, DeviceType, PartitionFormat, PartitionSignature};
+use uefi::proto::media::fs::SimpleFileSystem;
+use uefi::table::boot::{BootServices, SearchType};
+
+pub fn test(bt: &BootServices) {
+    info!("Running device path protocol test");
+    path_length();
+    hard_drive_node();
+
+    let fs_handles = bt
+        .locate_handle_buffer(SearchType::from_proto::<SimpleFileSystem>())
+        .expect_success("Failed to locate file system handles");
+    let path_handles = bt
+        .locate_handle_buffer(SearchType::from_proto::<DevicePath>())
+        .expect_success("Failed to locate device path handles");
+    let device_path = |handle| unsafe {
+        &*bt.handle_protocol::<DevicePath>(handle)
+            .expect_success("Failed to open device path protocol")
+            .get()
+    };
+
+    for &fs_handle in fs_handles.iter() {
+        let fs_path = device_path(fs_handle);
+        assert!(fs_path == device_path(fs_handle));
+        assert!(fs_path.starts_with(fs_path));
+
+        // The device which a file system lives on is located under other
+        // devices, such as a PCI root bridge, whose paths are prefixes of its
+        // path.
+        let parents = path_handles
+            .iter()
+            .map(|&handle| device_path(handle))
+            .filter(|&path| path != fs_path && fs_path.starts_with(path));
+        let mut found_parent = false;
+        for parent in parents {
+            assert!(!parent.starts_with(fs_path), "Prefix longer than path");
+            assert!(parent.node_iter().count() < fs_path.node_iter().count());
+            found_parent = true;
+        }
+        assert!(found_parent, "No parent device path found");
+
+        append_file_path(bt, fs_path);
+    }
+}
+
+// Build the path of a file on a file system.
+fn append_file_path(bt: &BootServices, fs_path: &DevicePath) {
+    let file_name = CString16::try_from("\\EFI\\Boot\\BootX64.efi").unwrap();
+    let node = device_path::file_path_node(bt, &file_name)
+        .expect_success("Failed to build a file path node");
+    assert_eq!(node.length(), 4 + 2 * (file_name.to_u16_slice().len() + 1));
+
+    let file_path =
+        device_path::append(bt, fs_path, &node).expect_success("Failed to append to a path");
+    assert!(file_path.starts_with(fs_path));
+    assert_eq!(file_path.num_nodes(), fs_path.num_nodes() + 1);
+    assert_eq!(
+        file_path.byte_len(),
+        fs_path.byte_len().map(|len| len + node.length())
+    );
+
+    let last = file_path.node_iter().last().unwrap();
+    assert_eq!(last.device_type(), DeviceType::MEDIA);
+    assert_eq!(last.sub_type(), device_path::FILE_PATH);
+    assert_eq!(last.data(), node.data());
+}
+
+// Measure paths built in memory.
+fn path_length() {
+    #[rustfmt::skip]
+    let bytes: [u8; 22] = [
+        // PciRoot(0x0)
+        0x02, 0x01, 12, 0, 0xd0, 0x41, 0x03, 0x0a, 0, 0, 0, 0,
+        // Pci(0x1,0x0)
+        0x01, 0x01, 6, 0, 0, 1,
+        // End of the path
+        0x7f, 0xff, 4, 0,
+    ];
+    let path = unsafe { &*(bytes.as_ptr() as *const DevicePath) };
+    assert_eq!(path.num_nodes(), 2);
+    assert_eq!(path.byte_len(), Some(22));
+    let lengths: Vec<usize> = path.node_iter().map(|node| node.length()).collect();
+    assert_eq!(lengths, [12, 6]);
+
+    // A node with a zero length must not be walked over forever
+    let malformed: [u8; 8] = [0x01, 0x01, 0, 0, 0x7f, 0xff, 4, 0];
+    let path = unsafe { &*(malformed.as_ptr() as *const DevicePath) };
+    assert_eq!(path.num_nodes(), 0);
+    assert_eq!(path.byte_len(), None);
+
+    // Neither must a path whose end node is missing
+    let endless = [[0x01u8, 0x01, 4, 0]; 1100];
+    let path = unsafe { &*(endless.as_ptr() as *const DevicePath) };
+    assert_eq!(path.byte_len(), None);
+}
+
+// Parse a GPT partition node built in memory.
+fn hard_drive_node() {
+    #[rustfmt::skip]
+    let bytes: [u8; 46] = [
+        // HD(2,GPT,0FC63DAF-8483-4772-8E79-3D69D8477DE4,0x800,0x100000)
+        0x04, 0x01, 42, 0,
+        2, 0, 0, 0,
+        0x00, 0x08, 0, 0, 0, 0, 0, 0,
+        0x00, 0x00, 0x10, 0, 0, 0, 0, 0,
+        0xaf, 0x3d, 0xc6, 0x0f, 0x83, 0x84, 0x72, 0x47,
+        0x8e, 0x79, 0x3d, 0x69, 0xd8, 0x47, 0x7d, 0xe4,
+        0x02, 0x02,
+        // End of the path
+        0x7f, 0xff, 4, 0,
+    ];
+    let path = unsafe { &*(bytes.as_ptr() as *const DevicePath) };
+    let node = path.node_iter().next().unwrap();
+    let hd = node
+        .as_hard_drive()
+        .expect("Failed to parse a hard drive node");
+    assert_eq!(hd.partition_number(), 2);
+    assert_eq!(hd.partition_start(), 0x800);
+    assert_eq!(hd.partition_size(), 0x10_0000);
+    assert_eq!(hd.partition_format(), PartitionFormat::GPT);
+    let guid = uefi::Guid::from_values(
+        0x0fc6_3daf,
+        0x8483,
+        0x4772,
+        0x8e79,
+        [0x3d, 0x69, 0xd8, 0x47, 0x7d, 0xe4],
+    );
+    assert_eq!(hd.signature(), PartitionSignature::Gpt(guid));
+
+    // Other nodes are not hard drive nodes
+    let pci: [u8; 10] = [0x01, 0x01, 6, 0, 0, 1, 0x7f, 0xff, 4, 0];
+    let path = unsafe { &*(pci.as_ptr() as *const DevicePath) };
+    assert!(path.node_iter().next().unwrap().as_hard_drive().is_none());
+}
@@ -0,0 +1,173 @@
+use uefi::guids;
+use uefi::prelude::*;
+use uefi::proto::media::file::{
+    Directory, File, FileAttribute, FileInfo, FileMode, FileSystemInfo, FileSystemVolumeLabel,
+    FileType,
+};
+use uefi::proto::media::fs::SimpleFileSystem;
+use uefi::table::boot::{BootServices, SearchType};
+use uefi::Identify;
+
+pub fn test(bt: &BootServices) {
+    info!("Testing file system protocols");
+    info_guids();
+    find_file_systems(bt);
+    if let Ok(sfs) = bt.locate_protocol::<SimpleFileSystem>() {
+        let sfs = sfs.expect("Warnings encountered while opening file system protocol");
+        let sfs = unsafe { &mut *sfs.get() };
+
+        let mut root = sfs.open_volume().expect_success("Failed to open volume");
+
+        file_times(&mut root);
+        read_whole_file(&mut root);
+        open_flags(&mut root);
+        open_read_only(&mut root);
+    } else {
+        warn!("No file system found");
+    }
+}
+
+// The GUIDs of the file information types must match the specification.
+fn info_guids() {
+    assert_eq!(
+        format!("{}", guids::FILE_INFO_GUID),
+        "09576e92-6d3f-11d2-8e39-00a0c969723b"
+    );
+    assert_eq!(guids::FILE_INFO_GUID, FileInfo::GUID);
+    assert_eq!(guids::FILE_SYSTEM_INFO_GUID, FileSystemInfo::GUID);
+    assert_eq!(
+        guids::FILE_SYSTEM_VOLUME_LABEL_GUID,
+        FileSystemVolumeLabel::GUID
+    );
+}
+
+// Check that the timestamps of the test runner executable make sense.
+fn file_times(root: &mut impl File) {
+    let mut file = root
+        .open(
+            "EFI\\Boot\\BootX64.efi",
+            FileMode::Read,
+            FileAttribute::empty(),
+        )
+        .expect_success("Failed to open the test runner executable");
+    let info = file
+        .get_boxed_info::<FileInfo>()
+        .expect_success("Failed to query file info");
+
+    let modified = info
+        .modified()
+        .expect("The file system did not record a modification time");
+    assert!(
+        modified.year() >= 2020 && modified.year() <= 2107,
+        "Implausible modification year: {}",
+        modified.year()
+    );
+}
+
+// Read the test runner executable at once.
+fn read_whole_file(root: &mut impl File) {
+    let file = root
+        .open(
+            "EFI\\Boot\\BootX64.efi",
+            FileMode::Read,
+            FileAttribute::empty(),
+        )
+        .expect_success("Failed to open the test runner executable");
+    let mut file = match file.into_type().expect_success("Failed to query file type") {
+        FileType::Regular(file) => file,
+        FileType::Dir(_) => panic!("The test runner executable is a directory"),
+    };
+
+    let size = file
+        .get_boxed_info::<FileInfo>()
+        .expect_success("Failed to query file info")
+        .file_size();
+    // Reading starts from the beginning, even after a partial read
+    file.read(&mut [0; 16])
+        .expect_success("Failed to read the test runner executable");
+    let contents = file
+        .read_to_vec()
+        .expect_success("Failed to read the whole test runner executable");
+    assert_eq!(contents.len() as u64, size);
+    assert_eq!(&contents[..2], b"MZ", "Not a PE executable");
+}
+
+// Illegal combinations of open mode and attributes are rejected up front.
+fn open_flags(root: &mut impl File) {
+    assert!(FileMode::Read.accepts(FileAttribute::empty()));
+    assert!(FileMode::CreateReadWrite.accepts(FileAttribute::DIRECTORY | FileAttribute::HIDDEN));
+    assert!(!FileMode::Read.accepts(FileAttribute::DIRECTORY));
+    assert!(!FileMode::ReadWrite.accepts(FileAttribute::READ_ONLY));
+    assert!(!FileMode::CreateReadWrite.accepts(FileAttribute::RESERVED));
+
+    let path = "EFI\\Boot\\BootX64.efi";
+    root.open(path, FileMode::Read, FileAttribute::empty())
+        .expect_success("Failed to open the test runner executable");
+    for &(mode, attributes) in &[
+        (FileMode::Read, FileAttribute::DIRECTORY),
+        (FileMode::ReadWrite, FileAttribute::ARCHIVE),
+        (FileMode::CreateReadWrite, FileAttribute::RESERVED),
+    ] {
+        let status = root
+            .open(path, mode, attributes)
+            .map(|_| ())
+            .map_err(|err| err.status());
+        assert_eq!(
+            status,
+            Err(Status::INVALID_PARAMETER),
+            "{:?} with {:?} was not rejected",
+            mode,
+            attributes
+        );
+    }
+}
+
+// Files opened for reading only cannot be written to.
+fn open_read_only(root: &mut Directory) {
+    let file = root
+        .open_read("EFI\\Boot\\BootX64.efi")
+        .expect_success("Failed to open the test runner executable");
+    let mut file = match file.into_type().expect_success("Failed to query file type") {
+        FileType::Regular(file) => file,
+        FileType::Dir(_) => panic!("The test runner executable is a directory"),
+    };
+
+    let status = file.write(b"MZ").map(|_| ()).map_err(|err| err.status());
+    assert!(
+        status == Err(Status::ACCESS_DENIED) || status == Err(Status::WRITE_PROTECTED),
+        "Writing to a read-only file was not rejected: {:?}",
+        status
+    );
+}
+
+// Enumerate the file systems using a firmware-allocated handle buffer.
+fn find_file_systems(bt: &BootServices) {
+    let handles = bt
+        .locate_handle_buffer(SearchType::from_proto::<SimpleFileSystem>())
+        .expect_success("Failed to locate file system handles");
+
+    assert!(
+        !handles.is_empty(),
+        "The boot volume should have a file system"
+    );
+    for &handle in handles.iter() {
+        bt.handle_protocol::<SimpleFileSystem>(handle)
+            .expect_success("Located handle does not support the file system protocol");
+    }
+
+    // `handle_protocol` opened the protocol on our behalf, so someone must be
+    // holding it now.
+    let agents = bt
+        .open_protocol_information(handles[0], &SimpleFileSystem::GUID)
+        .expect_success("Failed to query protocol openers");
+    assert!(
+        !agents.is_empty(),
+        "Nobody has the file system protocol open"
+    );
+    for agent in &agents {
+        info!(
+            "File system protocol opened with {:?}, {} time(s)",
+            agent.attributes, agent.open_count
+        );
+    }
+}
@@ -0,0 +1,29 @@
+use uefi::prelude::*;
+use uefi::proto::misc::rng::{BufferedRng, Rng, RngCore};
+
+pub fn test(bt: &BootServices) {
+    info!("Running miscellaneous protocol tests");
+    if let Ok(rng) = bt.locate_protocol::<Rng>() {
+        let rng = rng.expect("Warnings encountered while opening RNG protocol");
+        let rng = unsafe { &mut *rng.get() };
+
+        fill_random(rng);
+    } else {
+        warn!("Random number generator protocol is not supported");
+    }
+}
+
+fn fill_random(rng: &mut Rng) {
+    let mut rng = BufferedRng::new(rng);
+
+    // More than a buffer's worth, to exercise refilling
+    let mut bytes = [0u8; 100];
+    rng.fill_bytes(&mut bytes);
+    assert!(
+        bytes.iter().any(|&byte| byte != bytes[0]),
+        "Random bytes are all identical"
+    );
+
+    let values = [rng.next_u64(), rng.next_u64()];
+    assert_ne!(values[0], values[1], "Random values are identical");
+}
@@ -1,17 +1,24 @@
 use uefi::prelude::*;
 
 use uefi::proto;
+use uefi::Identify;
 
-pub fn test(st: &SystemTable<Boot>) {
+pub fn test(image: Handle, st: &SystemTable<Boot>) {
     info!("Testing various protocols");
 
     let bt = st.boot_services();
 
     find_protocol(bt);
+    open_if(bt);
+    first_protocol(image, bt);
 
     console::test(st);
     debug::test(bt);
+    device_path::test(bt);
+    media::test(bt);
+    misc::test(bt);
     pi::test(bt);
+    string::test(bt);
 }
 
 fn find_protocol(bt: &BootServices) {
@@ -27,6 +34,57 @@ fn find_protocol(bt: &BootServices) {
     );
 }
 
+fn open_if(bt: &BootServices) {
+    use proto::console::gop::GraphicsOutput;
+    use uefi::table::boot::SearchType;
+
+    let handles = bt
+        .locate_handle_buffer(SearchType::AllHandles)
+        .expect_success("Failed to retrieve list of handles");
+
+    // Try to open the GOP on every handle, most of which do not support it
+    let mut gop_handles = 0;
+    for &handle in handles.iter() {
+        let gop = proto::open_if::<GraphicsOutput>(bt, handle)
+            .expect_success("Failed to check for GOP support");
+        if gop.is_some() {
+            gop_handles += 1;
+        }
+    }
+
+    let expected = bt
+        .find_handles::<GraphicsOutput>()
+        .map_or(0, |handles| handles.log().len());
+    assert_eq!(gop_handles, expected, "GOP handles were not all found");
+}
+
+fn first_protocol(image: Handle, bt: &BootServices) {
+    use proto::media::fs::SimpleFileSystem;
+    use uefi::table::boot::SearchType;
+
+    let fs = bt
+        .first_protocol::<SimpleFileSystem>(image)
+        .expect_success("Failed to open the first file system");
+    let handles = bt
+        .locate_handle_buffer(SearchType::from_proto::<SimpleFileSystem>())
+        .expect_success("Failed to retrieve list of handles");
+    let first = *handles.first().unwrap();
+    assert_eq!(fs.handle(), first, "Not opened on the first handle");
+
+    // The handle can be queried further, and the firmware knows who opened it
+    let agents = bt
+        .open_protocol_information(fs.handle(), &SimpleFileSystem::GUID)
+        .expect_success("Failed to list protocol users");
+    assert!(agents.iter().any(|agent| agent.agent_handle == image));
+    let fs = unsafe { &mut *fs.get() };
+    fs.open_volume()
+        .expect_success("Failed to open the root directory");
+}
+
 mod console;
 mod debug;
+mod device_path;
+mod media;
+mod misc;
 mod pi;
+mod string;
@@ -0,0 +1,40 @@
+use uefi::proto::string::unicode_collation::UnicodeCollation;
+use uefi::table::boot::BootServices;
+use uefi::CStr16;
+
+pub fn test(bt: &BootServices) {
+    info!("Running Unicode collation protocol test");
+    if let Ok(collation) = bt.locate_protocol::<UnicodeCollation>() {
+        let collation = collation.expect("Warnings encountered while opening collation protocol");
+        let collation = unsafe { &*collation.get() };
+
+        let mut upper_buf = [0; 16];
+        let mut lower_buf = [0; 16];
+        let mut other_buf = [0; 16];
+        let upper = to_cstr16("BOOTX64.EFI", &mut upper_buf);
+        let lower = to_cstr16("bootx64.efi", &mut lower_buf);
+        let other = to_cstr16("bootia32.efi", &mut other_buf);
+
+        assert!(
+            upper.eq_collated(lower, collation),
+            "File names differing by case should match"
+        );
+        assert!(
+            !upper.eq_collated(other, collation),
+            "Different file names should not match"
+        );
+        assert!(upper != lower, "Plain comparison should be case-sensitive");
+    } else {
+        warn!("Unicode collation protocol is not supported");
+    }
+}
+
+// Converts an ASCII string to a UCS-2 string in the provided storage
+fn to_cstr16<'buf>(s: &str, buf: &'buf mut [u16]) -> &'buf CStr16 {
+    assert!(s.len() < buf.len(), "String is too long for the buffer");
+    for (code, byte) in buf.iter_mut().zip(s.bytes()) {
+        *code = u16::from(byte);
+    }
+    buf[s.len()] = 0;
+    CStr16::from_u16_with_nul(&buf[..=s.len()]).unwrap_or_else(|_| panic!("Invalid UCS-2 string"))
+}
@@ -0,0 +1,68 @@
+use core::convert::TryFrom;
+use uefi::{ResultExt, Status, StatusCategory};
+
+pub fn test() {
+    info!("Testing status codes");
+    status_category();
+    status_lists();
+    int_conversions();
+    failure_messages();
+}
+
+fn status_category() {
+    assert_eq!(
+        Status::TFTP_ERROR.category(),
+        StatusCategory::Network,
+        "TFTP errors should be network errors"
+    );
+    assert_eq!(
+        Status::HTTP_ERROR.category(),
+        StatusCategory::Network,
+        "HTTP errors should be network errors"
+    );
+    assert_eq!(
+        Status::VOLUME_CORRUPTED.category(),
+        StatusCategory::Media,
+        "Corrupted volumes should be media errors"
+    );
+}
+
+fn status_lists() {
+    assert_eq!(Status::all_warnings().count(), 7);
+    assert!(Status::all_warnings().all(Status::is_warning));
+    assert_eq!(Status::all_errors().count(), 33);
+    assert!(Status::all_errors().all(Status::is_error));
+
+    // Each status code should only be listed once
+    for (i, status) in Status::all_errors().enumerate() {
+        assert!(
+            Status::all_errors()
+                .skip(i + 1)
+                .all(|other| other != status),
+            "{:?} is listed twice",
+            status
+        );
+    }
+}
+
+fn int_conversions() {
+    fn count_to_u32(count: usize) -> uefi::Result<u32> {
+        Ok(u32::try_from(count)?.into())
+    }
+
+    assert_eq!(count_to_u32(42).map(|c| c.unwrap()).ok(), Some(42));
+    let status = count_to_u32(usize::MAX).map_err(|err| err.status());
+    assert_eq!(status.err(), Some(Status::BAD_BUFFER_SIZE));
+}
+
+fn failure_messages() {
+    // Panics abort the tests, so check the text which `expect_success` and
+    // `or_reset` report alongside their message instead.
+    let result: uefi::Result = Err(Status::NOT_FOUND.into());
+    let error = result.expect_error("Failure was not reported");
+    assert!(format!("{:?}", error).contains("NOT_FOUND"));
+    assert_eq!(
+        format!("{:?}", Status::WARN_DELETE_FAILURE),
+        "WARN_DELETE_FAILURE"
+    );
+}
@@ -0,0 +1,147 @@
+use core::convert::TryFrom;
+use uefi::data_types::CString16;
+use uefi::guids::GLOBAL_VARIABLE_GUID;
+use uefi::prelude::*;
+use uefi::table::runtime::{
+    CapsuleFlags, CapsuleHeader, Daylight, RuntimeServices, Time, TimeError, VariableAttributes,
+};
+use uefi::{Guid, Status};
+
+pub fn test(rt: &RuntimeServices) {
+    info!("Testing runtime service types");
+    time_ordering();
+    time_display();
+    time_builder();
+    capsule_capabilities(rt);
+    unsigned_authenticated_write(rt);
+    global_variables(rt);
+}
+
+fn time(year: u16, month: u8, day: u8, hour: u8, minute: u8, time_zone: i16) -> Time {
+    Time::new(
+        year,
+        month,
+        day,
+        hour,
+        minute,
+        0,
+        0,
+        time_zone,
+        Daylight::empty(),
+    )
+}
+
+fn time_ordering() {
+    // Local times are compared field by field, across day and year boundaries
+    let before = time(2019, 12, 31, 23, 59, 2047);
+    let after = time(2020, 1, 1, 0, 0, 2047);
+    assert!(before < after);
+    assert!(after > before);
+    assert_eq!(before, before);
+
+    // Leap days are accounted for
+    assert!(time(2020, 2, 29, 12, 0, 2047) < time(2020, 3, 1, 0, 0, 2047));
+
+    // Times with a time zone are normalized to UTC (UTC = local + TimeZone),
+    // so 23:00 at UTC-2 comes after 00:30 of the following day at UTC.
+    let evening = time(2020, 1, 1, 23, 0, 120);
+    let night = time(2020, 1, 2, 0, 30, 0);
+    assert!(evening > night);
+
+    // The same instant in two different time zones
+    assert_eq!(time(2020, 1, 1, 22, 0, 120), time(2020, 1, 2, 0, 0, 0));
+}
+
+fn time_display() {
+    let time = Time::new(2024, 1, 2, 3, 4, 5, 0, 2047, Daylight::empty());
+    assert_eq!(format!("{}", time), "2024-01-02 03:04:05");
+}
+
+fn time_builder() {
+    let date = |year, month, day| Time::builder().date(year, month, day).build();
+
+    // Leap days only exist in leap years
+    let leap_day = date(2020, 2, 29).expect("Leap day rejected");
+    assert_eq!((leap_day.month(), leap_day.day()), (2, 29));
+    assert_eq!(date(2021, 2, 29).err(), Some(TimeError::Day));
+    assert_eq!(date(1900, 2, 29).err(), Some(TimeError::Day));
+    assert!(date(2000, 2, 29).is_ok());
+    assert_eq!(date(2021, 4, 31).err(), Some(TimeError::Day));
+    assert_eq!(date(2021, 13, 1).err(), Some(TimeError::Month));
+    assert_eq!(date(1899, 12, 31).err(), Some(TimeError::Year));
+
+    let builder = Time::builder().date(2021, 6, 15);
+    assert_eq!(builder.time(24, 0, 0).build().err(), Some(TimeError::Hour));
+    assert_eq!(
+        builder.nanosecond(1_000_000_000).build().err(),
+        Some(TimeError::Nanosecond)
+    );
+
+    // The builder starts in local time, and only takes concrete offsets
+    assert_eq!(builder.build().unwrap().time_zone(), None);
+    let utc = builder.time_zone(-60).build().unwrap();
+    assert_eq!(utc.time_zone(), Some(-60));
+    assert_eq!(
+        builder.time_zone(2047).build().err(),
+        Some(TimeError::TimeZone)
+    );
+}
+
+fn capsule_capabilities(rt: &RuntimeServices) {
+    // A capsule of a made-up type, which no firmware should accept
+    let guid = Guid::from_values(0x1234_5678, 0x9abc, 0xdef0, 0x1234, [1, 2, 3, 4, 5, 6]);
+    let header = CapsuleHeader::new(
+        guid,
+        CapsuleFlags::PERSIST_ACROSS_RESET,
+        core::mem::size_of::<CapsuleHeader>() as u32,
+    );
+    match rt.query_capsule_capabilities(&[&header]) {
+        Ok(caps) => {
+            let (max_size, reset_type) = caps.unwrap();
+            info!(
+                "Dummy capsule accepted (max size {}, {:?} reset)",
+                max_size, reset_type
+            );
+        }
+        Err(err) if err.status() == Status::UNSUPPORTED => {
+            info!("Dummy capsule is not supported by the firmware");
+        }
+        Err(err) => panic!("Failed to query capsule capabilities: {:?}", err.status()),
+    }
+}
+
+fn unsigned_authenticated_write(rt: &RuntimeServices) {
+    let name = CString16::try_from("UefiRsAuthTest").unwrap();
+    let vendor = Guid::from_values(0x8f1d_2b9e, 0x5c3a, 0x4e6f, 0x9d21, [7, 8, 9, 10, 11, 12]);
+    let attributes = VariableAttributes::NON_VOLATILE | VariableAttributes::BOOTSERVICE_ACCESS;
+
+    // A value without any authentication descriptor in front of it
+    match rt.set_variable_authenticated(&name, &vendor, attributes, b"unsigned") {
+        Ok(_) => panic!("Unsigned authenticated write was accepted"),
+        Err(err) if err.status() == Status::SECURITY_VIOLATION => {}
+        Err(err) => warn!(
+            "Firmware does not support authenticated variables: {:?}",
+            err.status()
+        ),
+    }
+}
+
+fn global_variables(rt: &RuntimeServices) {
+    let boot_order = CString16::try_from("BootOrder").unwrap();
+    let mut found = false;
+    for name in rt.variables_with_vendor(&GLOBAL_VARIABLE_GUID) {
+        let name = name.expect_success("Failed to enumerate global variables");
+        found |= name == boot_order;
+    }
+    assert!(found, "BootOrder is not a global variable");
+
+    // Every variable of the full enumeration is found under its vendor
+    let (name, vendor) = rt
+        .variable_names()
+        .next()
+        .expect("No variables were found")
+        .expect_success("Failed to enumerate variables");
+    assert!(rt
+        .variables_with_vendor(&vendor)
+        .any(|other| other.map_or(false, |other| other.log() == name)));
+}